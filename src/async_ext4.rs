@@ -0,0 +1,989 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Async API on top of the synchronous filesystem code.
+//!
+//! All of the filesystem parsing is done by the regular synchronous
+//! code, using an [`Ext4`] whose reader can only return data that has
+//! already been read. When the synchronous code needs data that is not
+//! yet available, the reader fails with [`PendingRead`]. The async
+//! code then reads the requested data with the [`AsyncExt4Read`]
+//! reader, and runs the operation again. Data that has been read is
+//! kept until the operation completes, so each retry gets further
+//! than the previous one. Once the total amount of kept data is over a
+//! limit, data that has already been used is dropped. Sequential reads
+//! are made larger, up to a limit, to reduce the number of retries.
+//!
+//! Values returned to the caller never contain an `Ext4` with this
+//! reader. For example, [`AsyncExt4::read_dir`] returns
+//! [`AsyncDirEntry`] rather than [`DirEntry`].
+//!
+//! File data does not go through this retry mechanism. Only the
+//! mapping from file blocks to filesystem blocks is done with the
+//! synchronous code; the data itself is read directly into the
+//! caller's buffer.
+
+use crate::block_index::{FileBlockIndex, FsBlockIndex};
+use crate::dir_entry::DirEntryNameBuf;
+use crate::error::{BoxedError, CorruptKind, Ext4Error};
+use crate::file::check_is_regular_file;
+use crate::file_attributes::FileAttributes;
+use crate::file_type::FileType;
+use crate::inline_data::read_inline_data;
+use crate::inode::{Inode, InodeIndex};
+use crate::iters::file_blocks::FileBlocks;
+use crate::reader::{AsyncExt4Read, Ext4Read};
+use crate::resolve::FollowSymlinks;
+use crate::superblock::Superblock;
+use crate::sync::{Lock, Shared};
+use crate::xattr;
+use crate::{
+    DirEntry, DirEntryName, Ext4, Ext4Options, Metadata, Path, PathBuf,
+};
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt::{self, Debug, Display, Formatter};
+
+/// Maximum number of bytes of prefetched data held during an
+/// operation. Once this is exceeded, data that has already been
+/// consumed is dropped.
+const MAX_PREFETCHED_BYTES: usize = 1024 * 1024;
+
+/// Maximum number of bytes read at once by the async reader, unless
+/// the synchronous code requested more than that.
+const MAX_PREFETCH_LEN: usize = 256 * 1024;
+
+/// Data that has been read by the async reader for the current
+/// operation.
+struct PrefetchState {
+    /// Prefetched data, keyed by absolute byte offset. The flag is set
+    /// once any of the data has been returned by [`PrefetchReader`].
+    ranges: BTreeMap<u64, (Vec<u8>, bool)>,
+
+    /// Total length of the data in `ranges`.
+    num_bytes: usize,
+
+    /// Byte ranges that were dropped from `ranges`, as a map from start
+    /// to end.
+    dropped: BTreeMap<u64, u64>,
+
+    /// Set if dropped data was needed again. Data is not dropped for
+    /// the rest of the operation, so that the operation is guaranteed
+    /// to make progress.
+    keep_all: bool,
+}
+
+impl PrefetchState {
+    fn new() -> Self {
+        Self {
+            ranges: BTreeMap::new(),
+            num_bytes: 0,
+            dropped: BTreeMap::new(),
+            keep_all: false,
+        }
+    }
+
+    /// Copy prefetched data at `start_byte` into `dst`. Returns false if
+    /// the data is not fully contained within a prefetched range.
+    fn read(&mut self, start_byte: u64, dst: &mut [u8]) -> bool {
+        // Find the closest range that starts at or before `start_byte`,
+        // and check if it contains all of the requested data.
+        let Some((range_start, (data, consumed))) =
+            self.ranges.range_mut(..=start_byte).next_back()
+        else {
+            return false;
+        };
+
+        // OK to unwrap: `range_start` is less than or equal to
+        // `start_byte`.
+        let offset = start_byte.checked_sub(*range_start).unwrap();
+        let src = usize::try_from(offset).ok().and_then(|offset| {
+            data.get(offset..offset.checked_add(dst.len())?)
+        });
+        if let Some(src) = src {
+            dst.copy_from_slice(src);
+            *consumed = true;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Add data read at `start_byte`.
+    ///
+    /// If that puts the total over [`MAX_PREFETCHED_BYTES`], data that
+    /// has already been consumed is dropped; it is either in the block
+    /// cache, or not needed again. If dropped data turns out to be
+    /// needed again, dropping is disabled.
+    fn insert(&mut self, start_byte: u64, data: Vec<u8>) {
+        if let Some((_, end)) = self.dropped.range(..=start_byte).next_back() {
+            if *end > start_byte {
+                self.keep_all = true;
+            }
+        }
+
+        self.num_bytes = self.num_bytes.saturating_add(data.len());
+        if !self.keep_all && self.num_bytes > MAX_PREFETCHED_BYTES {
+            let dropped = &mut self.dropped;
+            let num_bytes = &mut self.num_bytes;
+            self.ranges.retain(|start, (data, consumed)| {
+                if *consumed {
+                    let len = u64::try_from(data.len()).unwrap_or(u64::MAX);
+                    dropped.insert(*start, start.saturating_add(len));
+                    *num_bytes = num_bytes.saturating_sub(data.len());
+                }
+                !*consumed
+            });
+        }
+
+        if let Some((old, _)) = self.ranges.insert(start_byte, (data, false)) {
+            self.num_bytes = self.num_bytes.saturating_sub(old.len());
+        }
+    }
+
+    /// Drop all data, at the end of an operation.
+    fn clear(&mut self) {
+        *self = Self::new();
+    }
+}
+
+/// Data that has been read by the async reader, shared with
+/// [`PrefetchReader`].
+type Prefetched = Shared<Lock<PrefetchState>>;
+
+/// Error returned by [`PrefetchReader`] when the requested data has not
+/// been read yet.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct PendingRead {
+    start_byte: u64,
+    len: usize,
+}
+
+impl Display for PendingRead {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "read of {} bytes at offset {} is pending",
+            self.len, self.start_byte
+        )
+    }
+}
+
+impl Error for PendingRead {}
+
+/// Synchronous reader that only returns prefetched data.
+struct PrefetchReader(Prefetched);
+
+impl Ext4Read for PrefetchReader {
+    fn read(
+        &mut self,
+        start_byte: u64,
+        dst: &mut [u8],
+    ) -> Result<(), BoxedError> {
+        if self.0.lock().read(start_byte, dst) {
+            Ok(())
+        } else {
+            Err(Box::new(PendingRead {
+                start_byte,
+                len: dst.len(),
+            }))
+        }
+    }
+}
+
+/// Use `reader` to read `len` bytes at `start_byte`, and add the data
+/// to `prefetched`.
+async fn prefetch<R: AsyncExt4Read>(
+    reader: &mut R,
+    prefetched: &Prefetched,
+    start_byte: u64,
+    len: usize,
+) -> Result<(), Ext4Error> {
+    let mut data = vec![0; len];
+    reader
+        .read(start_byte, &mut data)
+        .await
+        .map_err(Ext4Error::Io)?;
+    prefetched.lock().insert(start_byte, data);
+    Ok(())
+}
+
+/// Run `op` until it succeeds or fails with an error other than
+/// [`PendingRead`]. Each time `op` fails with `PendingRead`, the
+/// requested data is read with `reader` and `op` is run again.
+///
+/// Each retry runs `op` from the start, so to limit the number of
+/// retries, more data than requested is read when the requests are
+/// sequential. The amount doubles with each sequential request, up to
+/// [`MAX_PREFETCH_LEN`], and is limited to the first `fs_size` bytes
+/// if that is known.
+///
+/// All prefetched data is dropped once `op` is done.
+async fn run_with_prefetch<R, T, F>(
+    reader: &mut R,
+    prefetched: &Prefetched,
+    fs_size: Option<u64>,
+    mut op: F,
+) -> Result<T, Ext4Error>
+where
+    R: AsyncExt4Read,
+    F: FnMut() -> Result<T, Ext4Error>,
+{
+    // Length and end of the previous read.
+    let mut prev_len: usize = 0;
+    let mut prev_end: Option<u64> = None;
+
+    let result = loop {
+        let err = match op() {
+            Ok(val) => break Ok(val),
+            Err(err) => err,
+        };
+
+        let Some(pending) = err
+            .as_io()
+            .and_then(|err| err.downcast_ref::<PendingRead>())
+            .copied()
+        else {
+            break Err(err);
+        };
+
+        let mut len = pending.len;
+        if fs_size.is_some() && prev_end == Some(pending.start_byte) {
+            len = len.max(prev_len.saturating_mul(2).min(MAX_PREFETCH_LEN));
+        }
+        if let Some(fs_size) = fs_size {
+            let available = fs_size.saturating_sub(pending.start_byte);
+            len = len.min(usize::try_from(available).unwrap_or(usize::MAX));
+            len = len.max(pending.len);
+        }
+
+        let mut res =
+            prefetch(reader, prefetched, pending.start_byte, len).await;
+        if res.is_err() && len > pending.len {
+            // Reading more than requested may fail even though the
+            // requested read would not, for example if the underlying
+            // storage is smaller than the filesystem.
+            len = pending.len;
+            res = prefetch(reader, prefetched, pending.start_byte, len).await;
+        }
+        if let Err(err) = res {
+            break Err(err);
+        }
+
+        prev_len = len;
+        prev_end = u64::try_from(len)
+            .ok()
+            .and_then(|len| pending.start_byte.checked_add(len));
+    };
+
+    // Any data needed by later operations is either in the block cache,
+    // or will be read again.
+    prefetched.lock().clear();
+
+    result
+}
+
+/// Read-only async access to an [ext4] filesystem.
+///
+/// This provides a subset of the [`Ext4`] API, reading data with an
+/// [`AsyncExt4Read`] reader instead of an [`Ext4Read`] reader. The
+/// filesystem is parsed by the same code as `Ext4`.
+///
+/// Methods take `&mut self`, so only one operation runs at a time.
+///
+/// [ext4]: https://en.wikipedia.org/wiki/Ext4
+///
+/// # Example
+///
+/// ```
+/// use ext4_view::{AsyncExt4, Ext4Error};
+///
+/// async fn async_example(fs_data: Vec<u8>) -> Result<(), Ext4Error> {
+///     let mut fs = AsyncExt4::load(fs_data).await?;
+///
+///     let file_data: Vec<u8> = fs.read("/some/file").await?;
+///
+///     for entry in fs.read_dir("/some/dir").await? {
+///         println!("{}", entry.path().display());
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+pub struct AsyncExt4<R> {
+    fs: Ext4,
+    reader: R,
+    prefetched: Prefetched,
+}
+
+impl<R: AsyncExt4Read> AsyncExt4<R> {
+    /// Load an `AsyncExt4` instance from the given `reader`.
+    ///
+    /// See [`Ext4::load`].
+    pub async fn load(reader: R) -> Result<Self, Ext4Error> {
+        Self::load_with_options(reader, Ext4Options::default()).await
+    }
+
+    /// Load an `AsyncExt4` instance from the given `reader`, using
+    /// `options` to control how the filesystem is loaded.
+    ///
+    /// See [`Ext4::load_with_options`].
+    pub async fn load_with_options(
+        mut reader: R,
+        options: Ext4Options,
+    ) -> Result<Self, Ext4Error> {
+        let prefetched = Shared::new(Lock::new(PrefetchState::new()));
+
+        // The superblock is read directly from the reader rather than
+        // through the block cache. Read it up front, so that loading
+        // doesn't need to be retried for it.
+        prefetch(
+            &mut reader,
+            &prefetched,
+            1024,
+            Superblock::SIZE_IN_BYTES_ON_DISK,
+        )
+        .await?;
+
+        let fs = run_with_prefetch(&mut reader, &prefetched, None, || {
+            Ext4::load_with_options(
+                Box::new(PrefetchReader(prefetched.clone())),
+                options.clone(),
+            )
+        })
+        .await?;
+
+        Ok(Self {
+            fs,
+            reader,
+            prefetched,
+        })
+    }
+
+    /// Run a synchronous operation on the filesystem, reading data
+    /// with the async reader as needed.
+    async fn run<T, F>(&mut self, mut op: F) -> Result<T, Ext4Error>
+    where
+        F: FnMut(&Ext4) -> Result<T, Ext4Error>,
+    {
+        let fs = &self.fs;
+        let fs_size =
+            fs.0.superblock
+                .blocks_count
+                .checked_mul(fs.0.superblock.block_size.to_u64());
+        run_with_prefetch(&mut self.reader, &self.prefetched, fs_size, || {
+            op(fs)
+        })
+        .await
+    }
+
+    /// Open the file at `path`.
+    ///
+    /// See [`Ext4::open`].
+    pub async fn open<'p, P>(&mut self, path: P) -> Result<AsyncFile, Ext4Error>
+    where
+        P: TryInto<Path<'p>>,
+    {
+        let path = path.try_into().map_err(|_| Ext4Error::MalformedPath)?;
+
+        let inode = self
+            .run(|fs| {
+                let inode = fs.path_to_inode(path, FollowSymlinks::All)?;
+                check_is_regular_file(&inode)?;
+                Ok(inode)
+            })
+            .await?;
+
+        AsyncFile::open_inode(self, inode).await
+    }
+
+    /// Read the entire contents of a file as raw bytes.
+    ///
+    /// See [`Ext4::read`].
+    pub async fn read<'p, P>(&mut self, path: P) -> Result<Vec<u8>, Ext4Error>
+    where
+        P: TryInto<Path<'p>>,
+    {
+        let mut file = self.open(path).await?;
+
+        let file_size_in_bytes = usize::try_from(file.metadata().len())
+            .map_err(|_| Ext4Error::FileTooLarge)?;
+        let mut dst = vec![0; file_size_in_bytes];
+
+        let mut remaining = dst.as_mut();
+        loop {
+            let bytes_read = file.read_bytes(self, remaining).await?;
+            if bytes_read == 0 {
+                break;
+            }
+            remaining = &mut remaining[bytes_read..];
+        }
+        Ok(dst)
+    }
+
+    /// Get the entries in a directory.
+    ///
+    /// Unlike [`Ext4::read_dir`], this returns all entries at once
+    /// rather than an iterator.
+    ///
+    pub async fn read_dir<'p, P>(
+        &mut self,
+        path: P,
+    ) -> Result<Vec<AsyncDirEntry>, Ext4Error>
+    where
+        P: TryInto<Path<'p>>,
+    {
+        let path = path.try_into().map_err(|_| Ext4Error::MalformedPath)?;
+        self.run(|fs| {
+            fs.read_dir(path)?
+                .map(|entry| entry.map(AsyncDirEntry::from))
+                .collect()
+        })
+        .await
+    }
+
+    /// Check if `path` exists.
+    ///
+    /// See [`Ext4::exists`].
+    pub async fn exists<'p, P>(&mut self, path: P) -> Result<bool, Ext4Error>
+    where
+        P: TryInto<Path<'p>>,
+    {
+        let path = path.try_into().map_err(|_| Ext4Error::MalformedPath)?;
+        self.run(|fs| fs.exists(path)).await
+    }
+
+    /// Get [`Metadata`] for `path`.
+    ///
+    /// See [`Ext4::metadata`].
+    pub async fn metadata<'p, P>(
+        &mut self,
+        path: P,
+    ) -> Result<Metadata, Ext4Error>
+    where
+        P: TryInto<Path<'p>>,
+    {
+        let path = path.try_into().map_err(|_| Ext4Error::MalformedPath)?;
+        self.run(|fs| fs.metadata(path)).await
+    }
+}
+
+impl<R> Debug for AsyncExt4<R> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("AsyncExt4").field(&self.fs).finish()
+    }
+}
+
+/// Directory entry returned by [`AsyncExt4::read_dir`].
+///
+/// This is the async equivalent of [`DirEntry`]. Methods that read from
+/// the filesystem take the `AsyncExt4` the entry came from.
+#[derive(Clone)]
+pub struct AsyncDirEntry {
+    inode: InodeIndex,
+    name: DirEntryNameBuf,
+    path: Shared<PathBuf>,
+    file_type: FileType,
+}
+
+impl AsyncDirEntry {
+    /// Get the directory entry's name.
+    ///
+    /// See [`DirEntry::file_name`].
+    #[must_use]
+    #[inline]
+    pub fn file_name(&self) -> DirEntryName<'_> {
+        self.name.as_dir_entry_name()
+    }
+
+    /// Get the entry's path.
+    ///
+    /// See [`DirEntry::path`].
+    #[must_use]
+    pub fn path(&self) -> PathBuf {
+        self.path.join(self.name.as_bytes())
+    }
+
+    /// Get the inode number that the entry points to.
+    ///
+    /// See [`DirEntry::ino`].
+    #[must_use]
+    pub fn ino(&self) -> u32 {
+        self.inode.get()
+    }
+
+    /// Get the entry's file type.
+    ///
+    /// See [`DirEntry::file_type`].
+    pub fn file_type(&self) -> Result<FileType, Ext4Error> {
+        Ok(self.file_type)
+    }
+
+    /// Get [`Metadata`] for the entry.
+    ///
+    /// See [`DirEntry::metadata`].
+    pub async fn metadata<R: AsyncExt4Read>(
+        &self,
+        fs: &mut AsyncExt4<R>,
+    ) -> Result<Metadata, Ext4Error> {
+        fs.run(|fs| Ok(Inode::read(fs, self.inode)?.metadata)).await
+    }
+
+    /// Get the names of all extended attributes of the entry.
+    ///
+    /// See [`DirEntry::list_xattrs`].
+    pub async fn list_xattrs<R: AsyncExt4Read>(
+        &self,
+        fs: &mut AsyncExt4<R>,
+    ) -> Result<Vec<Vec<u8>>, Ext4Error> {
+        fs.run(|fs| {
+            let inode = Inode::read(fs, self.inode)?;
+            xattr::list_xattrs(fs, &inode)
+        })
+        .await
+    }
+
+    /// Get the value of the extended attribute `name` of the entry.
+    ///
+    /// See [`DirEntry::get_xattr`].
+    pub async fn get_xattr<R: AsyncExt4Read, N: AsRef<[u8]>>(
+        &self,
+        fs: &mut AsyncExt4<R>,
+        name: N,
+    ) -> Result<Option<Vec<u8>>, Ext4Error> {
+        let name = name.as_ref();
+        fs.run(|fs| {
+            let inode = Inode::read(fs, self.inode)?;
+            xattr::get_xattr(fs, &inode, name)
+        })
+        .await
+    }
+}
+
+// Only the parts of the entry that don't refer to the filesystem are
+// kept. The `Ext4` in a `DirEntry` from an `AsyncExt4` uses a reader
+// that can only return prefetched data, so it must not escape.
+impl From<DirEntry> for AsyncDirEntry {
+    fn from(entry: DirEntry) -> Self {
+        Self {
+            inode: entry.inode,
+            name: entry.name,
+            path: entry.path,
+            file_type: entry.file_type,
+        }
+    }
+}
+
+impl Debug for AsyncDirEntry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("AsyncDirEntry").field(&self.path()).finish()
+    }
+}
+
+/// An open file within an [`AsyncExt4`] filesystem.
+///
+/// Data is read with [`AsyncFile::read_bytes`], passing in the
+/// filesystem the file was opened from.
+pub struct AsyncFile {
+    inode: Inode,
+
+    /// Current byte offset within the file.
+    position: u64,
+
+    /// File data, if the data is stored inline in the inode.
+    inline_data: Option<Vec<u8>>,
+
+    /// Iterator over the blocks of the file. If `None`, the iterator
+    /// needs to be recreated, either because it has not been created
+    /// yet, or because it failed with an error.
+    file_blocks: Option<FileBlocks>,
+
+    /// Index within the file of the next block `file_blocks` will
+    /// yield.
+    next_block: u64,
+
+    /// The most recent block yielded by `file_blocks`.
+    last_block: FsBlockIndex,
+}
+
+impl AsyncFile {
+    /// Open `inode`.
+    async fn open_inode<R: AsyncExt4Read>(
+        fs: &mut AsyncExt4<R>,
+        inode: Inode,
+    ) -> Result<Self, Ext4Error> {
+        let inline_data =
+            if inode.metadata.flags.contains(FileAttributes::INLINE_DATA) {
+                Some(fs.run(|fs| read_inline_data(fs, &inode)).await?)
+            } else {
+                None
+            };
+
+        Ok(Self {
+            inode,
+            position: 0,
+            inline_data,
+            file_blocks: None,
+            next_block: 0,
+            last_block: 0,
+        })
+    }
+
+    /// Get the file metadata.
+    #[must_use]
+    pub fn metadata(&self) -> &Metadata {
+        &self.inode.metadata
+    }
+
+    /// Current position within the file.
+    #[must_use]
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Seek from the start of the file to `position`.
+    ///
+    /// Seeking past the end of the file is allowed.
+    pub fn seek_to(&mut self, position: u64) {
+        self.position = position;
+    }
+
+    /// Get the absolute block index of block `block_within_file`.
+    ///
+    /// Iteration continues from the previous call if possible,
+    /// otherwise a new iterator is created starting at
+    /// `block_within_file`.
+    fn get_block(
+        &mut self,
+        fs: &Ext4,
+        block_within_file: u64,
+    ) -> Result<FsBlockIndex, Ext4Error> {
+        if self.file_blocks.is_some()
+            && self.next_block.checked_sub(1) == Some(block_within_file)
+        {
+            return Ok(self.last_block);
+        }
+
+        let file_blocks = match &mut self.file_blocks {
+            Some(file_blocks) if self.next_block == block_within_file => {
+                file_blocks
+            }
+            _ => {
+                // The block index is past the end of the file if it
+                // doesn't fit in a `FileBlockIndex`.
+                let start = FileBlockIndex::try_from(block_within_file)
+                    .unwrap_or(FileBlockIndex::MAX);
+                // Creating the iterator may fail with `PendingRead`, so
+                // don't update any state until it succeeds. Otherwise
+                // the old iterator would be reused on the next call.
+                let file_blocks =
+                    FileBlocks::new_at(fs.clone(), &self.inode, start)?;
+                self.next_block = block_within_file;
+                self.file_blocks.insert(file_blocks)
+            }
+        };
+
+        match file_blocks.next() {
+            Some(Ok(block_index)) => {
+                // OK to unwrap: the number of blocks in a file fits in
+                // a `u32`, so this is at most `2^32`.
+                self.next_block = self.next_block.checked_add(1).unwrap();
+                self.last_block = block_index;
+                Ok(block_index)
+            }
+            Some(Err(err)) => {
+                // The iterator stops after an error, so it will need to
+                // be recreated.
+                self.file_blocks = None;
+                Err(err)
+            }
+            None => {
+                // The iterator yields a block for each block within the
+                // file size, so this should not happen. Treat the block
+                // as a hole.
+                self.file_blocks = None;
+                Ok(0)
+            }
+        }
+    }
+
+    /// Read bytes from the file into `buf`, returning how many bytes
+    /// were read. The number may be smaller than the length of the
+    /// input buffer.
+    ///
+    /// `fs` must be the filesystem that the file was opened from.
+    ///
+    /// This advances the position of the file by the number of bytes
+    /// read, so calling `read_bytes` repeatedly can be used to read the
+    /// entire file.
+    ///
+    /// Returns `Ok(0)` if the end of the file has been reached.
+    ///
+    /// See [`File::read_bytes`].
+    ///
+    /// [`File::read_bytes`]: crate::File::read_bytes
+    pub async fn read_bytes<R: AsyncExt4Read>(
+        &mut self,
+        fs: &mut AsyncExt4<R>,
+        mut buf: &mut [u8],
+    ) -> Result<usize, Ext4Error> {
+        // Nothing to do if output buffer is empty, or if already at
+        // the end of the file.
+        if buf.is_empty() || self.position >= self.inode.metadata.size_in_bytes
+        {
+            return Ok(0);
+        }
+
+        // Shrink the buffer if it's larger than the number of bytes
+        // remaining in the file.
+        //
+        // OK to unwrap: just checked that `position` is less than the
+        // file size.
+        let bytes_remaining = self
+            .inode
+            .metadata
+            .size_in_bytes
+            .checked_sub(self.position)
+            .unwrap();
+        if let Ok(bytes_remaining) = usize::try_from(bytes_remaining) {
+            if buf.len() > bytes_remaining {
+                buf = &mut buf[..bytes_remaining];
+            }
+        }
+
+        if let Some(inline_data) = &self.inline_data {
+            // OK to unwrap: inline data is held in memory, so the
+            // position (which is less than the file size) fits in a
+            // `usize`.
+            let start = usize::try_from(self.position).unwrap();
+            // OK to unwrap: the buffer length has been capped to the
+            // number of bytes remaining in the file.
+            let end = start.checked_add(buf.len()).unwrap();
+            buf.copy_from_slice(&inline_data[start..end]);
+        } else {
+            buf = self.read_blocks(fs, buf).await?;
+        }
+
+        // OK to unwrap: the buffer length is capped such that this
+        // calculation is at most the length of the file, which fits in
+        // a `u64`.
+        self.position = self
+            .position
+            .checked_add(u64::try_from(buf.len()).unwrap())
+            .unwrap();
+
+        Ok(buf.len())
+    }
+
+    /// Read data from a run of contiguous blocks at the current
+    /// position. Returns the part of `buf` that was filled.
+    async fn read_blocks<'b, R: AsyncExt4Read>(
+        &mut self,
+        fs: &mut AsyncExt4<R>,
+        buf: &'b mut [u8],
+    ) -> Result<&'b mut [u8], Ext4Error> {
+        let block_size = fs.fs.0.superblock.block_size;
+        let first_block = self.position / block_size.to_nz_u64();
+        // OK to unwrap: the offset is less than the block size, which
+        // fits in a `u32`.
+        let offset_within_block =
+            u32::try_from(self.position % block_size.to_nz_u64()).unwrap();
+
+        // Get the maximum number of blocks the read could cover.
+        let max_blocks = u64::try_from(buf.len())
+            .ok()
+            .and_then(|len| len.checked_add(u64::from(offset_within_block)))
+            .and_then(|end| end.checked_next_multiple_of(block_size.to_u64()))
+            .map(|end| end / block_size.to_nz_u64())
+            .ok_or(Ext4Error::FileTooLarge)?;
+
+        // Find the run of contiguous blocks starting at `first_block`.
+        // If the first block is a hole, this instead finds a run of
+        // holes.
+        //
+        // The operation may be run more than once, so journal remaps
+        // are counted here and only recorded once the data is read.
+        let (original_block_index, block_index, num_blocks, num_remaps) = fs
+            .run(|fs| {
+                let journal = &fs.0.journal;
+                let original_block_index = self.get_block(fs, first_block)?;
+                let block_index = journal.map_block_index(original_block_index);
+                let mut num_remaps =
+                    usize::from(block_index != original_block_index);
+
+                let mut num_blocks: u64 = 1;
+                while num_blocks < max_blocks {
+                    let next = first_block
+                        .checked_add(num_blocks)
+                        .ok_or(Ext4Error::FileTooLarge)?;
+                    let original_next = self.get_block(fs, next)?;
+                    let next = journal.map_block_index(original_next);
+                    let expected = if block_index == 0 {
+                        Some(0)
+                    } else {
+                        block_index.checked_add(num_blocks)
+                    };
+                    if Some(next) != expected {
+                        break;
+                    }
+                    // OK to unwrap: less than `max_blocks`.
+                    num_blocks = num_blocks.checked_add(1).unwrap();
+                    // OK to unwrap: at most `num_blocks`.
+                    num_remaps = num_remaps
+                        .checked_add(usize::from(next != original_next))
+                        .unwrap();
+                }
+
+                Ok((original_block_index, block_index, num_blocks, num_remaps))
+            })
+            .await?;
+
+        // Shrink the buffer to the end of the run.
+        let run_len = num_blocks
+            .checked_mul(block_size.to_u64())
+            .and_then(|len| len.checked_sub(u64::from(offset_within_block)))
+            .ok_or(Ext4Error::FileTooLarge)?;
+        let buf = if let Ok(run_len) = usize::try_from(run_len) {
+            let len = buf.len().min(run_len);
+            &mut buf[..len]
+        } else {
+            buf
+        };
+
+        // Read the data, or zeros if in a hole or unwritten extent.
+        if block_index == 0 {
+            buf.fill(0);
+            return Ok(buf);
+        }
+
+        let read_len = buf.len();
+        let err = || {
+            Ext4Error::from(CorruptKind::BlockRead {
+                block_index,
+                original_block_index,
+                offset_within_block,
+                read_len,
+            })
+        };
+
+        // Check that the run is within the filesystem.
+        let end_block = block_index.checked_add(num_blocks).ok_or_else(err)?;
+        if end_block > fs.fs.0.superblock.blocks_count {
+            return Err(err());
+        }
+
+        let start_byte = block_index
+            .checked_mul(block_size.to_u64())
+            .and_then(|start| start.checked_add(u64::from(offset_within_block)))
+            .ok_or_else(err)?;
+        fs.reader
+            .read(start_byte, buf)
+            .await
+            .map_err(Ext4Error::Io)?;
+        fs.fs.0.stats.record_journal_remaps(num_remaps);
+
+        Ok(buf)
+    }
+}
+
+impl Debug for AsyncFile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AsyncFile")
+            // Just show the index from `self.inode`, the full `Inode`
+            // output is verbose.
+            .field("inode", &self.inode.index)
+            .field("position", &self.position)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test that `PrefetchReader` only returns data that is fully
+    /// contained within a prefetched range.
+    #[test]
+    fn test_prefetch_reader() {
+        let prefetched = Shared::new(Lock::new(PrefetchState::new()));
+        prefetched.lock().insert(100, vec![1, 2, 3, 4]);
+        prefetched.lock().insert(200, vec![5, 6]);
+        let mut reader = PrefetchReader(prefetched);
+
+        let mut dst = [0; 2];
+        reader.read(101, &mut dst).unwrap();
+        assert_eq!(dst, [2, 3]);
+        reader.read(200, &mut dst).unwrap();
+        assert_eq!(dst, [5, 6]);
+
+        let mut pending = |start_byte, len| {
+            let mut dst = vec![0; len];
+            let err = reader.read(start_byte, &mut dst).unwrap_err();
+            *err.downcast_ref::<PendingRead>().unwrap()
+        };
+        // Before any prefetched data.
+        assert_eq!(
+            pending(0, 2),
+            PendingRead {
+                start_byte: 0,
+                len: 2
+            }
+        );
+        // Extends past the end of a range.
+        assert_eq!(
+            pending(103, 2),
+            PendingRead {
+                start_byte: 103,
+                len: 2
+            }
+        );
+        // Between ranges.
+        assert_eq!(
+            pending(150, 1),
+            PendingRead {
+                start_byte: 150,
+                len: 1
+            }
+        );
+    }
+
+    /// Test that consumed data is dropped once the total is over the
+    /// limit, unless dropped data is needed again.
+    #[test]
+    fn test_prefetch_state_drop() {
+        let mut state = PrefetchState::new();
+        let len = MAX_PREFETCHED_BYTES / 2;
+        let mut dst = [0; 1];
+
+        state.insert(0, vec![1; len]);
+        assert!(state.read(0, &mut dst));
+        state.insert(1 << 30, vec![2; len]);
+
+        // Over the limit: the consumed range is dropped, the unconsumed
+        // range is kept.
+        state.insert(2 << 30, vec![3; len]);
+        assert!(!state.read(0, &mut dst));
+        assert!(state.read(1 << 30, &mut dst));
+        assert!(state.read(2 << 30, &mut dst));
+        assert_eq!(state.num_bytes, 2 * len);
+
+        // The dropped range is needed again, so nothing more is
+        // dropped.
+        state.insert(0, vec![1; len]);
+        state.insert(3 << 30, vec![4; len]);
+        for start in [0, 1 << 30, 2 << 30, 3 << 30] {
+            assert!(state.read(start, &mut dst));
+        }
+
+        state.clear();
+        assert!(!state.read(0, &mut dst));
+    }
+}
@@ -10,42 +10,216 @@ use crate::block_index::FsBlockIndex;
 use crate::block_size::BlockSize;
 use crate::error::CorruptKind;
 use crate::error::Ext4Error;
+use crate::options::Ext4Options;
+use crate::stats::{self, CacheCounters};
+use crate::sync::{Lock, Shared};
 use crate::util::usize_from_u32;
 use alloc::boxed::Box;
-use alloc::collections::VecDeque;
 use alloc::vec;
+use core::fmt::{self, Debug, Formatter};
 
-/// Entry for a single block in the cache.
-#[derive(Clone)]
-struct CacheEntry {
-    /// Absolute block index within the filesystem.
+mod lru;
+mod table;
+mod two_queue;
+
+pub use lru::LruBlockCache;
+pub use two_queue::TwoQueueBlockCache;
+
+/// Key identifying a block in a [`BlockCacheBackend`].
+///
+/// Each loaded [`Ext4`] is assigned a filesystem ID that is unique
+/// within its cache, so that blocks from multiple filesystems can be
+/// held in the same cache.
+///
+/// [`Ext4`]: crate::Ext4
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct BlockCacheKey {
+    fs_id: u64,
     block_index: FsBlockIndex,
+}
+
+impl BlockCacheKey {
+    /// Create a new key.
+    #[must_use]
+    pub fn new(fs_id: u64, block_index: u64) -> Self {
+        Self { fs_id, block_index }
+    }
+
+    /// ID of the filesystem the block belongs to. IDs are unique
+    /// within a cache.
+    #[must_use]
+    pub fn fs_id(&self) -> u64 {
+        self.fs_id
+    }
 
-    /// Block data. The length is always equal to the filesystem block size.
-    data: Box<[u8]>,
+    /// Absolute index of the block within the filesystem.
+    #[must_use]
+    pub fn block_index(&self) -> u64 {
+        self.block_index
+    }
+}
+
+/// Kind of data stored in a block.
+///
+/// This is passed to [`BlockCacheBackend::insert`] as a hint, so that
+/// backends can treat metadata and file data differently. For example,
+/// [`TwoQueueBlockCache`] limits how much of the cache file data can
+/// use.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum BlockKind {
+    /// Filesystem metadata, such as inodes, directory entries, extent
+    /// tree nodes, and the journal.
+    Metadata,
+
+    /// File data.
+    Data,
+}
+
+/// Define the `BlockCacheBackend` trait. See `define_ext4_read` in
+/// `reader.rs` for why this is a macro.
+macro_rules! define_block_cache_backend {
+    ($($supertrait:tt)*) => {
+        /// Storage for cached filesystem blocks.
+        ///
+        /// A backend can be shared between multiple filesystems with
+        /// [`SharedBlockCache`]. The default backend is
+        /// [`TwoQueueBlockCache`].
+        ///
+        /// Backends only store blocks; reading from the underlying
+        /// storage (including reading multiple blocks at once) is
+        /// handled by the filesystem.
+        ///
+        /// If the `sync` feature is enabled, implementations must also
+        /// be [`Send`].
+        ///
+        /// The backend may still be used after one of its methods
+        /// panics, so a panic must not leave it in a state where
+        /// [`BlockCacheBackend::get`] returns incorrect data.
+        pub trait BlockCacheBackend $($supertrait)* {
+            /// Get the data of a cached block, or `None` if the block is
+            /// not in the cache.
+            ///
+            /// The returned data must be the same as the data passed to
+            /// [`BlockCacheBackend::insert`] for `key`. If the length
+            /// does not match the filesystem's block size, the block is
+            /// treated as not cached.
+            fn get(&mut self, key: BlockCacheKey) -> Option<&[u8]>;
+
+            /// Add a block to the cache. The length of `data` is the
+            /// filesystem's block size.
+            ///
+            /// `kind` indicates what the block holds. Blocks that are
+            /// read together are all given the kind of the block that
+            /// was requested, so this is only a hint.
+            ///
+            /// The backend is free to evict other blocks, or to not
+            /// store the block at all. Returns the number of blocks
+            /// evicted, which is reported in [`Ext4Stats`].
+            ///
+            /// [`Ext4Stats`]: crate::Ext4Stats
+            fn insert(
+                &mut self,
+                key: BlockCacheKey,
+                data: &[u8],
+                kind: BlockKind,
+            ) -> usize;
+
+            /// Remove all blocks belonging to the filesystem `fs_id`.
+            ///
+            /// This is called when the filesystem is dropped; its ID
+            /// will not be used again. The default implementation does
+            /// nothing, leaving the blocks to be evicted normally.
+            fn remove_filesystem(&mut self, fs_id: u64) {
+                let _ = fs_id;
+            }
+        }
+    };
 }
 
-/// LRU block cache.
+#[cfg(not(feature = "sync"))]
+define_block_cache_backend!();
+#[cfg(feature = "sync")]
+define_block_cache_backend!(: Send);
+
+/// Block cache that can be shared between multiple filesystems.
+///
+/// Pass to [`Ext4Options::block_cache`] to use the same cache, and
+/// therefore the same memory budget, for every filesystem loaded with
+/// those options. Cloning a `SharedBlockCache` is cheap; clones refer
+/// to the same cache.
 ///
-/// This is a fairly simple cache that holds a fixed number of blocks in
-/// a deque. The front of the deque is for most-recently accessed
-/// blocks, the back for least-recently accessed.
+/// # Example
 ///
-/// When a block in the cache is accessed, it's moved to the front of
-/// the cache, and new blocks are also added directly to the front.
+/// ```
+/// use ext4_view::{Ext4Options, SharedBlockCache, TwoQueueBlockCache};
 ///
-/// When new blocks are added, an equal number of blocks are popped off
-/// the back. At the end of insertion, the total number of cache entries
-/// remains unchanged. The block allocations within each entry are
-/// reused, so allocation only occurs when initializing the cache.
+/// let cache = SharedBlockCache::new(TwoQueueBlockCache::new(1024));
+/// let options = Ext4Options::new().block_cache(cache);
+/// ```
+///
+/// [`Ext4Options::block_cache`]: crate::Ext4Options::block_cache
+#[derive(Clone)]
+pub struct SharedBlockCache(Shared<Lock<SharedBlockCacheInner>>);
+
+struct SharedBlockCacheInner {
+    backend: Box<dyn BlockCacheBackend>,
+
+    /// ID to give the next filesystem that uses the cache.
+    next_fs_id: u64,
+}
+
+impl SharedBlockCache {
+    /// Create a shared cache using `backend` to store blocks.
+    #[must_use]
+    pub fn new<B: BlockCacheBackend + 'static>(backend: B) -> Self {
+        Self(Shared::new(Lock::new(SharedBlockCacheInner {
+            backend: Box::new(backend),
+            next_fs_id: 0,
+        })))
+    }
+
+    /// Get a new filesystem ID, unique within this cache.
+    fn new_fs_id(&self) -> u64 {
+        let mut inner = self.0.lock();
+        let fs_id = inner.next_fs_id;
+        inner.next_fs_id = fs_id.wrapping_add(1);
+        fs_id
+    }
+}
+
+impl Debug for SharedBlockCache {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedBlockCache").finish_non_exhaustive()
+    }
+}
+
+/// Two `SharedBlockCache`s are equal if they refer to the same cache.
+impl PartialEq for SharedBlockCache {
+    fn eq(&self, other: &Self) -> bool {
+        Shared::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for SharedBlockCache {}
+
+/// Per-filesystem interface to the block cache.
+///
+/// Blocks are stored in a [`BlockCacheBackend`], which may be shared
+/// with other filesystems.
 ///
 /// Blocks are read in a group. Depending on the underlying data source,
 /// this can be much more efficient than reading one by one.
 ///
-/// The number of entries in the cache, and the size of the read buffer,
-/// are controlled by the block size. The intent is to strike a
-/// reasonable balance between speed and memory usage.
+/// The size of the read buffer is controlled by the block size and the
+/// load options. By default, the intent is to strike a reasonable
+/// balance between speed and memory usage.
 pub(crate) struct BlockCache {
+    /// Storage for cached blocks.
+    backend: SharedBlockCache,
+
+    /// ID of this filesystem, unique within the backend.
+    fs_id: u64,
+
     /// Contiguous buffer of multiple blocks.
     ///
     /// Depending on the underlying data source, it can be much more
@@ -53,21 +227,14 @@ pub(crate) struct BlockCache {
     /// each one block in length. And it's a good bet that if we read
     /// block X, we'll soon need blocks X+1, X+2, etc.
     ///
-    /// Immediately after blocks are read into this buffer, they are individually
-    /// copied to an entry in `entries`.
+    /// Immediately after blocks are read into this buffer, they are
+    /// individually inserted into the backend.
     read_buf: Box<[u8]>,
 
     /// Maximum number of blocks that can be read into `read_buf`. The
     /// length of `read_buf` is `max_blocks_per_read * block_size`.
     max_blocks_per_read: u32,
 
-    /// Cache entries, sorted from most-recently-used to least.
-    ///
-    /// The entries are fully allocated when the cache is
-    /// created. During regular operation no additional allocation or
-    /// deallocation occurs, data is just copied around.
-    entries: VecDeque<CacheEntry>,
-
     /// File system block size.
     block_size: BlockSize,
 
@@ -76,62 +243,78 @@ pub(crate) struct BlockCache {
     /// This is used to ensure that when reading multiple blocks we
     /// don't go past the end of the filesystem.
     num_fs_blocks: u64,
+
+    /// Hit, miss, and eviction counters for this filesystem.
+    counters: CacheCounters,
 }
 
 impl BlockCache {
-    /// Create a block cache with sensible defaults.
+    /// Create a block cache, with the size controlled by `options`.
+    ///
+    /// If `options` contains a shared cache, blocks are stored there.
+    /// Otherwise a new [`TwoQueueBlockCache`] is created.
     pub(crate) fn new(
         block_size: BlockSize,
         num_fs_blocks: u64,
+        options: &Ext4Options,
     ) -> Result<Self, Ext4Error> {
-        Self::with_opts(CacheOpts::new(block_size), num_fs_blocks)
+        let opts = CacheOpts::from_options(block_size, options);
+        let backend = options.block_cache.clone().unwrap_or_else(|| {
+            SharedBlockCache::new(TwoQueueBlockCache::new(opts.num_entries))
+        });
+        Self::with_opts(opts, backend, num_fs_blocks)
     }
 
-    /// Create a block cache with control over the number of entries and
-    /// the read size.
-    ///
-    /// # Preconditions
-    ///
-    /// `max_blocks_per_read` must be less than or equal to `num_entries`.
+    /// Create a block cache with control over the read size.
     fn with_opts(
         opts: CacheOpts,
+        backend: SharedBlockCache,
         num_fs_blocks: u64,
     ) -> Result<Self, Ext4Error> {
-        assert!(usize_from_u32(opts.max_blocks_per_read) <= opts.num_entries);
-
         let read_buf_len = opts.read_buf_size_in_bytes();
 
-        let entries = vec![
-            CacheEntry {
-                block_index: 0,
-                data: vec![0; opts.block_size.to_usize()].into_boxed_slice(),
-            };
-            opts.num_entries
-        ];
         Ok(Self {
-            entries: VecDeque::from(entries),
+            fs_id: backend.new_fs_id(),
+            backend,
             max_blocks_per_read: opts.max_blocks_per_read,
             read_buf: vec![0; read_buf_len].into_boxed_slice(),
             block_size: opts.block_size,
             num_fs_blocks,
+            counters: CacheCounters::default(),
         })
     }
 
+    /// Get the hit, miss, and eviction counters.
+    pub(crate) fn counters(&self) -> CacheCounters {
+        self.counters
+    }
+
+    /// Reset the hit, miss, and eviction counters to zero.
+    pub(crate) fn reset_counters(&mut self) {
+        self.counters = CacheCounters::default();
+    }
+
     /// Get the number of blocks to read.
     ///
-    /// Normally this returns `max_blocks_per_read`. If reading that
-    /// many blocks would go past the end of the filesystem, the number
-    /// is clamped to avoid that.
+    /// This returns `max_blocks`, clamped to between one and
+    /// `max_blocks_per_read`. If reading that many blocks would go past
+    /// the end of the filesystem, the number is clamped to avoid that.
     ///
     /// # Preconditions
     ///
     /// `block_index` must be less than `num_fs_blocks`.
-    fn num_blocks_to_read(&self, block_index: FsBlockIndex) -> u32 {
+    fn num_blocks_to_read(
+        &self,
+        block_index: FsBlockIndex,
+        max_blocks: u32,
+    ) -> u32 {
         assert!(block_index < self.num_fs_blocks);
 
+        let max_blocks = max_blocks.clamp(1, self.max_blocks_per_read);
+
         // Get the index of the block right after the last block to read.
         let end_block = block_index
-            .saturating_add(u64::from(self.max_blocks_per_read))
+            .saturating_add(u64::from(max_blocks))
             .min(self.num_fs_blocks);
 
         // OK to unwrap: `end_block` can't be less than `block_index`.
@@ -142,113 +325,131 @@ impl BlockCache {
         u32::try_from(num_blocks).unwrap()
     }
 
-    /// Get the cache entry for `block_index`, reading and inserting
-    /// blocks into the cache if not already present.
+    /// Get the key for `block_index` in this filesystem.
+    fn key(&self, block_index: FsBlockIndex) -> BlockCacheKey {
+        BlockCacheKey::new(self.fs_id, block_index)
+    }
+
+    /// Read part of block `block_index` into `dst`, starting at
+    /// `offset_within_block`, if the block is cached. Returns `false`
+    /// without changing `dst` if the block is not cached.
     ///
-    /// If the entry is already present, it is moved to the front of the
-    /// cache to indicate it was accessed most recently.
+    /// Only hits are counted; the caller is responsible for reading
+    /// the block some other way on a miss.
     ///
-    /// Otherwise, `f` is called to read a contiguous group of
-    /// blocks. Each block is inserted into the cache, with the
-    /// requested `block_index` at the front of the cache. `f` is called
-    /// only once.
+    /// # Preconditions
+    ///
+    /// `offset_within_block + dst.len()` must be at most the block
+    /// size.
+    pub(crate) fn read_cached(
+        &mut self,
+        block_index: FsBlockIndex,
+        offset_within_block: u32,
+        dst: &mut [u8],
+    ) -> bool {
+        let block_size = self.block_size.to_usize();
+        let start = usize_from_u32(offset_within_block);
+        // OK to unwrap: precondition says the range is within a block.
+        let end = start.checked_add(dst.len()).unwrap();
+        assert!(end <= block_size);
+
+        let key = self.key(block_index);
+        let mut shared = self.backend.0.lock();
+        match shared.backend.get(key) {
+            Some(data) if data.len() == block_size => {
+                dst.copy_from_slice(&data[start..end]);
+                stats::add(&mut self.counters.hits, 1);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Read part of block `block_index` into `dst`, starting at
+    /// `offset_within_block`, reading and inserting blocks into the
+    /// cache if not already present.
+    ///
+    /// If the block is not cached, `f` is called to read a contiguous
+    /// group of up to `max_blocks` blocks, starting with `block_index`.
+    /// The size of the group is also limited by the read-ahead size.
+    /// Each block is inserted into the cache as a block of `kind`. `f`
+    /// is called at most once.
     ///
     /// # Preconditions
     ///
-    /// `block_index` must be less than `num_fs_blocks`.
-    pub(crate) fn get_or_insert_blocks<F>(
+    /// `block_index` must be less than `num_fs_blocks`, and
+    /// `offset_within_block + dst.len()` must be at most the block
+    /// size.
+    pub(crate) fn read<F>(
         &mut self,
         block_index: FsBlockIndex,
+        offset_within_block: u32,
+        dst: &mut [u8],
+        kind: BlockKind,
+        max_blocks: u32,
         f: F,
-    ) -> Result<&[u8], Ext4Error>
+    ) -> Result<(), Ext4Error>
     where
         F: FnOnce(&mut [u8]) -> Result<(), Ext4Error>,
     {
         assert!(block_index < self.num_fs_blocks);
 
-        // Check if the block is already cached.
-        if let Some(index) = self
-            .entries
-            .iter()
-            .position(|entry| entry.block_index == block_index)
-        {
-            // Move the entry to the front of the cache if it's not
-            // already there.
-            if index != 0 {
-                let entry = self.entries.remove(index).unwrap();
-                self.entries.push_front(entry);
-            }
+        let block_size = self.block_size.to_usize();
+        let start = usize_from_u32(offset_within_block);
+        // OK to unwrap: precondition says the range is within a block.
+        let end = start.checked_add(dst.len()).unwrap();
+        assert!(end <= block_size);
 
-            // Return the cached block data.
-            return Ok(&*self.entries[0].data);
+        // Check if the block is already cached.
+        if self.read_cached(block_index, offset_within_block, dst) {
+            return Ok(());
         }
+        stats::add(&mut self.counters.misses, 1);
 
         // Get the number of blocks/bytes to read.
-        let num_blocks = self.num_blocks_to_read(block_index);
+        let num_blocks = self.num_blocks_to_read(block_index, max_blocks);
         let num_bytes = usize_from_u32(num_blocks)
-            .checked_mul(self.block_size.to_usize())
+            .checked_mul(block_size)
             .ok_or(CorruptKind::BlockCacheReadTooLarge {
                 num_blocks,
                 block_size: self.block_size,
             })?;
 
-        // Read blocks into the read buffer.
-        f(&mut self.read_buf[..num_bytes])?;
-
-        // Add blocks to the cache. Blocks are added to the front in
-        // reverse order, so that the requested `block_index` is at the
-        // very front of the cache.
-        for i in (0..num_blocks).rev() {
+        // Read blocks into the read buffer. The backend is not locked
+        // during the read, so other filesystems sharing it can continue
+        // to use it.
+        let read_buf = &mut self.read_buf[..num_bytes];
+        f(read_buf)?;
+
+        // Add blocks to the cache. Blocks are added in reverse order,
+        // so that the requested `block_index` is the most recently
+        // added.
+        let mut shared = self.backend.0.lock();
+        for (i, data) in read_buf.chunks_exact(block_size).enumerate().rev() {
             // OK to unwrap: function precondition requires that the
-            // requested blocks are valid (i.e. within the filesystem),
+            // requested blocks are valid (i.e. within the filesystem).
             // Valid block indices fit in a `u64`, so this can't
             // overflow.
-            let block_index = block_index.checked_add(u64::from(i)).unwrap();
-
-            self.insert_block(block_index, i);
+            let block_index = u64::try_from(i)
+                .ok()
+                .and_then(|i| block_index.checked_add(i))
+                .unwrap();
+            let num_evicted = shared.backend.insert(
+                BlockCacheKey::new(self.fs_id, block_index),
+                data,
+                kind,
+            );
+            stats::add(&mut self.counters.evictions, num_evicted);
         }
 
-        // Get the requested block data, which should be at the front of
-        // the cache now.
-        let entry = &self.entries[0];
-        assert_eq!(entry.block_index, block_index);
-        Ok(&*entry.data)
+        dst.copy_from_slice(&read_buf[start..end]);
+        Ok(())
     }
+}
 
-    /// Add a block to the front of the cache. The block data is read
-    /// from the `read_buf` at an offset of `block_within_read_buf *
-    /// block_size`.
-    ///
-    /// # Preconditions
-    ///
-    /// `block_within_read_buf` must be a valid block index within the
-    /// read buf.
-    fn insert_block(
-        &mut self,
-        block_index: FsBlockIndex,
-        block_within_read_buf: u32,
-    ) {
-        assert!(block_within_read_buf < self.max_blocks_per_read);
-
-        // OK to unwrap: precondition says that `block_within_read_buf`
-        // is valid.
-        let start = usize_from_u32(block_within_read_buf)
-            .checked_mul(self.block_size.to_usize())
-            .unwrap();
-        let end = start.checked_add(self.block_size.to_usize()).unwrap();
-        let src = &self.read_buf[start..end];
-
-        // Take an entry from the back of the cache. Note that although
-        // this removes the entry from the deque, the entry is just
-        // being moved, so the large block allocation within the entry
-        // is not freed or reallocated.
-        let mut entry = self.entries.pop_back().unwrap();
-
-        entry.block_index = block_index;
-        entry.data.copy_from_slice(src);
-
-        // Move the entry to the front of the cache.
-        self.entries.push_front(entry);
+impl Drop for BlockCache {
+    fn drop(&mut self) {
+        self.backend.0.lock().backend.remove_filesystem(self.fs_id);
     }
 }
 
@@ -261,32 +462,40 @@ struct CacheOpts {
 
 impl CacheOpts {
     /// Create `CacheOpts` with sensible values based on the block size.
+    #[cfg(test)]
     fn new(block_size: BlockSize) -> Self {
-        // On a typical 4K-blocksize filesystem, read 8 blocks at a
+        Self::from_options(block_size, &Ext4Options::default())
+    }
+
+    /// Create `CacheOpts` from the load `options`.
+    fn from_options(block_size: BlockSize, options: &Ext4Options) -> Self {
+        // By default, on a typical 4K-blocksize filesystem, read 8
+        // blocks at a time. Ensure that at least one block is read at a
         // time.
-        let max_bytes_per_read = 8 * 4096;
-        // Ensure that at least one block is read at a time.
         let max_blocks_per_read =
-            1.max(max_bytes_per_read / block_size.to_nz_u32());
+            1.max(options.read_ahead_size / block_size.to_nz_u32());
 
-        // OK to unwrap: the smallest block size is 1024, so
-        // `max_blocks_per_read` cannot exceed
-        // ((8*4096)/1024)=32. `num_entries` is therefore at most
-        // 32*8=256, which fits in `u32`.
-        let num_entries: u32 = max_blocks_per_read.checked_mul(8).unwrap();
+        let num_entries = if let Some(num_entries) = options.cache_entries {
+            num_entries.max(usize_from_u32(max_blocks_per_read))
+        } else {
+            // Cache eight times the number of blocks read at once. This
+            // saturates rather than failing, since a cache that large
+            // will fail to allocate anyway.
+            usize_from_u32(max_blocks_per_read).saturating_mul(8)
+        };
 
         Self {
             block_size,
             max_blocks_per_read,
-            num_entries: usize_from_u32(num_entries),
+            num_entries,
         }
     }
 
     fn read_buf_size_in_bytes(&self) -> usize {
         // OK to unwrap: outside of tests, `CacheOpts` is always created
-        // by the new method. For any large block size,
-        // `max_blocks_per_read` is capped to 1, so the multiplication
-        // cannot cause overflow.
+        // by `from_options`. Either `max_blocks_per_read` is 1, or
+        // `max_blocks_per_read * block_size` is at most
+        // `read_ahead_size`, which fits in a `u32`.
         usize_from_u32(self.max_blocks_per_read)
             .checked_mul(self.block_size.to_usize())
             .unwrap()
@@ -338,75 +547,98 @@ mod tests {
     }
 
     #[test]
-    fn test_num_blocks_to_read() {
-        let num_fs_blocks = 8;
-        let cache = BlockCache::with_opts(
+    fn test_cache_opts_from_options() {
+        let block_size = get_block_size(4096);
+
+        // Custom read-ahead size, rounded down to a multiple of the
+        // block size.
+        let options = Ext4Options::new().read_ahead_size(5 * 4096 + 100);
+        assert_eq!(
+            CacheOpts::from_options(block_size, &options),
             CacheOpts {
-                block_size: get_block_size(1024),
-                max_blocks_per_read: 4,
-                num_entries: 4,
-            },
-            num_fs_blocks,
-        )
-        .unwrap();
-        assert_eq!(cache.num_blocks_to_read(0), 4);
-        assert_eq!(cache.num_blocks_to_read(4), 4);
-        assert_eq!(cache.num_blocks_to_read(5), 3);
-        assert_eq!(cache.num_blocks_to_read(7), 1);
+                block_size,
+                max_blocks_per_read: 5,
+                num_entries: 40,
+            }
+        );
+
+        // At least one block is always read.
+        let options = Ext4Options::new().read_ahead_size(0);
+        assert_eq!(
+            CacheOpts::from_options(block_size, &options),
+            CacheOpts {
+                block_size,
+                max_blocks_per_read: 1,
+                num_entries: 8,
+            }
+        );
+
+        // Custom number of entries.
+        let options = Ext4Options::new().cache_entries(100);
+        assert_eq!(
+            CacheOpts::from_options(block_size, &options),
+            CacheOpts {
+                block_size,
+                max_blocks_per_read: 8,
+                num_entries: 100,
+            }
+        );
+
+        // The number of entries is at least the number of blocks read
+        // at once.
+        let options = Ext4Options::new().cache_entries(1);
+        assert_eq!(
+            CacheOpts::from_options(block_size, &options),
+            CacheOpts {
+                block_size,
+                max_blocks_per_read: 8,
+                num_entries: 8,
+            }
+        );
     }
 
-    #[test]
-    fn test_insert_block() {
-        let num_fs_blocks = 8;
-        let mut cache = BlockCache::with_opts(
+    /// Create a `BlockCache` with a 1K block size and its own
+    /// `LruBlockCache`, so that evictions are predictable.
+    fn new_cache(
+        max_blocks_per_read: u32,
+        num_entries: usize,
+        num_fs_blocks: u64,
+    ) -> BlockCache {
+        BlockCache::with_opts(
             CacheOpts {
                 block_size: get_block_size(1024),
-                max_blocks_per_read: 4,
-                num_entries: 4,
+                max_blocks_per_read,
+                num_entries,
             },
+            SharedBlockCache::new(LruBlockCache::new(num_entries)),
             num_fs_blocks,
         )
-        .unwrap();
-
-        cache.read_buf[0] = 6;
-        cache.read_buf[1024] = 7;
-
-        // Insert a block and check that it's in the front of the cache.
-        cache.insert_block(123, 0);
-        assert_eq!(cache.entries[0].block_index, 123);
-        assert_eq!(cache.entries[0].data[0], 6);
-        let block123_ptr = cache.entries[0].data.as_ptr();
-
-        // Insert another block, which is now the front of the cache.
-        cache.insert_block(456, 1);
-        assert_eq!(cache.entries[0].block_index, 456);
-        assert_eq!(cache.entries[0].data[0], 7);
-
-        // Check that the previous front of the cache is now in the
-        // second entry.
-        assert_eq!(cache.entries[1].block_index, 123);
-        assert_eq!(cache.entries[1].data[0], 6);
-        // And verify that the underlying allocation hasn't changed.
-        assert_eq!(cache.entries[1].data.as_ptr(), block123_ptr);
+        .unwrap()
     }
 
     #[test]
-    fn test_get_or_insert_blocks() {
-        let num_fs_blocks = 8;
-        let mut cache = BlockCache::with_opts(
-            CacheOpts {
-                block_size: get_block_size(1024),
-                max_blocks_per_read: 2,
-                num_entries: 4,
-            },
-            num_fs_blocks,
-        )
-        .unwrap();
+    fn test_num_blocks_to_read() {
+        let cache = new_cache(4, 4, 8);
+        assert_eq!(cache.num_blocks_to_read(0, u32::MAX), 4);
+        assert_eq!(cache.num_blocks_to_read(4, u32::MAX), 4);
+        assert_eq!(cache.num_blocks_to_read(5, u32::MAX), 3);
+        assert_eq!(cache.num_blocks_to_read(7, u32::MAX), 1);
+
+        // Fewer blocks can be requested, but at least one is read.
+        assert_eq!(cache.num_blocks_to_read(0, 2), 2);
+        assert_eq!(cache.num_blocks_to_read(6, 3), 2);
+        assert_eq!(cache.num_blocks_to_read(0, 0), 1);
+    }
+
+    #[test]
+    fn test_block_cache_read() {
+        let mut cache = new_cache(2, 4, 8);
+        let mut dst = [0; 2];
 
         // Test that an error in the closure is propagated.
         assert_eq!(
             cache
-                .get_or_insert_blocks(1, |_| {
+                .read(1, 0, &mut dst, BlockKind::Metadata, u32::MAX, |_| {
                     Err(CorruptKind::TooManyBlocksInFile.into())
                 })
                 .unwrap_err(),
@@ -415,58 +647,110 @@ mod tests {
 
         // Request block 1. This requires reading, so blocks 1 and 2 are
         // added to the cache.
-        let data = cache
-            .get_or_insert_blocks(1, |buf| {
-                // Expecting two blocks due to `max_blocks_per_read=2`.
-                assert_eq!(buf.len(), 1024 * 2);
+        cache
+            .read(
+                1,
+                1023,
+                &mut dst[..1],
+                BlockKind::Metadata,
+                u32::MAX,
+                |buf| {
+                    // Expecting two blocks due to `max_blocks_per_read=2`.
+                    assert_eq!(buf.len(), 1024 * 2);
+
+                    // Block 1:
+                    buf[1023] = 3;
+                    // Block 2:
+                    buf[1024] = 4;
+
+                    Ok(())
+                },
+            )
+            .unwrap();
+        assert_eq!(dst[0], 3);
 
-                // Block 1:
-                buf[0] = 3;
-                // Block 2:
-                buf[1024] = 4;
+        // Request block 2. This is already in the cache, so no read
+        // should occur.
+        cache
+            .read(2, 0, &mut dst[..1], BlockKind::Metadata, u32::MAX, |_| {
+                panic!("read closure called unexpectedly");
+            })
+            .unwrap();
+        assert_eq!(dst[0], 4);
 
+        // Add blocks 3 to 6 to the cache. This causes blocks 1 and 2 to
+        // be evicted.
+        cache
+            .read(3, 0, &mut dst, BlockKind::Metadata, u32::MAX, |_| Ok(()))
+            .unwrap();
+        cache
+            .read(5, 0, &mut dst, BlockKind::Metadata, u32::MAX, |_| Ok(()))
+            .unwrap();
+        let mut read_called = false;
+        cache
+            .read(1, 0, &mut dst, BlockKind::Metadata, u32::MAX, |_| {
+                read_called = true;
                 Ok(())
             })
             .unwrap();
+        assert!(read_called);
 
-        // Check that block 1's data was returned.
-        assert_eq!(data[0], 3);
-
-        // Requested block should be at the front of the cache.
-        assert_eq!(cache.entries[0].block_index, 1);
-        assert_eq!(cache.entries[0].data[0], 3);
-        // Followed by the other blocks read.
-        assert_eq!(cache.entries[1].block_index, 2);
-        assert_eq!(cache.entries[1].data[0], 4);
-
-        // Request block 2. This is already in the cache, so no read
-        // should occur.
-        let data = cache
-            .get_or_insert_blocks(2, |_| {
-                panic!("read closure called unexpectedly");
+        // Request a smaller group of blocks.
+        cache
+            .read(6, 0, &mut dst, BlockKind::Data, 1, |buf| {
+                assert_eq!(buf.len(), 1024);
+                Ok(())
             })
             .unwrap();
+    }
 
-        // Check that block 2's data was returned.
-        assert_eq!(data[0], 4);
-
-        // The requested block should now be at the front of the cache.
-        assert_eq!(cache.entries[0].block_index, 2);
-        assert_eq!(cache.entries[1].block_index, 1);
-
-        // Add blocks 3 and 4 to the cache.
-        cache.get_or_insert_blocks(3, |_| Ok(())).unwrap();
-        assert_eq!(cache.entries[0].block_index, 3);
-        assert_eq!(cache.entries[1].block_index, 4);
-        assert_eq!(cache.entries[2].block_index, 2);
-        assert_eq!(cache.entries[3].block_index, 1);
-
-        // Add blocks 5 and 6 to the cache. This causes blocks 1 and 2
-        // to be evicted.
-        cache.get_or_insert_blocks(5, |_| Ok(())).unwrap();
-        assert_eq!(cache.entries[0].block_index, 5);
-        assert_eq!(cache.entries[1].block_index, 6);
-        assert_eq!(cache.entries[2].block_index, 3);
-        assert_eq!(cache.entries[3].block_index, 4);
+    /// Test that filesystems sharing a backend don't see each other's
+    /// blocks, and that a filesystem's blocks are removed when it is
+    /// dropped.
+    #[test]
+    fn test_shared_block_cache() {
+        let backend = SharedBlockCache::new(LruBlockCache::new(16));
+        let opts = || CacheOpts {
+            block_size: get_block_size(1024),
+            max_blocks_per_read: 1,
+            num_entries: 16,
+        };
+        let mut cache1 =
+            BlockCache::with_opts(opts(), backend.clone(), 8).unwrap();
+        let mut cache2 =
+            BlockCache::with_opts(opts(), backend.clone(), 8).unwrap();
+        assert_ne!(cache1.fs_id, cache2.fs_id);
+
+        let mut dst = [0; 1];
+        cache1
+            .read(0, 0, &mut dst, BlockKind::Metadata, u32::MAX, |buf| {
+                buf[0] = 1;
+                Ok(())
+            })
+            .unwrap();
+        cache2
+            .read(0, 0, &mut dst, BlockKind::Metadata, u32::MAX, |buf| {
+                buf[0] = 2;
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(dst[0], 2);
+        cache1
+            .read(0, 0, &mut dst, BlockKind::Metadata, u32::MAX, |_| {
+                panic!("unexpected read")
+            })
+            .unwrap();
+        assert_eq!(dst[0], 1);
+
+        let fs_id1 = cache1.fs_id;
+        drop(cache1);
+        let mut shared = backend.0.lock();
+        assert!(shared.backend.get(BlockCacheKey::new(fs_id1, 0)).is_none());
+        assert!(
+            shared
+                .backend
+                .get(BlockCacheKey::new(cache2.fs_id, 0))
+                .is_some()
+        );
     }
 }
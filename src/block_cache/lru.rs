@@ -0,0 +1,166 @@
+// Copyright 2025 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use super::table::{EntryTable, List, store_data};
+use super::{BlockCacheBackend, BlockCacheKey, BlockKind};
+
+/// LRU block cache.
+///
+/// This is a simple cache that holds a fixed number of blocks in a
+/// single list, ordered from most-recently to least-recently accessed.
+///
+/// When a block in the cache is accessed, it's moved to the front of
+/// the list, and new blocks are also added directly to the front.
+///
+/// Once the cache is full, adding a block evicts the least-recently
+/// accessed block. Its allocation is reused for the new block if the
+/// block sizes match, so allocation only occurs while the cache is
+/// filling up.
+///
+/// Note that a single large read can evict every block in an LRU
+/// cache. [`TwoQueueBlockCache`] is resistant to that, and is used by
+/// default.
+///
+/// [`TwoQueueBlockCache`]: crate::TwoQueueBlockCache
+pub struct LruBlockCache {
+    entries: EntryTable<()>,
+
+    /// Entries, from most-recently-used to least.
+    lru: List,
+
+    /// Maximum number of entries.
+    max_entries: usize,
+}
+
+impl LruBlockCache {
+    /// Create a cache that holds up to `max_entries` blocks.
+    ///
+    /// Entries are allocated as blocks are added.
+    #[must_use]
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            entries: EntryTable::new(),
+            lru: List::new(),
+            max_entries,
+        }
+    }
+}
+
+impl BlockCacheBackend for LruBlockCache {
+    fn get(&mut self, key: BlockCacheKey) -> Option<&[u8]> {
+        let index = self.entries.find(key)?;
+        self.entries.move_to_front(&mut self.lru, index);
+        Some(&self.entries.get(index).data)
+    }
+
+    fn insert(
+        &mut self,
+        key: BlockCacheKey,
+        data: &[u8],
+        _kind: BlockKind,
+    ) -> usize {
+        // Replace the existing entry for this block if present.
+        if let Some(index) = self.entries.find(key) {
+            store_data(&mut self.entries.get_mut(index).data, data);
+            self.entries.move_to_front(&mut self.lru, index);
+            return 0;
+        }
+
+        if self.max_entries == 0 {
+            return 0;
+        }
+
+        // Evict the least-recently used entry if the cache is full,
+        // keeping its allocation for the new block.
+        let mut block = Default::default();
+        let mut num_evicted = 0;
+        if self.lru.len() >= self.max_entries {
+            if let Some(index) = self.lru.back() {
+                self.entries.unlink(&mut self.lru, index);
+                block = self.entries.remove(index);
+                num_evicted = 1;
+            }
+        }
+        store_data(&mut block, data);
+
+        let index = self.entries.insert(key, ());
+        self.entries.get_mut(index).data = block;
+        self.entries.push_front(&mut self.lru, index);
+        num_evicted
+    }
+
+    fn remove_filesystem(&mut self, fs_id: u64) {
+        for index in self.entries.indices_where(|e| e.key.fs_id() == fs_id) {
+            self.entries.unlink(&mut self.lru, index);
+            self.entries.remove(index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    /// Get the keys in an `LruBlockCache`, from most to least recently
+    /// used.
+    fn lru_keys(cache: &LruBlockCache) -> Vec<(u64, u64)> {
+        cache
+            .entries
+            .iter(&cache.lru)
+            .map(|index| {
+                let key = cache.entries.get(index).key;
+                (key.fs_id(), key.block_index())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_lru_block_cache() {
+        let mut cache = LruBlockCache::new(3);
+        let key = |fs_id, block_index| BlockCacheKey::new(fs_id, block_index);
+        let meta = BlockKind::Metadata;
+
+        assert!(cache.get(key(0, 1)).is_none());
+
+        cache.insert(key(0, 1), &[1; 4], meta);
+        cache.insert(key(0, 2), &[2; 4], meta);
+        cache.insert(key(1, 1), &[3; 4], meta);
+        assert_eq!(lru_keys(&cache), [(1, 1), (0, 2), (0, 1)]);
+
+        // Getting a block moves it to the front.
+        assert_eq!(cache.get(key(0, 1)).unwrap(), [1; 4]);
+        assert_eq!(lru_keys(&cache), [(0, 1), (1, 1), (0, 2)]);
+
+        // Inserting a new block evicts the least-recently used block,
+        // and reuses its allocation.
+        let index = cache.entries.find(key(0, 2)).unwrap();
+        let ptr = cache.entries.get(index).data.as_ptr();
+        cache.insert(key(0, 3), &[4; 4], meta);
+        assert_eq!(lru_keys(&cache), [(0, 3), (0, 1), (1, 1)]);
+        assert_eq!(cache.get(key(0, 3)).unwrap().as_ptr(), ptr);
+        assert!(cache.get(key(0, 2)).is_none());
+
+        // Inserting an existing block replaces it.
+        cache.insert(key(1, 1), &[5; 4], meta);
+        assert_eq!(lru_keys(&cache), [(1, 1), (0, 3), (0, 1)]);
+        assert_eq!(cache.get(key(1, 1)).unwrap(), [5; 4]);
+
+        // Blocks of different sizes can be stored.
+        cache.insert(key(2, 1), &[6; 8], meta);
+        assert_eq!(cache.get(key(2, 1)).unwrap(), [6; 8]);
+
+        cache.remove_filesystem(0);
+        assert_eq!(lru_keys(&cache), [(2, 1), (1, 1)]);
+
+        // A cache with no entries stores nothing.
+        let mut cache = LruBlockCache::new(0);
+        cache.insert(key(0, 1), &[1; 4], meta);
+        assert!(cache.get(key(0, 1)).is_none());
+    }
+}
@@ -0,0 +1,407 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::block_cache::BlockCacheKey;
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Sentinel index used for the end of a linked list or hash chain.
+const NIL: usize = usize::MAX;
+
+/// Minimum number of hash buckets, once any entries have been added.
+const MIN_BUCKETS: usize = 16;
+
+/// Entry in an [`EntryTable`].
+pub(super) struct Entry<T> {
+    pub(super) key: BlockCacheKey,
+
+    /// Block data. This is empty for entries that only track a key
+    /// (such as ghost entries).
+    pub(super) data: Box<[u8]>,
+
+    /// Cache-policy specific tag, typically identifying which list the
+    /// entry is on.
+    pub(super) tag: T,
+
+    /// Previous entry in the entry's list (towards the front).
+    prev: usize,
+
+    /// Next entry in the entry's list (towards the back).
+    next: usize,
+
+    /// Next entry in the same hash bucket.
+    hash_next: usize,
+}
+
+/// Doubly-linked list of entries in an [`EntryTable`]. The front of the
+/// list is for most-recently added or used entries.
+pub(super) struct List {
+    head: usize,
+    tail: usize,
+    len: usize,
+}
+
+impl List {
+    pub(super) const fn new() -> Self {
+        Self {
+            head: NIL,
+            tail: NIL,
+            len: 0,
+        }
+    }
+
+    /// Number of entries in the list.
+    pub(super) fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Index of the entry at the back of the list.
+    pub(super) fn back(&self) -> Option<usize> {
+        (self.tail != NIL).then_some(self.tail)
+    }
+}
+
+/// Storage for cache entries, with constant-time lookup by key and
+/// constant-time list operations.
+///
+/// Entries are stored in a slab of slots and referred to by index.
+/// Lookup uses a chained hash table, and entries can be placed on
+/// intrusive doubly-linked [`List`]s owned by the caller. Entry
+/// indices remain valid until the entry is removed.
+pub(super) struct EntryTable<T> {
+    /// Entry slots. `None` for free slots.
+    slots: Vec<Option<Entry<T>>>,
+
+    /// Indices of free slots.
+    free: Vec<usize>,
+
+    /// Head of each hash chain. The length is zero or a power of two.
+    buckets: Vec<usize>,
+
+    /// Number of occupied slots.
+    len: usize,
+}
+
+impl<T> EntryTable<T> {
+    pub(super) const fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            free: Vec::new(),
+            buckets: Vec::new(),
+            len: 0,
+        }
+    }
+
+    /// Get the entry at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` does not refer to an entry.
+    pub(super) fn get(&self, index: usize) -> &Entry<T> {
+        // OK to unwrap: indices come from `insert`/`find` and are only
+        // used while the entry exists.
+        self.slots[index].as_ref().unwrap()
+    }
+
+    /// Get the entry at `index` mutably.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` does not refer to an entry.
+    pub(super) fn get_mut(&mut self, index: usize) -> &mut Entry<T> {
+        // OK to unwrap: indices come from `insert`/`find` and are only
+        // used while the entry exists.
+        self.slots[index].as_mut().unwrap()
+    }
+
+    /// Get the hash bucket for `key`.
+    ///
+    /// # Preconditions
+    ///
+    /// `buckets` must not be empty.
+    fn bucket(&self, key: BlockCacheKey) -> usize {
+        // Fibonacci hashing. The high bits are folded into the low bits
+        // since only the low bits are used to select the bucket.
+        let hash = (key.block_index ^ key.fs_id.rotate_left(32))
+            .wrapping_mul(0x9e37_79b9_7f4a_7c15);
+        let hash = hash ^ hash.rotate_left(32);
+
+        // OK to unwrap: `buckets` is not empty.
+        let mask = self.buckets.len().checked_sub(1).unwrap();
+        // Truncation is fine here, since the result is masked.
+        #[expect(clippy::as_conversions)]
+        let hash = hash as usize;
+        hash & mask
+    }
+
+    /// Find the index of the entry for `key`.
+    pub(super) fn find(&self, key: BlockCacheKey) -> Option<usize> {
+        if self.buckets.is_empty() {
+            return None;
+        }
+
+        let mut index = self.buckets[self.bucket(key)];
+        while index != NIL {
+            let entry = self.get(index);
+            if entry.key == key {
+                return Some(index);
+            }
+            index = entry.hash_next;
+        }
+        None
+    }
+
+    /// Add an entry for `key` with empty data, and return its index.
+    /// The entry is not on any list.
+    ///
+    /// # Preconditions
+    ///
+    /// There must not already be an entry for `key`.
+    pub(super) fn insert(&mut self, key: BlockCacheKey, tag: T) -> usize {
+        debug_assert!(self.find(key).is_none());
+
+        if self.len >= self.buckets.len() {
+            self.grow();
+        }
+
+        let bucket = self.bucket(key);
+        let entry = Entry {
+            key,
+            data: Box::default(),
+            tag,
+            prev: NIL,
+            next: NIL,
+            hash_next: self.buckets[bucket],
+        };
+        let index = if let Some(index) = self.free.pop() {
+            self.slots[index] = Some(entry);
+            index
+        } else {
+            self.slots.push(Some(entry));
+            // OK to unwrap: a slot was just pushed.
+            self.slots.len().checked_sub(1).unwrap()
+        };
+        self.buckets[bucket] = index;
+        // OK to unwrap: the number of entries is at most the number of
+        // slots, which fits in a `usize`.
+        self.len = self.len.checked_add(1).unwrap();
+        index
+    }
+
+    /// Remove the entry at `index` and return its data, so that the
+    /// allocation can be reused.
+    ///
+    /// # Preconditions
+    ///
+    /// The entry must not be on a list.
+    pub(super) fn remove(&mut self, index: usize) -> Box<[u8]> {
+        let key = self.get(index).key;
+        let bucket = self.bucket(key);
+
+        // Unlink the entry from its hash chain.
+        let hash_next = self.get(index).hash_next;
+        if self.buckets[bucket] == index {
+            self.buckets[bucket] = hash_next;
+        } else {
+            let mut prev = self.buckets[bucket];
+            while self.get(prev).hash_next != index {
+                prev = self.get(prev).hash_next;
+            }
+            self.get_mut(prev).hash_next = hash_next;
+        }
+
+        // OK to unwrap: the entry exists.
+        let entry = self.slots[index].take().unwrap();
+        self.free.push(index);
+        // OK to unwrap: there was at least one entry.
+        self.len = self.len.checked_sub(1).unwrap();
+        entry.data
+    }
+
+    /// Double the number of hash buckets and rehash all entries.
+    fn grow(&mut self) {
+        let num_buckets = self.buckets.len().saturating_mul(2).max(MIN_BUCKETS);
+        self.buckets = vec![NIL; num_buckets];
+        for index in 0..self.slots.len() {
+            let Some(key) = self.slots[index].as_ref().map(|entry| entry.key)
+            else {
+                continue;
+            };
+            let bucket = self.bucket(key);
+            self.get_mut(index).hash_next = self.buckets[bucket];
+            self.buckets[bucket] = index;
+        }
+    }
+
+    /// Get the indices of all entries matching `pred`.
+    pub(super) fn indices_where<F>(&self, mut pred: F) -> Vec<usize>
+    where
+        F: FnMut(&Entry<T>) -> bool,
+    {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(|(index, entry)| {
+                entry.as_ref().filter(|entry| pred(entry)).map(|_| index)
+            })
+            .collect()
+    }
+
+    /// Add the entry at `index` to the front of `list`.
+    ///
+    /// # Preconditions
+    ///
+    /// The entry must not be on a list.
+    pub(super) fn push_front(&mut self, list: &mut List, index: usize) {
+        let old_head = list.head;
+        {
+            let entry = self.get_mut(index);
+            entry.prev = NIL;
+            entry.next = old_head;
+        }
+        if old_head == NIL {
+            list.tail = index;
+        } else {
+            self.get_mut(old_head).prev = index;
+        }
+        list.head = index;
+        // OK to unwrap: the number of entries in a list is at most the
+        // number of slots, which fits in a `usize`.
+        list.len = list.len.checked_add(1).unwrap();
+    }
+
+    /// Remove the entry at `index` from `list`.
+    ///
+    /// # Preconditions
+    ///
+    /// The entry must be on `list`.
+    pub(super) fn unlink(&mut self, list: &mut List, index: usize) {
+        let (prev, next) = {
+            let entry = self.get_mut(index);
+            let links = (entry.prev, entry.next);
+            entry.prev = NIL;
+            entry.next = NIL;
+            links
+        };
+        if prev == NIL {
+            list.head = next;
+        } else {
+            self.get_mut(prev).next = next;
+        }
+        if next == NIL {
+            list.tail = prev;
+        } else {
+            self.get_mut(next).prev = prev;
+        }
+        // OK to unwrap: the list contained the entry.
+        list.len = list.len.checked_sub(1).unwrap();
+    }
+
+    /// Move the entry at `index` to the front of `list`.
+    ///
+    /// # Preconditions
+    ///
+    /// The entry must be on `list`.
+    pub(super) fn move_to_front(&mut self, list: &mut List, index: usize) {
+        if list.head != index {
+            self.unlink(list, index);
+            self.push_front(list, index);
+        }
+    }
+
+    /// Iterate over the indices of the entries in `list`, from front to
+    /// back.
+    #[cfg(test)]
+    pub(super) fn iter<'a>(
+        &'a self,
+        list: &'a List,
+    ) -> impl Iterator<Item = usize> + 'a {
+        let non_nil = |index| (index != NIL).then_some(index);
+        core::iter::successors(non_nil(list.head), move |index| {
+            non_nil(self.get(*index).next)
+        })
+    }
+}
+
+/// Copy `data` into `dst`, reusing the existing allocation if the
+/// length matches.
+pub(super) fn store_data(dst: &mut Box<[u8]>, data: &[u8]) {
+    if dst.len() == data.len() {
+        dst.copy_from_slice(data);
+    } else {
+        *dst = data.into();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_table() {
+        let key = |block_index| BlockCacheKey::new(0, block_index);
+        let mut table = EntryTable::new();
+        let mut list = List::new();
+        assert!(table.find(key(0)).is_none());
+
+        // Add enough entries to require growing the hash table.
+        for i in 0..100 {
+            let index = table.insert(key(i), ());
+            table.push_front(&mut list, index);
+        }
+        assert_eq!(list.len(), 100);
+        for i in 0..100 {
+            let index = table.find(key(i)).unwrap();
+            assert_eq!(table.get(index).key, key(i));
+        }
+        assert!(table.find(key(100)).is_none());
+        let keys: Vec<u64> = table
+            .iter(&list)
+            .map(|index| table.get(index).key.block_index())
+            .collect();
+        assert_eq!(keys, (0..100).rev().collect::<Vec<_>>());
+
+        // Remove an entry and check that its slot is reused.
+        let index = table.find(key(50)).unwrap();
+        table.unlink(&mut list, index);
+        table.remove(index);
+        assert!(table.find(key(50)).is_none());
+        assert_eq!(list.len(), 99);
+        assert_eq!(table.insert(key(200), ()), index);
+        table.push_front(&mut list, index);
+
+        // Move the last entry to the front.
+        let back = list.back().unwrap();
+        assert_eq!(table.get(back).key, key(0));
+        table.move_to_front(&mut list, back);
+        assert_eq!(table.get(list.back().unwrap()).key, key(1));
+        let front: Vec<u64> = table
+            .iter(&list)
+            .take(2)
+            .map(|index| table.get(index).key.block_index())
+            .collect();
+        assert_eq!(front, [0, 200]);
+
+        assert_eq!(
+            table.indices_where(|entry| entry.key.block_index() >= 99),
+            [table.find(key(200)).unwrap(), table.find(key(99)).unwrap()]
+        );
+    }
+
+    #[test]
+    fn test_store_data() {
+        let mut data: Box<[u8]> = Box::default();
+        store_data(&mut data, &[1, 2]);
+        assert_eq!(*data, [1, 2]);
+        let ptr = data.as_ptr();
+        store_data(&mut data, &[3, 4]);
+        assert_eq!(*data, [3, 4]);
+        assert_eq!(data.as_ptr(), ptr);
+    }
+}
@@ -0,0 +1,440 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use super::table::{EntryTable, List, store_data};
+use super::{BlockCacheBackend, BlockCacheKey, BlockKind};
+use alloc::boxed::Box;
+use core::mem;
+use core::num::NonZero;
+
+/// Queue that an entry is on.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Queue {
+    /// Blocks that have been added to the cache once.
+    Recent,
+
+    /// Keys of blocks recently evicted from `Recent`. No data is held.
+    Ghost,
+
+    /// Blocks that were needed again after being evicted from
+    /// `Recent`, split by kind.
+    Frequent(BlockKind),
+}
+
+impl Queue {
+    /// Index of the queue's list in [`TwoQueueBlockCache::lists`].
+    fn list_index(self) -> usize {
+        match self {
+            Self::Recent => 0,
+            Self::Ghost => 1,
+            Self::Frequent(BlockKind::Metadata) => 2,
+            Self::Frequent(BlockKind::Data) => 3,
+        }
+    }
+}
+
+/// Scan-resistant block cache using the 2Q algorithm.
+///
+/// Blocks are first added to a small FIFO queue of recently-added
+/// blocks. Accessing a block in that queue does not change its
+/// position, so a large read passes through the queue without
+/// disturbing the rest of the cache. When a block is evicted from the
+/// queue, its key is remembered in a ghost queue. If the block is added
+/// again while its key is still in the ghost queue, it is moved into
+/// the main LRU queue of frequently-used blocks.
+///
+/// The main queue is split into metadata and data tiers based on the
+/// [`BlockKind`] of each block. Each tier may use up to half of the
+/// main queue, plus any space the other tier is not using. When the
+/// main queue is full, blocks are evicted from whichever tier is over
+/// its share, so a workload that repeatedly reads file data cannot push
+/// out all of the inode table and directory blocks.
+///
+/// Lookup, insertion, and eviction are all constant time. Allocations
+/// of evicted blocks are reused if the block sizes match.
+///
+/// This is the default cache backend.
+pub struct TwoQueueBlockCache {
+    entries: EntryTable<Queue>,
+
+    /// One list per [`Queue`], indexed by [`Queue::list_index`]. The
+    /// front of each list is for the most recently added or used
+    /// entries.
+    lists: [List; 4],
+
+    /// Maximum number of blocks held in the cache.
+    max_entries: usize,
+
+    /// Number of blocks the recent queue holds before blocks are
+    /// evicted from it in preference to the main queue.
+    max_recent: usize,
+
+    /// Maximum number of keys in the ghost queue.
+    max_ghost: usize,
+
+    /// Share of the main queue for each tier.
+    tier_share: usize,
+}
+
+impl TwoQueueBlockCache {
+    /// Create a cache that holds up to `max_entries` blocks.
+    ///
+    /// A quarter of the entries are used for the queue of
+    /// recently-added blocks, and the rest for the main queue. In
+    /// addition, the keys of up to `max_entries / 2` recently-evicted
+    /// blocks are tracked.
+    ///
+    /// Entries are allocated as blocks are added.
+    #[must_use]
+    pub fn new(max_entries: usize) -> Self {
+        const TWO: NonZero<usize> = NonZero::new(2).unwrap();
+        const FOUR: NonZero<usize> = NonZero::new(4).unwrap();
+
+        let max_recent = (max_entries / FOUR).max(1);
+        Self {
+            entries: EntryTable::new(),
+            lists: [List::new(), List::new(), List::new(), List::new()],
+            max_entries,
+            max_recent,
+            max_ghost: max_entries / TWO,
+            tier_share: max_entries.saturating_sub(max_recent) / TWO,
+        }
+    }
+
+    fn list(&self, queue: Queue) -> &List {
+        &self.lists[queue.list_index()]
+    }
+
+    /// Number of blocks held in the cache.
+    fn num_blocks(&self) -> usize {
+        [
+            Queue::Recent,
+            Queue::Frequent(BlockKind::Metadata),
+            Queue::Frequent(BlockKind::Data),
+        ]
+        .into_iter()
+        .map(|queue| self.list(queue).len())
+        .sum()
+    }
+
+    /// Move the entry at `index` to the front of `queue`.
+    fn move_entry(&mut self, index: usize, queue: Queue) {
+        let entry = self.entries.get_mut(index);
+        let old_queue = mem::replace(&mut entry.tag, queue);
+        self.entries
+            .unlink(&mut self.lists[old_queue.list_index()], index);
+        self.entries
+            .push_front(&mut self.lists[queue.list_index()], index);
+    }
+
+    /// Remove the entry at `index` entirely, returning its data.
+    fn remove_entry(&mut self, index: usize) -> Box<[u8]> {
+        let queue = self.entries.get(index).tag;
+        self.entries
+            .unlink(&mut self.lists[queue.list_index()], index);
+        self.entries.remove(index)
+    }
+
+    /// Evict one block to make room for a block being added to
+    /// `new_queue`. The evicted block's data is returned so that the
+    /// allocation can be reused.
+    fn evict(&mut self, new_queue: Queue) -> Option<Box<[u8]>> {
+        let metadata_len =
+            self.list(Queue::Frequent(BlockKind::Metadata)).len();
+        let data_len = self.list(Queue::Frequent(BlockKind::Data)).len();
+
+        // Get the length of `queue` once the new block is added.
+        let new_len = |queue: Queue, len: usize| {
+            if new_queue == queue {
+                len.saturating_add(1)
+            } else {
+                len
+            }
+        };
+
+        // Evict from the recent queue if it will be over its limit, or
+        // if the main queue is empty. The evicted block's key is moved
+        // to the ghost queue.
+        let recent_len = self.list(Queue::Recent).len();
+        if new_len(Queue::Recent, recent_len) > self.max_recent
+            || (metadata_len == 0 && data_len == 0)
+        {
+            if let Some(index) = self.list(Queue::Recent).back() {
+                let data = mem::take(&mut self.entries.get_mut(index).data);
+                self.move_entry(index, Queue::Ghost);
+                if self.list(Queue::Ghost).len() > self.max_ghost {
+                    if let Some(index) = self.list(Queue::Ghost).back() {
+                        self.remove_entry(index);
+                    }
+                }
+                return Some(data);
+            }
+        }
+
+        // Otherwise evict the least-recently used block from the main
+        // queue, taking it from the data tier if that tier will be over
+        // its share once the new block is added.
+        let new_data_len = new_len(Queue::Frequent(BlockKind::Data), data_len);
+        let (kind, other_kind) = if new_data_len > self.tier_share {
+            (BlockKind::Data, BlockKind::Metadata)
+        } else {
+            (BlockKind::Metadata, BlockKind::Data)
+        };
+        let index = self
+            .list(Queue::Frequent(kind))
+            .back()
+            .or_else(|| self.list(Queue::Frequent(other_kind)).back())?;
+        Some(self.remove_entry(index))
+    }
+}
+
+impl BlockCacheBackend for TwoQueueBlockCache {
+    fn get(&mut self, key: BlockCacheKey) -> Option<&[u8]> {
+        let index = self.entries.find(key)?;
+        let queue = self.entries.get(index).tag;
+        match queue {
+            Queue::Ghost => return None,
+            // Blocks in the recent queue are not moved when accessed;
+            // see the type docs.
+            Queue::Recent => {}
+            Queue::Frequent(_) => {
+                self.entries
+                    .move_to_front(&mut self.lists[queue.list_index()], index);
+            }
+        }
+        Some(&self.entries.get(index).data)
+    }
+
+    fn insert(
+        &mut self,
+        key: BlockCacheKey,
+        data: &[u8],
+        kind: BlockKind,
+    ) -> usize {
+        if self.max_entries == 0 {
+            return 0;
+        }
+
+        let mut queue = Queue::Recent;
+        if let Some(index) = self.entries.find(key) {
+            match self.entries.get(index).tag {
+                Queue::Recent => {
+                    store_data(&mut self.entries.get_mut(index).data, data);
+                    return 0;
+                }
+                Queue::Frequent(_) => {
+                    store_data(&mut self.entries.get_mut(index).data, data);
+                    self.move_entry(index, Queue::Frequent(kind));
+                    return 0;
+                }
+                Queue::Ghost => {
+                    // The block was evicted recently and is needed
+                    // again, so add it to the main queue.
+                    self.remove_entry(index);
+                    queue = Queue::Frequent(kind);
+                }
+            }
+        }
+
+        let evicted = if self.num_blocks() >= self.max_entries {
+            self.evict(queue)
+        } else {
+            None
+        };
+        let num_evicted = usize::from(evicted.is_some());
+        let mut block = evicted.unwrap_or_default();
+        store_data(&mut block, data);
+
+        let index = self.entries.insert(key, queue);
+        self.entries.get_mut(index).data = block;
+        self.entries
+            .push_front(&mut self.lists[queue.list_index()], index);
+        num_evicted
+    }
+
+    fn remove_filesystem(&mut self, fs_id: u64) {
+        for index in self.entries.indices_where(|e| e.key.fs_id() == fs_id) {
+            self.remove_entry(index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    fn key(block_index: u64) -> BlockCacheKey {
+        BlockCacheKey::new(0, block_index)
+    }
+
+    /// Get the block indices in `queue`, from front to back.
+    fn queue_blocks(cache: &TwoQueueBlockCache, queue: Queue) -> Vec<u64> {
+        cache
+            .entries
+            .iter(cache.list(queue))
+            .map(|index| cache.entries.get(index).key.block_index())
+            .collect()
+    }
+
+    /// Insert `block_index` into the cache as a block of `kind`, with
+    /// the block index as the data.
+    fn insert(
+        cache: &mut TwoQueueBlockCache,
+        block_index: u64,
+        kind: BlockKind,
+    ) {
+        cache.insert(key(block_index), &block_index.to_le_bytes(), kind);
+    }
+
+    /// Insert `block_index` until it is in the main queue.
+    fn insert_frequent(
+        cache: &mut TwoQueueBlockCache,
+        block_index: u64,
+        kind: BlockKind,
+    ) {
+        insert(cache, block_index, kind);
+        for filler in 0.. {
+            let index = cache.entries.find(key(block_index)).unwrap();
+            if cache.entries.get(index).tag == Queue::Frequent(kind) {
+                break;
+            }
+
+            // Push the block out of the recent queue with blocks that
+            // won't be reused. These use a separate filesystem ID so that
+            // they don't conflict with other blocks.
+            let filler_key =
+                BlockCacheKey::new(block_index.wrapping_add(1), filler);
+            cache.insert(filler_key, &[0; 8], BlockKind::Data);
+            insert(cache, block_index, kind);
+        }
+    }
+
+    #[test]
+    fn test_two_queue_sizes() {
+        let cache = TwoQueueBlockCache::new(16);
+        assert_eq!(cache.max_recent, 4);
+        assert_eq!(cache.max_ghost, 8);
+        assert_eq!(cache.tier_share, 6);
+
+        let cache = TwoQueueBlockCache::new(1);
+        assert_eq!(cache.max_recent, 1);
+        assert_eq!(cache.max_ghost, 0);
+        assert_eq!(cache.tier_share, 0);
+    }
+
+    #[test]
+    fn test_two_queue_promotion() {
+        let mut cache = TwoQueueBlockCache::new(8);
+        assert!(cache.get(key(1)).is_none());
+
+        // New blocks go in the recent queue. Accessing them doesn't
+        // change their position.
+        for i in 1..=3 {
+            insert(&mut cache, i, BlockKind::Metadata);
+        }
+        assert_eq!(cache.get(key(1)).unwrap(), 1u64.to_le_bytes());
+        assert_eq!(queue_blocks(&cache, Queue::Recent), [3, 2, 1]);
+
+        // Fill the cache. Adding more blocks evicts the oldest blocks
+        // from the recent queue into the ghost queue.
+        for i in 4..=10 {
+            insert(&mut cache, i, BlockKind::Metadata);
+        }
+        assert_eq!(
+            queue_blocks(&cache, Queue::Recent),
+            [10, 9, 8, 7, 6, 5, 4, 3]
+        );
+        assert_eq!(queue_blocks(&cache, Queue::Ghost), [2, 1]);
+        assert!(cache.get(key(1)).is_none());
+
+        // Adding a block in the ghost queue moves it to the main queue.
+        let index = cache.entries.find(key(3)).unwrap();
+        let ptr = cache.entries.get(index).data.as_ptr();
+        insert(&mut cache, 1, BlockKind::Metadata);
+        assert_eq!(
+            queue_blocks(&cache, Queue::Frequent(BlockKind::Metadata)),
+            [1]
+        );
+        assert_eq!(queue_blocks(&cache, Queue::Ghost), [3, 2]);
+        // The evicted block's allocation was reused.
+        assert_eq!(cache.get(key(1)).unwrap().as_ptr(), ptr);
+        assert_eq!(cache.get(key(1)).unwrap(), 1u64.to_le_bytes());
+
+        // Replacing a block in the recent queue doesn't move it.
+        cache.insert(key(7), &[7; 8], BlockKind::Metadata);
+        assert_eq!(cache.get(key(7)).unwrap(), [7; 8]);
+        assert_eq!(queue_blocks(&cache, Queue::Recent), [10, 9, 8, 7, 6, 5, 4]);
+
+        // The ghost queue is limited to half the number of entries.
+        for i in 11..=20 {
+            insert(&mut cache, i, BlockKind::Metadata);
+        }
+        assert_eq!(queue_blocks(&cache, Queue::Ghost), [13, 12, 11, 10]);
+
+        cache.remove_filesystem(0);
+        assert!(cache.entries.indices_where(|_| true).is_empty());
+        assert_eq!(cache.num_blocks(), 0);
+    }
+
+    /// Test that a large scan doesn't evict blocks from the main queue.
+    #[test]
+    fn test_two_queue_scan_resistance() {
+        let mut cache = TwoQueueBlockCache::new(16);
+        for i in 0..4 {
+            insert_frequent(&mut cache, i, BlockKind::Metadata);
+        }
+        for i in 100..1000 {
+            insert(&mut cache, i, BlockKind::Data);
+            assert!(cache.get(key(i)).is_some());
+        }
+        for i in 0..4 {
+            assert_eq!(cache.get(key(i)).unwrap(), i.to_le_bytes());
+        }
+    }
+
+    /// Test that the data tier can't take more than its share of the
+    /// main queue when metadata needs space.
+    #[test]
+    fn test_two_queue_tiers() {
+        let mut cache = TwoQueueBlockCache::new(16);
+
+        // With no metadata blocks, data can use all of the main queue.
+        for i in 0..12 {
+            insert_frequent(&mut cache, i, BlockKind::Data);
+        }
+        assert_eq!(cache.list(Queue::Frequent(BlockKind::Data)).len(), 12);
+
+        // Adding metadata blocks evicts data blocks until the data tier
+        // is down to its share.
+        for i in 100..112 {
+            insert_frequent(&mut cache, i, BlockKind::Metadata);
+        }
+        assert_eq!(cache.list(Queue::Frequent(BlockKind::Data)).len(), 6);
+        assert_eq!(cache.list(Queue::Frequent(BlockKind::Metadata)).len(), 6);
+
+        // Adding more data blocks now evicts the least-recently used data
+        // blocks, not metadata.
+        assert!(cache.get(key(6)).is_some());
+        for i in 50..55 {
+            insert_frequent(&mut cache, i, BlockKind::Data);
+        }
+        assert_eq!(
+            queue_blocks(&cache, Queue::Frequent(BlockKind::Data)),
+            [54, 53, 52, 51, 50, 6]
+        );
+        assert_eq!(cache.list(Queue::Frequent(BlockKind::Metadata)).len(), 6);
+    }
+
+    #[test]
+    fn test_two_queue_zero_entries() {
+        let mut cache = TwoQueueBlockCache::new(0);
+        insert(&mut cache, 1, BlockKind::Metadata);
+        assert!(cache.get(key(1)).is_none());
+    }
+}
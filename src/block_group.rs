@@ -6,19 +6,67 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use crate::Ext4Read;
+use crate::Ext4;
 use crate::block_index::FsBlockIndex;
 use crate::checksum::Checksum;
 use crate::error::{CorruptKind, Ext4Error};
 use crate::features::{IncompatibleFeatures, ReadOnlyCompatibleFeatures};
 use crate::superblock::Superblock;
-use crate::util::{read_u16le, read_u32le, u64_from_hilo, usize_from_u32};
+use crate::util::{read_u16le, read_u32le, u64_from_hilo};
 use alloc::vec;
-use alloc::vec::Vec;
 
 pub(crate) type BlockGroupIndex = u32;
 
-#[derive(Debug)]
+/// Copy of the block group descriptor table.
+///
+/// With the `meta_bg` layout, each block of the table is stored in
+/// three block groups. Otherwise only the primary copy is used.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum TableCopy {
+    /// Copy in the first block group of the meta group, or the only
+    /// copy without `meta_bg`.
+    Primary,
+    /// Backup in the second block group of the meta group.
+    SecondGroup,
+    /// Backup in the last block group of the meta group.
+    LastGroup,
+}
+
+/// Check if block group `group` contains the superblock or a backup
+/// copy of it.
+///
+/// Group 0 always has the superblock. With `sparse_super2`, only the
+/// (at most two) groups listed in the superblock have a backup. With
+/// `sparse_super`, only group 1, and groups that are powers of 3, 5, or
+/// 7, have a backup. Otherwise every group has one.
+fn group_has_superblock(sb: &Superblock, group: BlockGroupIndex) -> bool {
+    if group == 0 {
+        return true;
+    }
+    if let Some(backup_block_groups) = sb.backup_block_groups {
+        return backup_block_groups.contains(&group);
+    }
+    if group == 1
+        || !sb
+            .read_only_compatible_features
+            .contains(ReadOnlyCompatibleFeatures::SPARSE_SUPERBLOCKS)
+    {
+        return true;
+    }
+
+    [3, 5, 7].into_iter().any(|base| {
+        let mut power: u32 = base;
+        while power < group {
+            match power.checked_mul(base) {
+                Some(next) => power = next,
+                None => return false,
+            }
+        }
+        power == group
+    })
+}
+
+#[derive(Clone, Copy, Debug)]
 pub(crate) struct BlockGroupDescriptor {
     pub(crate) inode_table_first_block: FsBlockIndex,
     checksum: u16,
@@ -53,49 +101,127 @@ impl BlockGroupDescriptor {
     }
 
     /// Map from a block group descriptor index to the absolute byte
-    /// within the file where the descriptor starts.
+    /// within the file where `copy` of the descriptor starts.
+    ///
+    /// Returns `None` if that copy does not exist, or if the location
+    /// cannot be calculated without overflow.
     fn get_start_byte(
         sb: &Superblock,
         bgd_index: BlockGroupIndex,
+        copy: TableCopy,
     ) -> Option<u64> {
-        let bgd_start_block: u32 = if sb.block_size == 1024 { 2 } else { 1 };
-        let bgd_per_block = sb
-            .block_size
-            .to_u32()
-            .checked_div(u32::from(sb.block_group_descriptor_size))?;
-        let block_index = bgd_start_block
-            .checked_add(bgd_index.checked_div(bgd_per_block)?)?;
+        let descriptor_size = u32::from(sb.block_group_descriptor_size);
+        let bgd_per_block =
+            sb.block_size.to_u32().checked_div(descriptor_size)?;
+        // Index of the descriptor's block within the table.
+        let table_block = bgd_index.checked_div(bgd_per_block)?;
         let offset_within_block = (bgd_index.checked_rem(bgd_per_block)?)
-            .checked_mul(u32::from(sb.block_group_descriptor_size))?;
+            .checked_mul(descriptor_size)?;
 
-        u64::from(block_index)
+        let block_index = if sb
+            .incompatible_features
+            .contains(IncompatibleFeatures::META_BLOCK_GROUPS)
+            && table_block >= sb.first_meta_bg
+        {
+            // With `meta_bg`, the block groups are divided into meta
+            // groups, each of which has one block of descriptors. That
+            // block is stored in the first, second, and last block
+            // groups of the meta group, just after the superblock
+            // backup (if the group has one).
+            let first_group = table_block.checked_mul(bgd_per_block)?;
+            let group = match copy {
+                TableCopy::Primary => first_group,
+                TableCopy::SecondGroup => first_group.checked_add(1)?,
+                TableCopy::LastGroup => {
+                    first_group.checked_add(bgd_per_block.checked_sub(1)?)?
+                }
+            };
+            if group >= sb.num_block_groups {
+                return None;
+            }
+
+            let mut has_superblock = u64::from(group_has_superblock(sb, group));
+            // With 1KiB blocks, the superblock is in block 1 even if
+            // `first_data_block` is zero (which is the case with
+            // bigalloc).
+            if group == 0 && sb.block_size == 1024 && sb.first_data_block == 0 {
+                has_superblock = has_superblock.checked_add(1)?;
+            }
+
+            u64::from(group)
+                .checked_mul(u64::from(sb.blocks_per_group.get()))?
+                .checked_add(u64::from(sb.first_data_block))?
+                .checked_add(has_superblock)?
+        } else {
+            // Otherwise the table is stored contiguously after the
+            // superblock. Backup copies of this table are not used.
+            if copy != TableCopy::Primary {
+                return None;
+            }
+            let bgd_start_block: u32 =
+                if sb.block_size == 1024 { 2 } else { 1 };
+            u64::from(bgd_start_block.checked_add(table_block)?)
+        };
+
+        block_index
             .checked_mul(sb.block_size.to_u64())?
             .checked_add(u64::from(offset_within_block))
     }
 
-    /// Read a block group descriptor.
-    fn read(
-        sb: &Superblock,
-        reader: &mut dyn Ext4Read,
+    /// Read a block group descriptor through the block cache.
+    ///
+    /// The descriptor checksum is verified if checksums are enabled
+    /// for the filesystem. If the checksum of the primary copy is
+    /// invalid, the backup copies (if any) are tried.
+    pub(crate) fn read(
+        fs: &Ext4,
         bgd_index: BlockGroupIndex,
     ) -> Result<Self, Ext4Error> {
+        if bgd_index >= fs.0.superblock.num_block_groups {
+            return Err(CorruptKind::BlockGroupDescriptor(bgd_index).into());
+        }
+
+        let primary = Self::read_copy(fs, bgd_index, TableCopy::Primary);
+        let checksum_err = CorruptKind::BlockGroupDescriptorChecksum(bgd_index);
+        if matches!(&primary, Err(err) if *err == checksum_err) {
+            for copy in [TableCopy::SecondGroup, TableCopy::LastGroup] {
+                if let Ok(bgd) = Self::read_copy(fs, bgd_index, copy) {
+                    return Ok(bgd);
+                }
+            }
+        }
+        primary
+    }
+
+    /// Read `copy` of a block group descriptor.
+    fn read_copy(
+        fs: &Ext4,
+        bgd_index: BlockGroupIndex,
+        copy: TableCopy,
+    ) -> Result<Self, Ext4Error> {
+        let sb = &fs.0.superblock;
+
         // Allocate a byte vec to read the raw data into.
         let block_group_descriptor_size =
             usize::from(sb.block_group_descriptor_size);
         let mut data = vec![0; block_group_descriptor_size];
 
-        let start = Self::get_start_byte(sb, bgd_index)
+        // Descriptors never cross a block boundary, so the descriptor
+        // can be read from a single block.
+        let start = Self::get_start_byte(sb, bgd_index, copy)
             .ok_or(CorruptKind::BlockGroupDescriptor(bgd_index))?;
-        reader.read(start, &mut data).map_err(Ext4Error::Io)?;
+        let block_index = start / sb.block_size.to_nz_u64();
+        // OK to unwrap: the remainder is less than the block size,
+        // which is a `u32`.
+        let offset_within_block =
+            u32::try_from(start % sb.block_size.to_nz_u64()).unwrap();
+        fs.read_from_block(block_index, offset_within_block, &mut data)?;
 
         let block_group_descriptor = Self::from_bytes(sb, &data);
 
-        let has_metadata_checksums = sb
-            .read_only_compatible_features
-            .contains(ReadOnlyCompatibleFeatures::METADATA_CHECKSUMS);
-
         // Verify the descriptor checksum.
-        if has_metadata_checksums {
+        if fs.verify_checksums() {
+            fs.record_checksum_verification();
             let mut checksum = Checksum::with_seed(sb.checksum_seed);
             checksum.update_u32_le(bgd_index);
             // Up to the checksum field.
@@ -113,9 +239,10 @@ impl BlockGroupDescriptor {
                 )
                 .into());
             }
-        } else if sb
-            .read_only_compatible_features
-            .contains(ReadOnlyCompatibleFeatures::GROUP_DESCRIPTOR_CHECKSUMS)
+        } else if !fs.has_metadata_checksums()
+            && sb.read_only_compatible_features.contains(
+                ReadOnlyCompatibleFeatures::GROUP_DESCRIPTOR_CHECKSUMS,
+            )
         {
             // TODO: prior to general checksum metadata being added,
             // there was a separate feature just for block group
@@ -124,20 +251,84 @@ impl BlockGroupDescriptor {
 
         Ok(block_group_descriptor)
     }
+}
 
-    /// Read all block group descriptors.
-    pub(crate) fn read_all(
-        sb: &Superblock,
-        reader: &mut dyn Ext4Read,
-    ) -> Result<Vec<Self>, Ext4Error> {
-        let mut block_group_descriptors =
-            Vec::with_capacity(usize_from_u32(sb.num_block_groups));
-
-        for bgd_index in 0..sb.num_block_groups {
-            let bgd = Self::read(sb, reader, bgd_index)?;
-            block_group_descriptors.push(bgd);
-        }
+#[cfg(feature = "std")]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::load_compressed_filesystem;
+
+    #[test]
+    fn test_group_has_superblock() {
+        let fs = load_compressed_filesystem("test_disk_meta_bg.bin.zst");
+        let mut sb = fs.0.superblock.clone();
+
+        let with_superblock: Vec<u32> =
+            (0..50).filter(|g| group_has_superblock(&sb, *g)).collect();
+        assert_eq!(with_superblock, [0, 1, 3, 5, 7, 9, 25, 27, 49]);
+
+        // Without `sparse_super`, every group has a superblock.
+        sb.read_only_compatible_features
+            .remove(ReadOnlyCompatibleFeatures::SPARSE_SUPERBLOCKS);
+        assert!((0..50).all(|g| group_has_superblock(&sb, g)));
+
+        // With `sparse_super2`, only the listed groups have a backup.
+        sb.backup_block_groups = Some([1, 19]);
+        let with_superblock: Vec<u32> =
+            (0..50).filter(|g| group_has_superblock(&sb, *g)).collect();
+        assert_eq!(with_superblock, [0, 1, 19]);
+    }
+
+    #[test]
+    fn test_get_start_byte_meta_bg() {
+        let fs = load_compressed_filesystem("test_disk_meta_bg.bin.zst");
+        let mut sb = fs.0.superblock.clone();
+        assert_eq!(sb.block_size, 1024);
+        assert_eq!(sb.block_group_descriptor_size, 64);
+        assert_eq!(sb.num_block_groups, 20);
+
+        let start = |sb: &Superblock, bgd_index, copy| {
+            BlockGroupDescriptor::get_start_byte(sb, bgd_index, copy)
+        };
+
+        // First meta group. Groups 0 and 1 have a superblock.
+        assert_eq!(start(&sb, 0, TableCopy::Primary), Some(2 * 1024));
+        assert_eq!(start(&sb, 1, TableCopy::Primary), Some(2 * 1024 + 64));
+        assert_eq!(start(&sb, 0, TableCopy::SecondGroup), Some(8194 * 1024));
+        assert_eq!(start(&sb, 0, TableCopy::LastGroup), Some(122881 * 1024));
+
+        // Second meta group. Neither group 16 nor 17 has a superblock,
+        // and the last group of the meta group (31) is past the end of
+        // the filesystem.
+        assert_eq!(start(&sb, 16, TableCopy::Primary), Some(131073 * 1024));
+        assert_eq!(
+            start(&sb, 19, TableCopy::Primary),
+            Some(131073 * 1024 + 3 * 64)
+        );
+        assert_eq!(start(&sb, 16, TableCopy::SecondGroup), Some(139265 * 1024));
+        assert_eq!(start(&sb, 16, TableCopy::LastGroup), None);
+
+        // With `sparse_super2`, the superblock backups are in the
+        // listed groups, shifting the descriptor blocks in those groups.
+        sb.backup_block_groups = Some([17, 16]);
+        assert_eq!(start(&sb, 16, TableCopy::Primary), Some(131074 * 1024));
+        assert_eq!(start(&sb, 16, TableCopy::SecondGroup), Some(139266 * 1024));
+        sb.backup_block_groups = Some([0, 0]);
+        assert_eq!(start(&sb, 0, TableCopy::SecondGroup), Some(8193 * 1024));
+        sb.backup_block_groups = None;
+
+        // Blocks of the table before `first_meta_bg` use the classic
+        // layout, which has no backups.
+        sb.first_meta_bg = 1;
+        assert_eq!(start(&sb, 0, TableCopy::Primary), Some(2 * 1024));
+        assert_eq!(start(&sb, 0, TableCopy::SecondGroup), None);
+        assert_eq!(start(&sb, 16, TableCopy::Primary), Some(131073 * 1024));
 
-        Ok(block_group_descriptors)
+        // Without `meta_bg`, the table is contiguous.
+        sb.incompatible_features
+            .remove(IncompatibleFeatures::META_BLOCK_GROUPS);
+        assert_eq!(start(&sb, 16, TableCopy::Primary), Some(3 * 1024));
+        assert_eq!(start(&sb, 16, TableCopy::SecondGroup), None);
     }
 }
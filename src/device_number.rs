@@ -0,0 +1,122 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::util::read_u32le;
+use core::fmt::{self, Display, Formatter};
+
+/// Major and minor device numbers of a character or block device.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct DeviceNumber {
+    major: u32,
+    minor: u32,
+}
+
+impl DeviceNumber {
+    /// Create a device number from major and minor numbers.
+    #[must_use]
+    pub const fn new(major: u32, minor: u32) -> Self {
+        Self { major, minor }
+    }
+
+    /// Decode a device number from the inode's `i_block` data.
+    ///
+    /// There are two encodings. The old encoding is stored in the
+    /// first `u32`, with an 8-bit major and an 8-bit minor. If that
+    /// value is zero, the new encoding is stored in the second `u32`,
+    /// with a 12-bit major and a 20-bit minor. See
+    /// `ext4_iget`/`ext4_do_update_inode` in the kernel.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i_block` is less than eight bytes long.
+    pub(crate) fn from_i_block(i_block: &[u8]) -> Self {
+        let old = read_u32le(i_block, 0);
+        if old != 0 {
+            // See `old_decode_dev` in the kernel.
+            Self {
+                major: (old >> 8) & 0xff,
+                minor: old & 0xff,
+            }
+        } else {
+            // See `new_decode_dev` in the kernel.
+            let new = read_u32le(i_block, 4);
+            Self {
+                major: (new & 0xf_ff00) >> 8,
+                minor: (new & 0xff) | ((new >> 12) & 0xf_ff00),
+            }
+        }
+    }
+
+    /// Major device number, identifying the device driver.
+    #[must_use]
+    pub const fn major(&self) -> u32 {
+        self.major
+    }
+
+    /// Minor device number, identifying the device within the driver.
+    #[must_use]
+    pub const fn minor(&self) -> u32 {
+        self.minor
+    }
+}
+
+impl Display for DeviceNumber {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.major, self.minor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn i_block(old: u32, new: u32) -> [u8; 8] {
+        let mut i_block = [0; 8];
+        i_block[..4].copy_from_slice(&old.to_le_bytes());
+        i_block[4..].copy_from_slice(&new.to_le_bytes());
+        i_block
+    }
+
+    #[test]
+    fn test_device_number_from_i_block() {
+        // Old encoding: /dev/null is 1:3.
+        assert_eq!(
+            DeviceNumber::from_i_block(&i_block(0x0103, 0)),
+            DeviceNumber::new(1, 3)
+        );
+
+        // New encoding, small numbers (e.g. /dev/nvme0n1 is 259:0).
+        assert_eq!(
+            DeviceNumber::from_i_block(&i_block(0, 0x1_0300)),
+            DeviceNumber::new(259, 0)
+        );
+
+        // New encoding, large minor number.
+        assert_eq!(
+            DeviceNumber::from_i_block(&i_block(0, 0x1234_5678)),
+            DeviceNumber::new(0x456, 0x12378)
+        );
+
+        // New encoding, maximum values.
+        assert_eq!(
+            DeviceNumber::from_i_block(&i_block(0, 0xffff_ffff)),
+            DeviceNumber::new(0xfff, 0xf_ffff)
+        );
+
+        // Both zero.
+        assert_eq!(
+            DeviceNumber::from_i_block(&i_block(0, 0)),
+            DeviceNumber::new(0, 0)
+        );
+    }
+
+    #[test]
+    fn test_device_number_display() {
+        assert_eq!(format!("{}", DeviceNumber::new(8, 1)), "8:1");
+    }
+}
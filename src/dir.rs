@@ -10,7 +10,8 @@ use crate::Ext4;
 use crate::dir_entry::DirEntryName;
 use crate::dir_htree::get_dir_entry_via_htree;
 use crate::error::Ext4Error;
-use crate::inode::{Inode, InodeFlags};
+use crate::file_attributes::FileAttributes;
+use crate::inode::Inode;
 use crate::iters::read_dir::ReadDir;
 use crate::path::PathBuf;
 
@@ -24,11 +25,17 @@ pub(crate) fn get_dir_entry_inode_by_name(
 ) -> Result<Inode, Ext4Error> {
     assert!(dir_inode.metadata.is_dir());
 
-    if dir_inode.flags.contains(InodeFlags::DIRECTORY_ENCRYPTED) {
+    if dir_inode.metadata.flags.contains(FileAttributes::ENCRYPTED) {
         return Err(Ext4Error::Encrypted);
     }
 
-    if dir_inode.flags.contains(InodeFlags::DIRECTORY_HTREE) {
+    // Inline directories never have an htree. Check for that
+    // explicitly since the htree flag is not meaningful when the
+    // directory's data is stored in the inode.
+    let flags = dir_inode.metadata.flags;
+    if flags.contains(FileAttributes::DIRECTORY_HTREE)
+        && !flags.contains(FileAttributes::INLINE_DATA)
+    {
         let entry = get_dir_entry_via_htree(fs, dir_inode, name)?;
         return Inode::read(fs, entry.inode);
     }
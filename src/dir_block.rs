@@ -58,9 +58,10 @@ impl DirBlock<'_> {
 
         self.fs.read_from_block(self.block_index, 0, block)?;
 
-        if !self.fs.has_metadata_checksums() {
+        if !self.fs.verify_checksums() {
             return Ok(());
         }
+        self.fs.record_checksum_verification();
 
         let block_type = self.get_block_type(block);
 
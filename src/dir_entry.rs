@@ -13,8 +13,10 @@ use crate::format::{BytesDisplay, format_bytes_debug};
 use crate::inode::{Inode, InodeIndex};
 use crate::metadata::Metadata;
 use crate::path::{Path, PathBuf};
+use crate::sync::Shared;
 use crate::util::{read_u16le, read_u32le};
-use alloc::rc::Rc;
+use crate::xattr;
+use alloc::vec::Vec;
 use core::error::Error;
 use core::fmt::{self, Debug, Display, Formatter};
 use core::hash::{Hash, Hasher};
@@ -140,7 +142,7 @@ impl<'a> TryFrom<&'a str> for DirEntryName<'a> {
 }
 
 #[derive(Clone, Eq, Ord, PartialOrd)]
-struct DirEntryNameBuf {
+pub(crate) struct DirEntryNameBuf {
     data: [u8; DirEntryName::MAX_LEN],
     len: u8,
 }
@@ -148,13 +150,13 @@ struct DirEntryNameBuf {
 impl DirEntryNameBuf {
     #[inline]
     #[must_use]
-    fn as_bytes(&self) -> &[u8] {
+    pub(crate) fn as_bytes(&self) -> &[u8] {
         &self.data[..usize::from(self.len)]
     }
 
     #[inline]
     #[must_use]
-    fn as_dir_entry_name(&self) -> DirEntryName<'_> {
+    pub(crate) fn as_dir_entry_name(&self) -> DirEntryName<'_> {
         DirEntryName(self.as_bytes())
     }
 }
@@ -210,14 +212,14 @@ pub struct DirEntry {
     pub(crate) inode: InodeIndex,
 
     /// Raw name of the entry.
-    name: DirEntryNameBuf,
+    pub(crate) name: DirEntryNameBuf,
 
-    /// Path that `read_dir` was called with. This is shared via `Rc` so
+    /// Path that `read_dir` was called with. This is shared via `Shared` so
     /// that only one allocation is required.
-    path: Rc<PathBuf>,
+    pub(crate) path: Shared<PathBuf>,
 
     /// Entry file type.
-    file_type: FileType,
+    pub(crate) file_type: FileType,
 }
 
 impl DirEntry {
@@ -238,7 +240,7 @@ impl DirEntry {
         fs: Ext4,
         bytes: &[u8],
         inode: InodeIndex,
-        path: Rc<PathBuf>,
+        path: Shared<PathBuf>,
     ) -> Result<(Option<Self>, NonZero<usize>), Ext4Error> {
         const NAME_OFFSET: usize = 8;
 
@@ -332,6 +334,17 @@ impl DirEntry {
         self.path.join(self.name.as_bytes())
     }
 
+    /// Get the inode number that the entry points to.
+    ///
+    /// This is read directly from the directory entry, so unlike
+    /// [`DirEntry::metadata`] it does not require reading the
+    /// inode. Entries with the same inode number are hard links to the
+    /// same file.
+    #[must_use]
+    pub fn ino(&self) -> u32 {
+        self.inode.get()
+    }
+
     /// Get the entry's file type.
     pub fn file_type(&self) -> Result<FileType, Ext4Error> {
         // Currently this function cannot fail, but return a `Result` to
@@ -349,6 +362,31 @@ impl DirEntry {
         let inode = Inode::read(&self.fs, self.inode)?;
         Ok(inode.metadata)
     }
+
+    /// Get the names of all extended attributes of the entry.
+    ///
+    /// If the entry is a symlink, the attributes of the symlink itself
+    /// will be returned, not the symlink target.
+    ///
+    /// See [`Ext4::list_xattrs`].
+    pub fn list_xattrs(&self) -> Result<Vec<Vec<u8>>, Ext4Error> {
+        let inode = Inode::read(&self.fs, self.inode)?;
+        xattr::list_xattrs(&self.fs, &inode)
+    }
+
+    /// Get the value of the extended attribute `name` of the entry.
+    ///
+    /// If the entry is a symlink, the attribute of the symlink itself
+    /// will be returned, not the symlink target.
+    ///
+    /// See [`Ext4::get_xattr`].
+    pub fn get_xattr<N: AsRef<[u8]>>(
+        &self,
+        name: N,
+    ) -> Result<Option<Vec<u8>>, Ext4Error> {
+        let inode = Inode::read(&self.fs, self.inode)?;
+        xattr::get_xattr(&self.fs, &inode, name.as_ref())
+    }
 }
 
 impl Debug for DirEntry {
@@ -472,7 +510,7 @@ mod tests {
 
         let inode1 = InodeIndex::new(1).unwrap();
         let inode2 = InodeIndex::new(2).unwrap();
-        let path = Rc::new(PathBuf::new("path"));
+        let path = Shared::new(PathBuf::new("path"));
 
         // Read a normal entry.
         let mut bytes = Vec::new();
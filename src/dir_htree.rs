@@ -13,12 +13,13 @@ use crate::dir_entry::{DirEntry, DirEntryName};
 use crate::dir_entry_hash::HashAlg;
 use crate::error::{CorruptKind, Ext4Error};
 use crate::extent::Extent;
-use crate::inode::{Inode, InodeFlags, InodeIndex};
+use crate::file_attributes::FileAttributes;
+use crate::inode::{Inode, InodeIndex};
 use crate::iters::extents::Extents;
 use crate::iters::file_blocks::FileBlocks;
 use crate::path::PathBuf;
+use crate::sync::Shared;
 use crate::util::{read_u16le, read_u32le, usize_from_u32};
-use alloc::rc::Rc;
 use alloc::vec;
 
 type DirHash = u32;
@@ -230,7 +231,7 @@ fn read_dot_or_dotdot(
         fs,
         &block[offset..],
         inode.index,
-        Rc::new(PathBuf::empty()),
+        Shared::new(PathBuf::empty()),
     )?;
     let entry = entry.ok_or_else(corrupt)?;
     if entry.file_name() == name {
@@ -267,7 +268,7 @@ fn block_from_file_block(
     inode: &Inode,
     relative_block: FileBlockIndex,
 ) -> Result<FsBlockIndex, Ext4Error> {
-    if inode.flags.contains(InodeFlags::EXTENTS) {
+    if inode.metadata.flags.contains(FileAttributes::EXTENTS) {
         let extent = find_extent_for_block(fs, inode, relative_block)?;
         let block_within_extent = relative_block
             .checked_sub(extent.block_within_file)
@@ -359,7 +360,12 @@ pub(crate) fn get_dir_entry_via_htree(
     inode: &Inode,
     name: DirEntryName<'_>,
 ) -> Result<DirEntry, Ext4Error> {
-    assert!(inode.flags.contains(InodeFlags::DIRECTORY_HTREE));
+    assert!(
+        inode
+            .metadata
+            .flags
+            .contains(FileAttributes::DIRECTORY_HTREE)
+    );
 
     let block_size = fs.0.superblock.block_size;
     let mut block = vec![0; block_size.to_usize()];
@@ -379,7 +385,7 @@ pub(crate) fn get_dir_entry_via_htree(
 
     // The entry's `path()` method will not be called, so the value of
     // the base path does not matter.
-    let path = Rc::new(PathBuf::empty());
+    let path = Shared::new(PathBuf::empty());
 
     // Do a linear search through the leaf block for the right entry.
     let mut offset_within_block = 0;
@@ -673,11 +679,13 @@ mod tests {
                     start_block: 2543,
                     num_blocks: 23,
                     block_within_file: 0,
+                    is_unwritten: false,
                 },
                 Extent {
                     start_block: 11,
                     num_blocks: 47,
                     block_within_file: 23,
+                    is_unwritten: false,
                 }
             ]
         );
@@ -56,6 +56,9 @@ pub enum Ext4Error {
     /// Data is not valid UTF-8.
     NotUtf8,
 
+    /// The end of a file was reached before a read was complete.
+    UnexpectedEof,
+
     /// Data cannot be converted into a valid path.
     MalformedPath,
 
@@ -123,6 +126,7 @@ impl Display for Ext4Error {
                 write!(f, "file is too large to store in memory")
             }
             Self::NotUtf8 => write!(f, "data is not utf-8"),
+            Self::UnexpectedEof => write!(f, "unexpected end of file"),
             Self::MalformedPath => write!(f, "data is not a valid path"),
             Self::PathTooLong => write!(f, "path is too long"),
             Self::TooManySymlinks => {
@@ -163,6 +167,7 @@ impl From<Ext4Error> for std::io::Error {
             Ext4Error::NotADirectory => NotADirectory.into(),
             Ext4Error::NotFound => NotFound.into(),
             Ext4Error::NotUtf8 => InvalidData.into(),
+            Ext4Error::UnexpectedEof => UnexpectedEof.into(),
             Ext4Error::Encrypted => PermissionDenied.into(),
         }
     }
@@ -266,6 +271,9 @@ pub(crate) enum CorruptKind {
     /// Journal sequence number overflowed.
     JournalSequenceOverflow,
 
+    /// Journal has both the v2 and v3 checksum features enabled.
+    JournalChecksumFeatures,
+
     /// Journal has a truncated descriptor block. Either it is missing a
     /// tag with the `LAST_TAG` flag set, or the final tag does have
     /// that flag set but there are not enough bytes to read the full
@@ -376,6 +384,37 @@ pub(crate) enum CorruptKind {
         read_len: usize,
     },
 
+    /// An inode's extended attribute block has an invalid header.
+    XattrBlockHeader(InodeIndex),
+
+    /// An inode's extended attribute block has an invalid checksum.
+    XattrBlockChecksum(InodeIndex),
+
+    /// An inode's extended attribute entry is invalid.
+    XattrEntry(InodeIndex),
+
+    /// An inode's extended attribute value is out of bounds.
+    XattrValue(InodeIndex),
+
+    /// An inode's POSIX ACL extended attribute is invalid.
+    XattrAcl(InodeIndex),
+
+    /// An inode's extended attribute references an invalid EA inode.
+    XattrInode {
+        inode: InodeIndex,
+        ea_inode: InodeIndex,
+    },
+
+    /// An inode's extended attribute value stored in an EA inode does
+    /// not match its hash.
+    XattrInodeHash {
+        inode: InodeIndex,
+        ea_inode: InodeIndex,
+    },
+
+    /// An inode's inline data is smaller than the inode's size.
+    InlineDataSize(InodeIndex),
+
     /// Attempting to read too much data in the block cache.
     BlockCacheReadTooLarge {
         num_blocks: u32,
@@ -448,6 +487,9 @@ impl Display for CorruptKind {
                     "journal revocation block table size is invalid: {size}"
                 )
             }
+            Self::JournalChecksumFeatures => {
+                write!(f, "journal has both v2 and v3 checksums enabled")
+            }
             Self::JournalSequenceOverflow => {
                 write!(f, "journal sequence number overflowed")
             }
@@ -579,6 +621,35 @@ impl Display for CorruptKind {
                     "invalid read of length {read_len} from block {block_index} (originally {original_block_index}) at offset {offset_within_block}"
                 )
             }
+            Self::XattrBlockHeader(inode) => write!(
+                f,
+                "extended attribute block of inode {inode} has an invalid header"
+            ),
+            Self::XattrBlockChecksum(inode) => write!(
+                f,
+                "extended attribute block of inode {inode} has an invalid checksum"
+            ),
+            Self::XattrEntry(inode) => {
+                write!(f, "invalid extended attribute entry in inode {inode}")
+            }
+            Self::XattrValue(inode) => write!(
+                f,
+                "extended attribute value in inode {inode} is out of bounds"
+            ),
+            Self::XattrAcl(inode) => {
+                write!(f, "invalid ACL extended attribute in inode {inode}")
+            }
+            Self::XattrInode { inode, ea_inode } => write!(
+                f,
+                "extended attribute in inode {inode} references invalid EA inode {ea_inode}"
+            ),
+            Self::XattrInodeHash { inode, ea_inode } => write!(
+                f,
+                "extended attribute in inode {inode} has an invalid hash for EA inode {ea_inode}"
+            ),
+            Self::InlineDataSize(inode) => {
+                write!(f, "inline data of inode {inode} is too small")
+            }
             Self::BlockCacheReadTooLarge {
                 num_blocks,
                 block_size,
@@ -657,12 +728,6 @@ pub(crate) enum IncompatibleKind {
         u8,
     ),
 
-    /// One or more required journal features are missing.
-    MissingRequiredJournalFeatures(
-        /// The missing feature bits.
-        u32,
-    ),
-
     /// One or more unsupported journal features are present.
     #[allow(clippy::enum_variant_names)]
     UnsupportedJournalFeatures(
@@ -704,9 +769,6 @@ impl Display for IncompatibleKind {
             Self::JournalChecksumType(val) => {
                 write!(f, "journal checksum type is not supported: {val}")
             }
-            Self::MissingRequiredJournalFeatures(feat) => {
-                write!(f, "missing required journal features: {feat:?}")
-            }
             Self::UnsupportedJournalFeatures(feat) => {
                 write!(f, "unsupported journal features: {feat:?}")
             }
@@ -19,4 +19,9 @@ pub(crate) struct Extent {
 
     // Number of blocks (both within the file, and on the filesystem).
     pub(crate) num_blocks: u16,
+
+    // Whether the extent is unwritten (also called uninitialized). The
+    // blocks of an unwritten extent are allocated, but have not been
+    // written to yet, so they must be read as zeros.
+    pub(crate) is_unwritten: bool,
 }
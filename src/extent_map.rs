@@ -0,0 +1,328 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::Ext4;
+use crate::block_index::FsBlockIndex;
+use crate::error::{CorruptKind, Ext4Error};
+use crate::file_attributes::FileAttributes;
+use crate::inode::Inode;
+use crate::iters::extents::Extents;
+use crate::iters::file_blocks::FileBlocks;
+use crate::journal::Journal;
+use alloc::vec::Vec;
+use bitflags::bitflags;
+
+bitflags! {
+    /// Flags describing a [`FileExtent`].
+    #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    pub struct FileExtentFlags: u32 {
+        /// The extent's blocks are allocated, but have not been written
+        /// to yet. The data reads as zeros.
+        const UNWRITTEN = 0x1;
+
+        /// The extent is a hole; no blocks are allocated. The data
+        /// reads as zeros, and the physical offset is zero.
+        const HOLE = 0x2;
+
+        /// This is the last extent in the file.
+        const LAST = 0x4;
+
+        /// The extent's data is stored in the journal, which contains a
+        /// newer copy of the blocks than the file's extent tree or block
+        /// map points to. The physical offset is the location within
+        /// the journal.
+        const JOURNAL = 0x8;
+    }
+}
+
+/// Range of a file's data, and where that data is stored within the
+/// filesystem.
+///
+/// See [`File::extent_map`].
+///
+/// [`File::extent_map`]: crate::File::extent_map
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct FileExtent {
+    logical_offset: u64,
+    physical_offset: u64,
+    len: u64,
+    flags: FileExtentFlags,
+}
+
+impl FileExtent {
+    /// Byte offset of the extent within the file.
+    #[must_use]
+    pub fn logical_offset(&self) -> u64 {
+        self.logical_offset
+    }
+
+    /// Byte offset of the extent within the filesystem. This is zero
+    /// for holes.
+    #[must_use]
+    pub fn physical_offset(&self) -> u64 {
+        self.physical_offset
+    }
+
+    /// Length of the extent in bytes. This is always a multiple of the
+    /// block size, so the last extent may extend past the end of the
+    /// file.
+    #[must_use]
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Extent flags.
+    #[must_use]
+    pub fn flags(&self) -> FileExtentFlags {
+        self.flags
+    }
+}
+
+/// Range of blocks used while building the extent map. Block indices
+/// are converted to byte offsets at the end.
+struct BlockRange {
+    block_within_file: u64,
+    /// Absolute block index, or zero for a hole.
+    start_block: FsBlockIndex,
+    num_blocks: u64,
+    is_unwritten: bool,
+    is_in_journal: bool,
+}
+
+impl BlockRange {
+    /// Get the absolute block index just past the end of the range,
+    /// or `None` for holes.
+    fn end_block(&self) -> Option<FsBlockIndex> {
+        if self.start_block == 0 {
+            None
+        } else {
+            self.start_block.checked_add(self.num_blocks)
+        }
+    }
+}
+
+/// Builds a list of `BlockRange`s, merging each range into the previous
+/// one if they are contiguous.
+#[derive(Default)]
+struct Builder {
+    ranges: Vec<BlockRange>,
+}
+
+impl Builder {
+    /// Add `num_blocks` blocks starting at `start_block`, or a hole if
+    /// `start_block` is zero. The new range must start where the
+    /// previous range ended within the file.
+    ///
+    /// Blocks that the journal contains a replacement for are mapped
+    /// through the journal, splitting the range as needed.
+    fn push(
+        &mut self,
+        journal: &Journal,
+        mut block_within_file: u64,
+        mut start_block: FsBlockIndex,
+        mut num_blocks: u64,
+        is_unwritten: bool,
+    ) {
+        if start_block != 0 {
+            let end_block = start_block.saturating_add(num_blocks);
+            for (block, mapped) in
+                journal.remapped_blocks(start_block..end_block)
+            {
+                // Add the part of the range before the remapped block.
+                // OK to unwrap: `block` is within `start_block..end_block`.
+                let len = block.checked_sub(start_block).unwrap();
+                self.push_range(
+                    block_within_file,
+                    start_block,
+                    len,
+                    is_unwritten,
+                    false,
+                );
+                self.push_range(
+                    block_within_file.saturating_add(len),
+                    mapped,
+                    1,
+                    is_unwritten,
+                    true,
+                );
+
+                // OK to unwrap: `len + 1` is at most `num_blocks`.
+                let consumed = len.checked_add(1).unwrap();
+                block_within_file = block_within_file.saturating_add(consumed);
+                start_block = block.saturating_add(1);
+                num_blocks = num_blocks.checked_sub(consumed).unwrap();
+            }
+        }
+        self.push_range(
+            block_within_file,
+            start_block,
+            num_blocks,
+            is_unwritten,
+            false,
+        );
+    }
+
+    /// Add a range of blocks without mapping them through the journal.
+    fn push_range(
+        &mut self,
+        block_within_file: u64,
+        start_block: FsBlockIndex,
+        num_blocks: u64,
+        is_unwritten: bool,
+        is_in_journal: bool,
+    ) {
+        if num_blocks == 0 {
+            return;
+        }
+
+        if let Some(prev) = self.ranges.last_mut() {
+            let is_contiguous = if start_block == 0 {
+                prev.start_block == 0
+            } else {
+                prev.is_unwritten == is_unwritten
+                    && prev.is_in_journal == is_in_journal
+                    && prev.end_block() == Some(start_block)
+            };
+            if is_contiguous {
+                // OK to unwrap: the number of blocks in a file fits in
+                // a `u32`, so the sum of two ranges within the file
+                // fits in a `u64`.
+                prev.num_blocks =
+                    prev.num_blocks.checked_add(num_blocks).unwrap();
+                return;
+            }
+        }
+
+        self.ranges.push(BlockRange {
+            block_within_file,
+            start_block,
+            num_blocks,
+            is_unwritten,
+            is_in_journal,
+        });
+    }
+
+    /// Convert the block ranges to `FileExtent`s.
+    fn build(
+        self,
+        fs: &Ext4,
+        inode: &Inode,
+    ) -> Result<Vec<FileExtent>, Ext4Error> {
+        let block_size = fs.0.superblock.block_size.to_u64();
+        let to_bytes = |block: u64| {
+            block
+                .checked_mul(block_size)
+                .ok_or(CorruptKind::ExtentBlock(inode.index))
+        };
+
+        let num_ranges = self.ranges.len();
+        self.ranges
+            .into_iter()
+            .enumerate()
+            .map(|(i, range)| {
+                let mut flags = FileExtentFlags::empty();
+                if range.start_block == 0 {
+                    flags |= FileExtentFlags::HOLE;
+                }
+                if range.is_unwritten {
+                    flags |= FileExtentFlags::UNWRITTEN;
+                }
+                if range.is_in_journal {
+                    flags |= FileExtentFlags::JOURNAL;
+                }
+                if Some(i) == num_ranges.checked_sub(1) {
+                    flags |= FileExtentFlags::LAST;
+                }
+
+                Ok(FileExtent {
+                    logical_offset: to_bytes(range.block_within_file)?,
+                    physical_offset: to_bytes(range.start_block)?,
+                    len: to_bytes(range.num_blocks)?,
+                    flags,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Get the extent map of `inode`.
+///
+/// Files with inline data have no blocks, so the map is empty.
+pub(crate) fn extent_map(
+    fs: &Ext4,
+    inode: &Inode,
+) -> Result<Vec<FileExtent>, Ext4Error> {
+    if inode.metadata.flags.contains(FileAttributes::INLINE_DATA) {
+        return Ok(Vec::new());
+    }
+
+    let journal = &fs.0.journal;
+    let num_blocks_total = u64::from(inode.file_size_in_blocks());
+    let mut builder = Builder::default();
+
+    // Index within the file of the next block to add to the map.
+    let mut next_block: u64 = 0;
+
+    if inode.metadata.flags.contains(FileAttributes::EXTENTS) {
+        for extent in Extents::new(fs.clone(), inode)? {
+            let extent = extent?;
+
+            // Extents are sorted, so any later extents are also past
+            // the end of the file.
+            let extent_start = u64::from(extent.block_within_file);
+            if extent_start >= num_blocks_total {
+                break;
+            }
+
+            // Add the hole (if any) before the extent.
+            if let Some(hole_len) = extent_start.checked_sub(next_block) {
+                builder.push(journal, next_block, 0, hole_len, false);
+            }
+
+            // Skip any part of the extent that overlaps the previous
+            // one, and clamp it to the end of the file.
+            let skip = next_block.saturating_sub(extent_start);
+            let start = extent_start.max(next_block);
+            let num_blocks = u64::from(extent.num_blocks)
+                .saturating_sub(skip)
+                .min(num_blocks_total.saturating_sub(start));
+            let start_block = extent
+                .start_block
+                .checked_add(skip)
+                .ok_or(CorruptKind::ExtentBlock(inode.index))?;
+            builder.push(
+                journal,
+                start,
+                start_block,
+                num_blocks,
+                extent.is_unwritten,
+            );
+
+            // OK to unwrap: `start + num_blocks` is at most
+            // `num_blocks_total`.
+            next_block = start.checked_add(num_blocks).unwrap();
+        }
+
+        // Add the hole (if any) at the end of the file.
+        if let Some(hole_len) = num_blocks_total.checked_sub(next_block) {
+            builder.push(journal, next_block, 0, hole_len, false);
+        }
+    } else {
+        // Block maps store each block individually; they are merged into
+        // contiguous ranges by the builder.
+        for block in FileBlocks::new(fs.clone(), inode)? {
+            builder.push(journal, next_block, block?, 1, false);
+            // OK to unwrap: the number of blocks in a file fits in a
+            // `u32`.
+            next_block = next_block.checked_add(1).unwrap();
+        }
+    }
+
+    builder.build(fs, inode)
+}
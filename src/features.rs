@@ -35,7 +35,11 @@ bitflags! {
         const IS_64BIT = 0x80;
         const MULTIPLE_MOUNT_PROTECTION = 0x100;
         const FLEXIBLE_BLOCK_GROUPS = 0x200;
+
+        /// Large extended attribute values may be stored in separate
+        /// inodes. This is the `ea_inode` feature.
         const LARGE_EXTENDED_ATTRIBUTES_IN_INODES = 0x400;
+
         const DATA_IN_DIR_ENTRY = 0x1000;
 
         /// The superblock contains the checksum seed. If not present,
@@ -43,7 +47,11 @@ bitflags! {
         const CHECKSUM_SEED_IN_SUPERBLOCK = 0x2000;
 
         const LARGE_DIRECTORIES = 0x4000;
+
+        /// Small files and directories may be stored directly in the
+        /// inode. This is the `inline_data` feature.
         const DATA_IN_INODE = 0x8000;
+
         const ENCRYPTED_INODES = 0x1_0000;
     }
 
@@ -78,5 +86,8 @@ bitflags! {
     #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
     pub struct CompatibleFeatures: u32 {
         const HAS_JOURNAL = 0x4;
+        /// Superblock backups are only stored in the (at most two)
+        /// block groups listed in the superblock.
+        const SPARSE_SUPER2 = 0x200;
     }
 }
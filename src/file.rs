@@ -6,19 +6,31 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use crate::Ext4;
-use crate::block_index::FsBlockIndex;
+use crate::block_index::{FileBlockIndex, FsBlockIndex};
 use crate::error::Ext4Error;
+use crate::extent_map::{self, FileExtent};
+use crate::file_attributes::FileAttributes;
+use crate::inline_data::read_inline_data;
 use crate::inode::Inode;
+use crate::iters::extents::Extents;
 use crate::iters::file_blocks::FileBlocks;
 use crate::metadata::Metadata;
 use crate::path::Path;
+use crate::read_ahead::{Advice, ReadAhead};
 use crate::resolve::FollowSymlinks;
 use crate::util::usize_from_u32;
+use crate::xattr;
+use crate::{DirectRead, Ext4};
+use alloc::vec;
+use alloc::vec::Vec;
 use core::fmt::{self, Debug, Formatter};
+use core::mem;
+use core::ops::Range;
 
 #[cfg(feature = "std")]
-use std::io::{self, ErrorKind, Read, Seek, SeekFrom};
+use crate::extent_map::FileExtentFlags;
+#[cfg(feature = "std")]
+use std::io::{self, ErrorKind, Read, Seek, SeekFrom, Write};
 
 /// An open file within an [`Ext4`] filesystem.
 pub struct File {
@@ -35,36 +47,46 @@ pub struct File {
     /// If `None`, either the next block needs to be fetched from the
     /// `file_blocks` iterator, or the end of the file has been reached.
     block_index: Option<FsBlockIndex>,
+
+    /// File data, if the data is stored inline in the inode.
+    inline_data: Option<Vec<u8>>,
+
+    /// Controls how many blocks are read into the block cache when
+    /// reading a block that is not cached.
+    read_ahead: ReadAhead,
 }
 
 impl File {
     /// Open the file at `path`.
     pub(crate) fn open(fs: &Ext4, path: Path<'_>) -> Result<Self, Ext4Error> {
         let inode = fs.path_to_inode(path, FollowSymlinks::All)?;
-
-        if inode.metadata.is_dir() {
-            return Err(Ext4Error::IsADirectory);
-        }
-        if !inode.metadata.file_type.is_regular_file() {
-            return Err(Ext4Error::IsASpecialFile);
-        }
+        check_is_regular_file(&inode)?;
 
         Self::open_inode(fs, inode)
     }
 
     /// Open `inode`. Note that unlike `File::open`, this allows any
     /// type of `inode` to be opened, including directories and
-    /// symlinks. This is used by `Ext4::read_inode_file`.
+    /// symlinks.
     pub(crate) fn open_inode(
         fs: &Ext4,
         inode: Inode,
     ) -> Result<Self, Ext4Error> {
+        let inline_data =
+            if inode.metadata.flags.contains(FileAttributes::INLINE_DATA) {
+                Some(read_inline_data(fs, &inode)?)
+            } else {
+                None
+            };
+
         Ok(Self {
             fs: fs.clone(),
             position: 0,
             file_blocks: FileBlocks::new(fs.clone(), &inode)?,
             inode,
             block_index: None,
+            inline_data,
+            read_ahead: ReadAhead::new(),
         })
     }
 
@@ -74,6 +96,45 @@ impl File {
         &self.inode.metadata
     }
 
+    /// Get the names of all extended attributes of the file.
+    ///
+    /// See [`Ext4::list_xattrs`].
+    pub fn list_xattrs(&self) -> Result<Vec<Vec<u8>>, Ext4Error> {
+        xattr::list_xattrs(&self.fs, &self.inode)
+    }
+
+    /// Get the value of the extended attribute `name` of the file.
+    ///
+    /// See [`Ext4::get_xattr`].
+    pub fn get_xattr<N: AsRef<[u8]>>(
+        &self,
+        name: N,
+    ) -> Result<Option<Vec<u8>>, Ext4Error> {
+        xattr::get_xattr(&self.fs, &self.inode, name.as_ref())
+    }
+
+    /// Get the locations of the file's data within the filesystem.
+    ///
+    /// The returned extents are sorted and cover the whole file, with
+    /// holes included as extents with the [`FileExtentFlags::HOLE`]
+    /// flag. Physically contiguous ranges are merged into a single
+    /// extent. This works for files that use extents as well as files
+    /// that use block maps (ext2/ext3).
+    ///
+    /// If the journal contains a newer copy of any of the file's
+    /// blocks, those blocks get their own extents with the
+    /// [`FileExtentFlags::JOURNAL`] flag, and the physical offset points
+    /// to the copy within the journal.
+    ///
+    /// Files with inline data are stored in the inode rather than in
+    /// data blocks, so the map is empty for those files.
+    ///
+    /// [`FileExtentFlags::HOLE`]: crate::FileExtentFlags::HOLE
+    /// [`FileExtentFlags::JOURNAL`]: crate::FileExtentFlags::JOURNAL
+    pub fn extent_map(&self) -> Result<Vec<FileExtent>, Ext4Error> {
+        extent_map::extent_map(&self.fs, &self.inode)
+    }
+
     /// Read bytes from the file into `buf`, returning how many bytes
     /// were read. The number may be smaller than the length of the
     /// input buffer.
@@ -83,6 +144,20 @@ impl File {
     /// entire file.
     ///
     /// Returns `Ok(0)` if the end of the file has been reached.
+    ///
+    /// If `buf` is at least [`Ext4Options::read_ahead_size`] bytes, the
+    /// read continues until `buf` is full, bypassing the block cache.
+    /// Blocks that are already cached are copied from the cache, and
+    /// each of the other runs of contiguous blocks is read directly
+    /// into `buf`, with all of the reads passed to a single
+    /// [`Ext4Read::read_many`] call.
+    ///
+    /// Smaller reads that extend past the end of the current block
+    /// continue through as many physically contiguous blocks as fit in
+    /// `buf`, and are read through the block cache.
+    ///
+    /// [`Ext4Options::read_ahead_size`]: crate::Ext4Options::read_ahead_size
+    /// [`Ext4Read::read_many`]: crate::Ext4Read::read_many
     pub fn read_bytes(
         &mut self,
         mut buf: &mut [u8],
@@ -120,6 +195,27 @@ impl File {
             }
         }
 
+        if let Some(inline_data) = &self.inline_data {
+            // OK to unwrap: inline data is held in memory, so the
+            // position (which is less than the file size) fits in a
+            // `usize`.
+            let start = usize::try_from(self.position).unwrap();
+            // OK to unwrap: the buffer length has been capped to the
+            // number of bytes remaining in the file.
+            let end = start.checked_add(buf.len()).unwrap();
+            buf.copy_from_slice(&inline_data[start..end]);
+
+            // OK to unwrap: the buffer length is capped such that this
+            // calculation is at most the length of the file, which
+            // fits in a `u64`.
+            self.position = self
+                .position
+                .checked_add(u64::try_from(buf.len()).unwrap())
+                .unwrap();
+
+            return Ok(buf.len());
+        }
+
         let block_size = self.fs.0.superblock.block_size;
 
         // Get the block to read from.
@@ -156,6 +252,14 @@ impl File {
             .checked_sub(offset_within_block)
             .unwrap();
 
+        // If the read continues past the end of this block, read as many
+        // contiguous blocks as possible with a single direct read.
+        if block_index != 0
+            && buf.len() > usize_from_u32(bytes_remaining_in_block)
+        {
+            return self.read_run(block_index, offset_within_block, buf);
+        }
+
         // If the output buffer is larger than the number of bytes
         // remaining in the block, shink the buffer.
         if buf.len() > usize_from_u32(bytes_remaining_in_block) {
@@ -167,12 +271,23 @@ impl File {
         // in a `u32`.
         let buf_len_u32: u32 = buf.len().try_into().unwrap();
 
-        // Read the block data, or zeros if in a hole.
+        // Record the access even for holes, so that sequential reads
+        // through a hole are still detected as sequential.
+        let window = self
+            .read_ahead
+            .access(self.position / block_size.to_nz_u64());
+
+        // Read the block data, or zeros if in a hole or unwritten
+        // extent.
         if block_index == 0 {
             buf.fill(0);
         } else {
-            self.fs
-                .read_from_block(block_index, offset_within_block, buf)?;
+            self.fs.read_data_from_block(
+                block_index,
+                offset_within_block,
+                buf,
+                max_blocks_to_read(window, &self.file_blocks),
+            )?;
         }
 
         // OK to unwrap: reads don't extend past a block, so this is at
@@ -196,6 +311,666 @@ impl File {
         Ok(buf.len())
     }
 
+    /// Read blocks starting at `offset_within_block` within
+    /// `first_block` into `buf`.
+    ///
+    /// If `buf` is at least as large as the read-ahead size, blocks are
+    /// taken from the `file_blocks` iterator until `buf` is full, and
+    /// read with [`read_runs_direct`]. This avoids copying the data
+    /// twice, and avoids evicting metadata from the cache.
+    ///
+    /// Otherwise, blocks are taken for as long as they are physically
+    /// contiguous (after journal remapping) and the run is shorter than
+    /// `buf`, and the run is read through the block cache.
+    ///
+    /// Returns the number of bytes read, and advances the position.
+    fn read_run(
+        &mut self,
+        first_block: FsBlockIndex,
+        offset_within_block: u32,
+        buf: &mut [u8],
+    ) -> Result<usize, Ext4Error> {
+        let block_size = self.fs.0.superblock.block_size;
+        let journal = &self.fs.0.journal;
+        let direct =
+            buf.len() >= usize_from_u32(self.fs.0.options.read_ahead_size);
+
+        let mut runs = vec![ReadRun::new(
+            first_block,
+            journal.map_block_index(first_block),
+        )];
+        // Number of blocks in the read that were remapped through the
+        // journal.
+        let mut num_remaps = usize::from(runs[0].is_remapped());
+
+        // Last block in the read, before journal remapping.
+        let mut last_block = first_block;
+        // Number of bytes in the runs, starting from
+        // `offset_within_block`.
+        //
+        // OK to unwrap: `offset_within_block` is less than the block
+        // size.
+        let mut run_len = usize_from_u32(
+            block_size
+                .to_u32()
+                .checked_sub(offset_within_block)
+                .unwrap(),
+        );
+        // The block after the run, if one was taken from the iterator
+        // but not added to the read.
+        let mut next_block = None;
+
+        while run_len < buf.len() {
+            match self.file_blocks.next() {
+                Some(Ok(block)) => {
+                    let mapped_block = journal.map_block_index(block);
+                    // OK to unwrap: `runs` is never empty.
+                    let run = runs.last_mut().unwrap();
+                    if !run.try_extend(block, mapped_block) {
+                        if !direct {
+                            next_block = Some(block);
+                            break;
+                        }
+                        runs.push(ReadRun::new(block, mapped_block));
+                    }
+                    if block != 0 && mapped_block != block {
+                        // OK to unwrap: at most the number of blocks in
+                        // the read.
+                        num_remaps = num_remaps.checked_add(1).unwrap();
+                    }
+                    last_block = block;
+                    // OK to unwrap: the read is shorter than `buf`, so
+                    // this cannot overflow.
+                    run_len =
+                        run_len.checked_add(block_size.to_usize()).unwrap();
+                }
+                Some(Err(err)) => {
+                    // Restart iteration at the current position so that
+                    // the file can still be read after the error.
+                    self.seek_to(self.position)?;
+                    return Err(err);
+                }
+                None => break,
+            }
+        }
+
+        let read_len = run_len.min(buf.len());
+
+        // Record the access to the whole read, so that sequential reads
+        // are still detected.
+        //
+        // OK to unwrap: `read_len` is nonzero, and the read ends within
+        // the file.
+        let first_block_within_file = self.position / block_size.to_nz_u64();
+        let last_block_within_file = self
+            .position
+            .checked_add(u64::try_from(read_len).unwrap())
+            .and_then(|end| end.checked_sub(1))
+            .unwrap()
+            / block_size.to_nz_u64();
+        let window = self
+            .read_ahead
+            .access_run(first_block_within_file, last_block_within_file);
+
+        let result = if direct {
+            read_runs_direct(
+                &self.fs,
+                &runs,
+                offset_within_block,
+                &mut buf[..read_len],
+            )
+        } else {
+            // Read at least the blocks covered by `buf`, plus any
+            // read-ahead allowed by the window. If the run ended because
+            // `buf` is full, read-ahead can continue past the run.
+            //
+            // OK to unwrap: the last block is not before the first, and
+            // the read is shorter than the read-ahead size, which is a
+            // `u32`.
+            let blocks_in_read = last_block_within_file
+                .checked_sub(first_block_within_file)
+                .and_then(|n| u32::try_from(n).ok())
+                .and_then(|n| n.checked_add(1))
+                .unwrap();
+            let blocks_after_run = if next_block.is_none() {
+                self.file_blocks.contiguous_blocks_remaining()
+            } else {
+                0
+            };
+            let max_blocks = window.max(blocks_in_read).min(
+                u32::try_from(runs[0].num_blocks)
+                    .unwrap_or(u32::MAX)
+                    .saturating_add(blocks_after_run),
+            );
+            self.fs.read_data_from_run(
+                runs[0].mapped_block,
+                first_block,
+                offset_within_block,
+                &mut buf[..read_len],
+                max_blocks,
+            )
+        };
+        if let Err(err) = result {
+            self.seek_to(self.position)?;
+            return Err(err);
+        }
+        self.fs.0.stats.record_journal_remaps(num_remaps);
+
+        // If the read stopped partway through the last block, that
+        // block is still the current block. Otherwise the next block is
+        // the one after the read.
+        self.block_index = if read_len < run_len {
+            Some(last_block)
+        } else {
+            next_block
+        };
+
+        // OK to unwrap: the buffer length is capped such that this
+        // calculation is at most the length of the file, which fits in
+        // a `u64`.
+        self.position = self
+            .position
+            .checked_add(u64::try_from(read_len).unwrap())
+            .unwrap();
+
+        Ok(read_len)
+    }
+
+    /// Read bytes starting at `offset` into `buf`, returning how many
+    /// bytes were read.
+    ///
+    /// Unlike [`File::read_bytes`], this does not use or change the
+    /// current position of the file, so it can be used to read from
+    /// arbitrary places within the file through a shared reference.
+    /// This matches the behavior of `std::os::unix::fs::FileExt::read_at`.
+    ///
+    /// The number of bytes read is only smaller than the length of
+    /// `buf` if the end of the file is reached. Returns `Ok(0)` if
+    /// `offset` is at or past the end of the file.
+    ///
+    /// Like [`File::read_bytes`], reads of at least
+    /// [`Ext4Options::read_ahead_size`] bytes bypass the block cache,
+    /// with a single [`Ext4Read::read_many`] call for all of the
+    /// uncached blocks.
+    ///
+    /// [`Ext4Options::read_ahead_size`]: crate::Ext4Options::read_ahead_size
+    /// [`Ext4Read::read_many`]: crate::Ext4Read::read_many
+    pub fn read_at(
+        &self,
+        offset: u64,
+        mut buf: &mut [u8],
+    ) -> Result<usize, Ext4Error> {
+        let file_size = self.inode.metadata.size_in_bytes;
+
+        // Nothing to do if the output buffer is empty or the offset is
+        // at or past the end of the file.
+        if buf.is_empty() || offset >= file_size {
+            return Ok(0);
+        }
+
+        // Shrink the buffer if it extends past the end of the file.
+        //
+        // OK to unwrap: just checked that `offset` is less than the
+        // file size.
+        let bytes_remaining = file_size.checked_sub(offset).unwrap();
+        if let Ok(bytes_remaining) = usize::try_from(bytes_remaining) {
+            if buf.len() > bytes_remaining {
+                buf = &mut buf[..bytes_remaining];
+            }
+        }
+        let read_len = buf.len();
+
+        if let Some(inline_data) = &self.inline_data {
+            // OK to unwrap: inline data is held in memory, so the
+            // offset (which is less than the file size) fits in a
+            // `usize`.
+            let start = usize::try_from(offset).unwrap();
+            // OK to unwrap: the buffer length has been capped to the
+            // number of bytes remaining in the file.
+            let end = start.checked_add(read_len).unwrap();
+            buf.copy_from_slice(&inline_data[start..end]);
+            return Ok(read_len);
+        }
+
+        let block_size = self.fs.0.superblock.block_size;
+
+        // OK to unwrap: the offset is less than the file size, and the
+        // number of blocks in a file fits in a `u32`.
+        let block_within_file =
+            FileBlockIndex::try_from(offset / block_size.to_nz_u64()).unwrap();
+        let mut file_blocks = FileBlocks::new_at(
+            self.fs.clone(),
+            &self.inode,
+            block_within_file,
+        )?;
+
+        // OK to unwrap: block size fits in a `u32`, so an offset within
+        // the block will as well.
+        let mut offset_within_block: u32 =
+            u32::try_from(offset % block_size.to_nz_u64()).unwrap();
+
+        // Reads that are at least as large as the read-ahead size bypass
+        // the block cache, and all of the file's runs of blocks within
+        // the read are read at once.
+        if read_len >= usize_from_u32(self.fs.0.options.read_ahead_size) {
+            let journal = &self.fs.0.journal;
+            let mut runs: Vec<ReadRun> = Vec::new();
+            let mut num_remaps: usize = 0;
+            // Number of bytes in the runs, starting from
+            // `offset_within_block`.
+            //
+            // OK to unwrap: `offset_within_block` is less than the
+            // block size.
+            let mut runs_len = usize_from_u32(
+                block_size
+                    .to_u32()
+                    .checked_sub(offset_within_block)
+                    .unwrap(),
+            );
+            loop {
+                // OK to unwrap: the buffer has been capped to the end
+                // of the file, so there must be at least one more block
+                // to read.
+                let block = file_blocks.next().unwrap()?;
+                let mapped_block = journal.map_block_index(block);
+                if !runs
+                    .last_mut()
+                    .is_some_and(|run| run.try_extend(block, mapped_block))
+                {
+                    runs.push(ReadRun::new(block, mapped_block));
+                }
+                // OK to unwrap: at most the number of blocks in the
+                // read.
+                num_remaps = num_remaps
+                    .checked_add(usize::from(mapped_block != block))
+                    .unwrap();
+
+                if runs_len >= read_len {
+                    break;
+                }
+                // OK to unwrap: the runs only extend past `read_len` by
+                // less than a block.
+                runs_len = runs_len.checked_add(block_size.to_usize()).unwrap();
+            }
+
+            read_runs_direct(&self.fs, &runs, offset_within_block, buf)?;
+            self.fs.0.stats.record_journal_remaps(num_remaps);
+            return Ok(read_len);
+        }
+
+        while !buf.is_empty() {
+            // OK to unwrap: the buffer has been capped to the end of the
+            // file, so there must be at least one more block to read.
+            let block_index = file_blocks.next().unwrap()?;
+
+            // OK to unwrap: `offset_within_block` is always less than
+            // the block size.
+            let bytes_remaining_in_block = block_size
+                .to_u32()
+                .checked_sub(offset_within_block)
+                .unwrap();
+            let chunk_len =
+                buf.len().min(usize_from_u32(bytes_remaining_in_block));
+            let (chunk, rest) = buf.split_at_mut(chunk_len);
+
+            // Read the block data, or zeros if in a hole or unwritten
+            // extent.
+            if block_index == 0 {
+                chunk.fill(0);
+            } else {
+                self.fs.read_data_from_block(
+                    block_index,
+                    offset_within_block,
+                    chunk,
+                    max_blocks_to_read(self.read_ahead.window(), &file_blocks),
+                )?;
+            }
+
+            buf = rest;
+            offset_within_block = 0;
+        }
+
+        Ok(read_len)
+    }
+
+    /// Read exactly enough bytes starting at `offset` to fill `buf`.
+    ///
+    /// Like [`File::read_at`], this does not use or change the current
+    /// position of the file.
+    ///
+    /// Returns [`Ext4Error::UnexpectedEof`] if the end of the file is
+    /// reached before `buf` is filled. The contents of `buf` are
+    /// unspecified in that case.
+    pub fn read_exact_at(
+        &self,
+        offset: u64,
+        buf: &mut [u8],
+    ) -> Result<(), Ext4Error> {
+        if self.read_at(offset, buf)? == buf.len() {
+            Ok(())
+        } else {
+            Err(Ext4Error::UnexpectedEof)
+        }
+    }
+
+    /// Give a hint about how the file will be accessed.
+    ///
+    /// [`Advice::Sequential`], [`Advice::Random`], and
+    /// [`Advice::Normal`] control how many blocks are read into the
+    /// block cache when a block that is not cached is read. As on
+    /// Linux, these apply to the whole file rather than to `range`. In
+    /// all cases, blocks are only read ahead within the current
+    /// extent, so read-ahead does not pull in data from unrelated
+    /// files.
+    ///
+    /// [`Advice::WillNeed`] reads the blocks in `range` into the block
+    /// cache immediately. Later reads of those blocks are served from
+    /// the cache, including large reads that otherwise bypass it (see
+    /// [`File::read_bytes`]). Prefetching more data than the block
+    /// cache can hold is wasted.
+    ///
+    /// The hints only affect performance; the data read is the same.
+    /// The file position is not changed.
+    pub fn advise(
+        &mut self,
+        range: Range<u64>,
+        advice: Advice,
+    ) -> Result<(), Ext4Error> {
+        if advice == Advice::WillNeed {
+            self.prefetch(range)
+        } else {
+            self.read_ahead.set_advice(advice);
+            Ok(())
+        }
+    }
+
+    /// Read the blocks containing `range` into the block cache.
+    fn prefetch(&self, range: Range<u64>) -> Result<(), Ext4Error> {
+        let end = range.end.min(self.inode.metadata.size_in_bytes);
+        if self.inline_data.is_some() || range.start >= end {
+            return Ok(());
+        }
+
+        let block_size = self.fs.0.superblock.block_size.to_nz_u64();
+        let first_block = range.start / block_size;
+        // OK to unwrap: `end` is greater than `range.start`, which is at
+        // least zero.
+        let last_block = end.checked_sub(1).unwrap() / block_size;
+        // OK to unwrap: the start offset is less than the file size,
+        // and the number of blocks in a file fits in a `u32`.
+        let mut file_blocks = FileBlocks::new_at(
+            self.fs.clone(),
+            &self.inode,
+            FileBlockIndex::try_from(first_block).unwrap(),
+        )?;
+
+        for block_within_file in first_block..=last_block {
+            let Some(block_index) = file_blocks.next().transpose()? else {
+                break;
+            };
+            if block_index == 0 {
+                continue;
+            }
+
+            // Read the rest of the range (within the extent) at once.
+            // Later blocks in the range will then be cache hits.
+            //
+            // OK to unwrap: `block_within_file` is at most `last_block`.
+            let blocks_in_range =
+                last_block.checked_sub(block_within_file).unwrap();
+            let window = u32::try_from(blocks_in_range)
+                .unwrap_or(u32::MAX)
+                .saturating_add(1);
+
+            // An empty read is enough to load the block into the cache.
+            self.fs.read_data_from_block(
+                block_index,
+                0,
+                &mut [],
+                max_blocks_to_read(window, &file_blocks),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Seek to the first byte of data at or after `offset`, returning
+    /// the new position.
+    ///
+    /// This is equivalent to `lseek` with `SEEK_DATA`. Holes and
+    /// unwritten extents are not considered data; everything else is,
+    /// even if it contains zeros.
+    ///
+    /// Returns `Ok(None)` if `offset` is at or past the end of the file,
+    /// or if there is no data after `offset`. The position is not
+    /// changed in that case.
+    pub fn seek_data(&mut self, offset: u64) -> Result<Option<u64>, Ext4Error> {
+        let position = self.find_offset(offset, false)?;
+        if let Some(position) = position {
+            self.seek_to(position)?;
+        }
+        Ok(position)
+    }
+
+    /// Seek to the first byte of a hole at or after `offset`, returning
+    /// the new position.
+    ///
+    /// This is equivalent to `lseek` with `SEEK_HOLE`. Unwritten
+    /// extents are treated as holes. There is an implicit hole at the
+    /// end of the file, so if there are no holes after `offset`, this
+    /// seeks to the end of the file.
+    ///
+    /// Returns `Ok(None)` if `offset` is at or past the end of the file.
+    /// The position is not changed in that case.
+    pub fn seek_hole(&mut self, offset: u64) -> Result<Option<u64>, Ext4Error> {
+        let position = self.find_offset(offset, true)?;
+        if let Some(position) = position {
+            self.seek_to(position)?;
+        }
+        Ok(position)
+    }
+
+    /// Find the first byte at or after `offset` that is in a hole (if
+    /// `find_hole` is true) or in data (if `find_hole` is false). The
+    /// end of the file counts as a hole.
+    ///
+    /// The search starts at `offset` rather than at the beginning of
+    /// the file, so only the extents (or blocks, for block maps) from
+    /// `offset` up to the result are visited.
+    fn find_offset(
+        &self,
+        offset: u64,
+        find_hole: bool,
+    ) -> Result<Option<u64>, Ext4Error> {
+        let file_size = self.inode.metadata.size_in_bytes;
+        if offset >= file_size {
+            return Ok(None);
+        }
+
+        // Inline data contains no holes.
+        if self.inline_data.is_some() {
+            return Ok(Some(if find_hole { file_size } else { offset }));
+        }
+
+        let block_size = self.fs.0.superblock.block_size.to_nz_u64();
+        // OK to unwrap: `offset` is less than the file size, and the
+        // number of blocks in a file fits in a `u32`.
+        let start_block =
+            FileBlockIndex::try_from(offset / block_size).unwrap();
+
+        let found_block =
+            if self.inode.metadata.flags.contains(FileAttributes::EXTENTS) {
+                self.find_block_in_extents(start_block, find_hole)?
+            } else {
+                self.find_block_in_block_map(start_block, find_hole)?
+            };
+
+        let found = found_block
+            .and_then(|block| {
+                if block == u64::from(start_block) {
+                    Some(offset)
+                } else {
+                    block.checked_mul(block_size.get())
+                }
+            })
+            .filter(|position| *position < file_size);
+
+        if find_hole {
+            Ok(Some(found.unwrap_or(file_size)))
+        } else {
+            Ok(found)
+        }
+    }
+
+    /// Find the first block at or after `start_block` that is in a hole
+    /// (including unwritten extents) if `find_hole` is true, or in data
+    /// if `find_hole` is false, by walking the extent tree.
+    ///
+    /// Returns `None` if there is no such block before the end of the
+    /// extents. Everything after the last extent is a hole.
+    fn find_block_in_extents(
+        &self,
+        start_block: FileBlockIndex,
+        find_hole: bool,
+    ) -> Result<Option<u64>, Ext4Error> {
+        // Index within the file of the next block to check.
+        let mut next_block = u64::from(start_block);
+
+        for extent in
+            Extents::new_at(self.fs.clone(), &self.inode, start_block)?
+        {
+            let extent = extent?;
+            let extent_start = u64::from(extent.block_within_file);
+            let extent_end =
+                extent_start.saturating_add(u64::from(extent.num_blocks));
+            if extent_end <= next_block {
+                continue;
+            }
+
+            // Check the hole (if any) before the extent.
+            if extent_start > next_block && find_hole {
+                return Ok(Some(next_block));
+            }
+
+            if extent.is_unwritten == find_hole {
+                return Ok(Some(extent_start.max(next_block)));
+            }
+            next_block = extent_end;
+        }
+
+        Ok(find_hole.then_some(next_block))
+    }
+
+    /// Find the first block at or after `start_block` that is in a hole
+    /// if `find_hole` is true, or in data if `find_hole` is false, by
+    /// iterating over the block map.
+    fn find_block_in_block_map(
+        &self,
+        start_block: FileBlockIndex,
+        find_hole: bool,
+    ) -> Result<Option<u64>, Ext4Error> {
+        let file_blocks =
+            FileBlocks::new_at(self.fs.clone(), &self.inode, start_block)?;
+        for (block, block_index) in (u64::from(start_block)..).zip(file_blocks)
+        {
+            if (block_index? == 0) == find_hole {
+                return Ok(Some(block));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Get the byte ranges of the file that contain data, in order.
+    /// Holes and unwritten extents are skipped, and adjacent data
+    /// extents are merged.
+    #[cfg(feature = "std")]
+    fn data_ranges(&self) -> Result<Vec<Range<u64>>, Ext4Error> {
+        let file_size = self.inode.metadata.size_in_bytes;
+
+        // Inline data contains no holes.
+        if self.inline_data.is_some() {
+            return Ok(alloc::vec![0..file_size]);
+        }
+
+        let mut ranges: Vec<Range<u64>> = Vec::new();
+        for extent in extent_map::extent_map(&self.fs, &self.inode)? {
+            // Holes and unwritten extents read as zeros.
+            if extent
+                .flags()
+                .intersects(FileExtentFlags::HOLE | FileExtentFlags::UNWRITTEN)
+            {
+                continue;
+            }
+            let start = extent.logical_offset();
+            let end = start.saturating_add(extent.len()).min(file_size);
+            if start >= end {
+                continue;
+            }
+            match ranges.last_mut() {
+                Some(prev) if prev.end == start => prev.end = end,
+                _ => ranges.push(start..end),
+            }
+        }
+        Ok(ranges)
+    }
+
+    /// Copy the file's data to `dst`, without writing the file's holes.
+    ///
+    /// Only the data ranges of the file (as found by
+    /// [`File::extent_map`]) are written. Holes and unwritten extents
+    /// read as zeros, so they are skipped over, and the length of `dst`
+    /// is set to the file size at the end. If `dst` is a newly-created
+    /// (or truncated) file on a filesystem that supports sparse files,
+    /// the holes are preserved.
+    ///
+    /// This does not use or change the current position of the file.
+    /// Data is written to `dst` at the same offset it has within this
+    /// file. Returns the number of data bytes copied.
+    #[cfg(feature = "std")]
+    pub fn copy_sparse_to(&self, dst: &mut std::fs::File) -> io::Result<u64> {
+        const CHUNK_SIZE: usize = 128 * 1024;
+
+        let file_size = self.inode.metadata.size_in_bytes;
+        let mut buf = alloc::vec![0; CHUNK_SIZE];
+        let mut num_copied: u64 = 0;
+
+        // The extent map is built once up front, rather than searching
+        // for each data range with `find_offset`.
+        for Range {
+            start: data_start,
+            end: data_end,
+        } in self.data_ranges()?
+        {
+            dst.seek(SeekFrom::Start(data_start))?;
+            let mut pos = data_start;
+            while pos < data_end {
+                // OK to unwrap: `pos` is less than `data_end`.
+                let remaining = data_end.checked_sub(pos).unwrap();
+                let chunk_len = usize::try_from(remaining)
+                    .unwrap_or(usize::MAX)
+                    .min(buf.len());
+                let chunk = &mut buf[..chunk_len];
+                self.read_exact_at(pos, chunk)?;
+                dst.write_all(chunk)?;
+
+                // OK to unwrap: `pos + chunk_len` is at most
+                // `data_end`.
+                pos =
+                    pos.checked_add(u64::try_from(chunk_len).unwrap()).unwrap();
+            }
+
+            // OK to unwrap: `data_end` is at most the file size, which
+            // fits in a `u64`.
+            num_copied = num_copied
+                .checked_add(data_end.checked_sub(data_start).unwrap())
+                .unwrap();
+        }
+
+        dst.set_len(file_size)?;
+
+        Ok(num_copied)
+    }
+
     /// Current position within the file.
     #[must_use]
     pub fn position(&self) -> u64 {
@@ -206,17 +981,20 @@ impl File {
     ///
     /// Seeking past the end of the file is allowed.
     pub fn seek_to(&mut self, position: u64) -> Result<(), Ext4Error> {
-        // Reset iteration.
-        self.file_blocks = FileBlocks::new(self.fs.clone(), &self.inode)?;
+        // Restart iteration at the block containing `position`. If the
+        // block index doesn't fit in a `FileBlockIndex`, it's past the
+        // end of the file.
+        let block_within_file =
+            position / self.fs.0.superblock.block_size.to_nz_u64();
+        let block_within_file = FileBlockIndex::try_from(block_within_file)
+            .unwrap_or(FileBlockIndex::MAX);
+        self.file_blocks = FileBlocks::new_at(
+            self.fs.clone(),
+            &self.inode,
+            block_within_file,
+        )?;
         self.block_index = None;
 
-        // Advance the block iterator by the number of whole blocks in
-        // `position`.
-        let num_blocks = position / self.fs.0.superblock.block_size.to_nz_u64();
-        for _ in 0..num_blocks {
-            self.file_blocks.next();
-        }
-
         self.position = position;
 
         Ok(())
@@ -236,6 +1014,130 @@ impl Debug for File {
     }
 }
 
+/// Get the maximum number of blocks to read into the block cache,
+/// starting with the block most recently yielded by `file_blocks`.
+///
+/// This is `window`, limited to the blocks that are known to be
+/// physically contiguous with the current block.
+fn max_blocks_to_read(window: u32, file_blocks: &FileBlocks) -> u32 {
+    window.min(file_blocks.contiguous_blocks_remaining().saturating_add(1))
+}
+
+/// Run of blocks within a read from a file. The blocks are either all
+/// holes, or physically contiguous after journal remapping.
+struct ReadRun {
+    /// First block of the run, before journal remapping. Zero for a
+    /// hole.
+    block: FsBlockIndex,
+
+    /// First block of the run, after journal remapping.
+    mapped_block: FsBlockIndex,
+
+    /// Number of blocks in the run.
+    num_blocks: u64,
+}
+
+impl ReadRun {
+    fn new(block: FsBlockIndex, mapped_block: FsBlockIndex) -> Self {
+        Self {
+            block,
+            mapped_block,
+            num_blocks: 1,
+        }
+    }
+
+    /// Whether the first block of the run was remapped through the
+    /// journal.
+    fn is_remapped(&self) -> bool {
+        self.block != self.mapped_block
+    }
+
+    /// Add `block` to the end of the run if it continues the run.
+    /// Returns false if it does not.
+    fn try_extend(
+        &mut self,
+        block: FsBlockIndex,
+        mapped_block: FsBlockIndex,
+    ) -> bool {
+        let is_contiguous = if block == 0 {
+            self.block == 0
+        } else {
+            self.block != 0
+                && self.mapped_block.checked_add(self.num_blocks)
+                    == Some(mapped_block)
+        };
+        if is_contiguous {
+            // OK to unwrap: the number of blocks in a file fits in a
+            // `u32`.
+            self.num_blocks = self.num_blocks.checked_add(1).unwrap();
+        }
+        is_contiguous
+    }
+}
+
+/// Read `runs` into `buf`, starting at `offset_within_block` within
+/// the first run. `buf` must not extend past the end of the runs.
+///
+/// Holes are filled with zeros. All other blocks are read with a single
+/// call to [`Ext4::read_from_blocks_direct`], except for blocks that
+/// are already cached.
+fn read_runs_direct(
+    fs: &Ext4,
+    runs: &[ReadRun],
+    mut offset_within_block: u32,
+    mut buf: &mut [u8],
+) -> Result<(), Ext4Error> {
+    let block_size = fs.0.superblock.block_size;
+
+    let mut reads = Vec::with_capacity(runs.len());
+    for run in runs {
+        if buf.is_empty() {
+            break;
+        }
+
+        // OK to unwrap: the number of blocks and the block size both
+        // fit in a `u32`, so the product fits in a `u64`, and
+        // `offset_within_block` is less than the block size. If the run
+        // length doesn't fit in a `usize`, it is larger than `buf`.
+        let run_len = run
+            .num_blocks
+            .checked_mul(block_size.to_u64())
+            .and_then(|len| len.checked_sub(u64::from(offset_within_block)))
+            .unwrap();
+        let len = usize::try_from(run_len)
+            .unwrap_or(usize::MAX)
+            .min(buf.len());
+        let (chunk, rest) = mem::take(&mut buf).split_at_mut(len);
+        buf = rest;
+
+        if run.block == 0 {
+            chunk.fill(0);
+        } else {
+            reads.push(DirectRead {
+                block_index: run.mapped_block,
+                original_block_index: run.block,
+                offset_within_block,
+                dst: chunk,
+            });
+        }
+        offset_within_block = 0;
+    }
+
+    fs.read_from_blocks_direct(reads)
+}
+
+/// Check that `inode` is a regular file. Returns `IsADirectory` or
+/// `IsASpecialFile` if not.
+pub(crate) fn check_is_regular_file(inode: &Inode) -> Result<(), Ext4Error> {
+    if inode.metadata.is_dir() {
+        return Err(Ext4Error::IsADirectory);
+    }
+    if !inode.metadata.file_type.is_regular_file() {
+        return Err(Ext4Error::IsASpecialFile);
+    }
+    Ok(())
+}
+
 #[cfg(feature = "std")]
 impl Read for File {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
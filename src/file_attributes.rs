@@ -0,0 +1,123 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use bitflags::bitflags;
+
+bitflags! {
+    /// Inode flags, also known as file attributes.
+    ///
+    /// Some of these flags can be viewed with [lsattr] and set with
+    /// [chattr]. The letter used by those tools is noted in the
+    /// documentation of each flag. Other flags are managed internally
+    /// by the filesystem.
+    ///
+    /// [chattr]: https://www.man7.org/linux/man-pages/man1/chattr.1.html
+    /// [lsattr]: https://www.man7.org/linux/man-pages/man1/lsattr.1.html
+    #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+    pub struct FileAttributes: u32 {
+        /// Secure deletion (`s`). Not implemented by ext4.
+        const SECURE_DELETION = 0x1;
+
+        /// Undelete (`u`). Not implemented by ext4.
+        const UNDELETE = 0x2;
+
+        /// Compressed file (`c`). Not implemented by ext4.
+        const COMPRESSED = 0x4;
+
+        /// Writes are synchronous (`S`).
+        const SYNC = 0x8;
+
+        /// File is immutable (`i`).
+        const IMMUTABLE = 0x10;
+
+        /// File can only be appended to (`a`).
+        const APPEND_ONLY = 0x20;
+
+        /// File is excluded from backups by `dump` (`d`).
+        const NO_DUMP = 0x40;
+
+        /// Access time is not updated (`A`).
+        const NO_ATIME = 0x80;
+
+        /// Reserved for compression. Not implemented by ext4.
+        const DIRTY = 0x100;
+
+        /// Reserved for compression. Not implemented by ext4.
+        const COMPRESSED_BLOCKS = 0x200;
+
+        /// Don't compress (`m`). Not implemented by ext4.
+        const NO_COMPRESSION = 0x400;
+
+        /// File or directory is encrypted (`E`).
+        const ENCRYPTED = 0x800;
+
+        /// Directory has hashed indexes (`I`).
+        const DIRECTORY_HTREE = 0x1000;
+
+        /// AFS magic directory.
+        const IMAGIC = 0x2000;
+
+        /// File data is written through the journal (`j`).
+        const JOURNAL_DATA = 0x4000;
+
+        /// File tail should not be merged (`t`). Not implemented by
+        /// ext4.
+        const NO_TAIL = 0x8000;
+
+        /// Directory changes are synchronous (`D`).
+        const DIRECTORY_SYNC = 0x1_0000;
+
+        /// Top of a directory hierarchy, used by the block allocator
+        /// (`T`).
+        const TOP_DIRECTORY = 0x2_0000;
+
+        /// File is huge; its block count is in filesystem blocks rather
+        /// than 512-byte units (`h`).
+        const HUGE_FILE = 0x4_0000;
+
+        /// Inode uses extents (`e`).
+        const EXTENTS = 0x8_0000;
+
+        /// Verity protected data (`V`).
+        const VERITY = 0x10_0000;
+
+        /// Inode stores a large extended attribute value in its data
+        /// blocks.
+        const EA_INODE = 0x20_0000;
+
+        /// Blocks allocated past the end of the file. Deprecated.
+        const EOF_BLOCKS = 0x40_0000;
+
+        /// Inode is a snapshot. Reserved for the out-of-tree
+        /// snapshot feature.
+        const SNAPSHOT = 0x100_0000;
+
+        /// File uses direct access (DAX) (`x`).
+        const DAX = 0x200_0000;
+
+        /// Snapshot is being deleted. Reserved for the out-of-tree
+        /// snapshot feature.
+        const SNAPSHOT_DELETED = 0x400_0000;
+
+        /// Snapshot shrink has completed. Reserved for the out-of-tree
+        /// snapshot feature.
+        const SNAPSHOT_SHRUNK = 0x800_0000;
+
+        /// Inode has inline data (`N`).
+        const INLINE_DATA = 0x1000_0000;
+
+        /// Children inherit the project ID of this directory (`P`).
+        const PROJECT_INHERIT = 0x2000_0000;
+
+        /// Directory lookups are case-insensitive (`F`).
+        const CASEFOLD = 0x4000_0000;
+
+        /// Reserved for ext4 library use.
+        const RESERVED = 0x8000_0000;
+    }
+}
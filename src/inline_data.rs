@@ -0,0 +1,153 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::Ext4;
+use crate::error::{CorruptKind, Ext4Error};
+use crate::inode::{Inode, InodeIndex};
+use crate::util::read_u32le;
+use crate::xattr;
+use alloc::vec::Vec;
+
+/// Name of the xattr that holds inline data that doesn't fit in
+/// `i_block`.
+const SYSTEM_DATA: &[u8] = b"system.data";
+
+/// Size of the parent inode field at the start of an inline directory.
+const PARENT_SIZE: usize = 4;
+
+/// Read the data of an inode that has the `INLINE_DATA` flag set.
+///
+/// The first 60 bytes are stored in `i_block`. If the data is larger
+/// than that, the rest is stored in the value of the `system.data`
+/// xattr.
+///
+/// The returned data is truncated to the size of the file.
+pub(crate) fn read_inline_data(
+    ext4: &Ext4,
+    inode: &Inode,
+) -> Result<Vec<u8>, Ext4Error> {
+    let size = usize::try_from(inode.metadata.size_in_bytes)
+        .map_err(|_| CorruptKind::InlineDataSize(inode.index))?;
+
+    let mut data = inode.inline_data.to_vec();
+    if size > data.len() {
+        if let Some(value) = xattr::get_xattr(ext4, inode, SYSTEM_DATA)? {
+            data.extend(value);
+        }
+    }
+
+    if size > data.len() {
+        return Err(CorruptKind::InlineDataSize(inode.index).into());
+    }
+    data.truncate(size);
+
+    Ok(data)
+}
+
+/// Read the raw entries of a directory that has the `INLINE_DATA` flag
+/// set.
+///
+/// Inline directories don't store `.` and `..` entries. Instead, the
+/// first four bytes of `i_block` contain the parent's inode index,
+/// followed by regular directory entries. Entries that don't fit in
+/// `i_block` are stored in the `system.data` xattr.
+///
+/// To allow the result to be parsed like a regular directory block,
+/// entries for `.` and `..` are synthesized and placed at the start of
+/// the returned data.
+pub(crate) fn read_inline_dir(
+    ext4: &Ext4,
+    inode: &Inode,
+) -> Result<Vec<u8>, Ext4Error> {
+    let data = read_inline_data(ext4, inode)?;
+    inline_dir_entries(inode.index, &data)
+}
+
+/// Convert inline directory `data` into regular directory entries. See
+/// [`read_inline_dir`].
+fn inline_dir_entries(
+    inode: InodeIndex,
+    data: &[u8],
+) -> Result<Vec<u8>, Ext4Error> {
+    if data.len() < PARENT_SIZE {
+        return Err(CorruptKind::InlineDataSize(inode).into());
+    }
+    let parent = read_u32le(data, 0);
+    if parent == 0 {
+        return Err(CorruptKind::DirEntry(inode).into());
+    }
+
+    let mut entries = Vec::new();
+    push_dot_entry(&mut entries, inode.get(), b".");
+    push_dot_entry(&mut entries, parent, b"..");
+    entries.extend(&data[PARENT_SIZE..]);
+    Ok(entries)
+}
+
+/// Append a directory entry for `.` or `..` to `entries`.
+fn push_dot_entry(entries: &mut Vec<u8>, inode: u32, name: &[u8]) {
+    /// File type of a directory, as stored in a directory entry.
+    const DIR_FILE_TYPE: u8 = 2;
+
+    // Size of the entry: eight bytes of header, plus the name padded to
+    // four bytes.
+    const REC_LEN: u16 = 12;
+
+    entries.extend(inode.to_le_bytes());
+    entries.extend(REC_LEN.to_le_bytes());
+    // OK to unwrap: name is either `.` or `..`.
+    entries.push(u8::try_from(name.len()).unwrap());
+    entries.push(DIR_FILE_TYPE);
+    entries.extend(name);
+    entries.resize(entries.len().next_multiple_of(4), 0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::num::NonZero;
+
+    #[test]
+    fn test_inline_dir_entries() {
+        let inode = NonZero::new(12).unwrap();
+
+        // Parent inode, followed by one entry that fills the rest of
+        // `i_block`.
+        let mut data = 2u32.to_le_bytes().to_vec();
+        data.extend(13u32.to_le_bytes());
+        data.extend(56u16.to_le_bytes());
+        data.extend([1, 1]);
+        data.extend(b"a");
+        data.resize(60, 0);
+
+        // Additional entry, as would be stored in `system.data`.
+        data.extend(14u32.to_le_bytes());
+        data.extend(12u16.to_le_bytes());
+        data.extend([1, 1]);
+        data.extend(b"bcd");
+        data.resize(72, 0);
+
+        let mut expected = vec![12, 0, 0, 0, 12, 0, 1, 2, b'.', 0, 0, 0];
+        expected.extend([2, 0, 0, 0, 12, 0, 2, 2, b'.', b'.', 0, 0]);
+        expected.extend(&data[4..]);
+
+        assert_eq!(inline_dir_entries(inode, &data).unwrap(), expected);
+
+        // Too short to contain the parent.
+        assert_eq!(
+            inline_dir_entries(inode, &[1, 2]).unwrap_err(),
+            CorruptKind::InlineDataSize(inode)
+        );
+
+        // Invalid parent.
+        assert_eq!(
+            inline_dir_entries(inode, &[0; 60]).unwrap_err(),
+            CorruptKind::DirEntry(inode)
+        );
+    }
+}
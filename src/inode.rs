@@ -9,14 +9,19 @@
 use crate::Ext4;
 use crate::block_index::FsBlockIndex;
 use crate::checksum::Checksum;
+use crate::device_number::DeviceNumber;
 use crate::error::{CorruptKind, Ext4Error};
+use crate::features::{IncompatibleFeatures, ReadOnlyCompatibleFeatures};
+use crate::file_attributes::FileAttributes;
 use crate::file_type::FileType;
 use crate::metadata::Metadata;
 use crate::path::PathBuf;
+use crate::timestamp::Timestamp;
 use crate::util::{
     read_u16le, read_u32le, u32_from_hilo, u64_from_hilo, usize_from_u32,
 };
 use alloc::vec;
+use alloc::vec::Vec;
 use bitflags::bitflags;
 use core::num::NonZeroU32;
 
@@ -25,38 +30,6 @@ use core::num::NonZeroU32;
 /// This is always nonzero.
 pub(crate) type InodeIndex = NonZeroU32;
 
-bitflags! {
-    /// Inode flags.
-    #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
-    pub(crate) struct InodeFlags: u32 {
-        /// File is immutable.
-        const IMMUTABLE = 0x10;
-
-        /// Directory is encrypted.
-        const DIRECTORY_ENCRYPTED = 0x800;
-
-        /// Directory has hashed indexes.
-        const DIRECTORY_HTREE = 0x1000;
-
-        /// File is huge.
-        const HUGE_FILE = 0x4_0000;
-
-        /// Inode uses extents.
-        const EXTENTS = 0x8_0000;
-
-        /// Verity protected data.
-        const VERITY = 0x10_0000;
-
-        /// Inode stores a large extended attribute value in its data blocks.
-        const EXTENDED_ATTRIBUTES = 0x20_0000;
-
-        /// Inode has inline data.
-        const INLINE_DATA = 0x1000_0000;
-
-        // TODO: other flags
-    }
-}
-
 bitflags! {
     /// Inode mode.
     ///
@@ -104,9 +77,6 @@ pub(crate) struct Inode {
 
     pub(crate) metadata: Metadata,
 
-    /// Internal inode flags.
-    pub(crate) flags: InodeFlags,
-
     /// Checksum seed used in various places.
     pub(crate) checksum_base: Checksum,
 
@@ -117,8 +87,13 @@ pub(crate) struct Inode {
 impl Inode {
     const INLINE_DATA_LEN: usize = 60;
     const L_I_CHECKSUM_LO_OFFSET: usize = 0x74 + 0x8;
+    const I_EXTRA_ISIZE_OFFSET: usize = 0x80;
     const I_CHECKSUM_HI_OFFSET: usize = 0x82;
 
+    /// Size of the original ext2 inode. Fields past this point are only
+    /// present if `i_extra_isize` is large enough to include them.
+    const GOOD_OLD_INODE_SIZE: usize = 128;
+
     /// Load an inode from `bytes`.
     ///
     /// If successful, returns a tuple containing the inode and its
@@ -152,12 +127,21 @@ impl Inode {
         let i_mode = read_u16le(data, 0x0);
         let i_uid = read_u16le(data, 0x2);
         let i_size_lo = read_u32le(data, 0x4);
+        let i_atime = read_u32le(data, 0x8);
+        let i_ctime = read_u32le(data, 0xc);
+        let i_mtime = read_u32le(data, 0x10);
         let i_gid = read_u16le(data, 0x18);
+        let i_links_count = read_u16le(data, 0x1a);
+        let i_blocks_lo = read_u32le(data, 0x1c);
         let i_flags = read_u32le(data, 0x20);
+        let l_i_version = read_u32le(data, 0x24);
         // OK to unwrap: already checked the length.
         let i_block = data.get(0x28..0x28 + Self::INLINE_DATA_LEN).unwrap();
         let i_generation = read_u32le(data, 0x64);
+        let i_file_acl_lo = read_u32le(data, 0x68);
         let i_size_high = read_u32le(data, 0x6c);
+        let l_i_blocks_high = read_u16le(data, 0x74);
+        let l_i_file_acl_high = read_u16le(data, 0x74 + 0x2);
         let l_i_uid_high = read_u16le(data, 0x74 + 0x4);
         let l_i_gid_high = read_u16le(data, 0x74 + 0x6);
         let (l_i_checksum_lo, i_checksum_hi) = if ext4.has_metadata_checksums()
@@ -172,20 +156,88 @@ impl Inode {
             (0, 0)
         };
 
+        // Read a `u32` field from the extended part of the inode. Returns
+        // `None` if the field is not within `i_extra_isize`.
+        let i_extra_isize = if data.len() > Self::GOOD_OLD_INODE_SIZE {
+            usize::from(read_u16le(data, Self::I_EXTRA_ISIZE_OFFSET))
+        } else {
+            0
+        };
+        let read_extra_u32 = |offset: usize| -> Option<u32> {
+            // OK to unwrap: all offsets passed in are small constants.
+            let field_end = offset.checked_add(4).unwrap();
+            let extra_end =
+                Self::GOOD_OLD_INODE_SIZE.checked_add(i_extra_isize)?;
+            if field_end <= extra_end && field_end <= data.len() {
+                Some(read_u32le(data, offset))
+            } else {
+                None
+            }
+        };
+        let i_ctime_extra = read_extra_u32(0x84);
+        let i_mtime_extra = read_extra_u32(0x88);
+        let i_atime_extra = read_extra_u32(0x8c);
+        let i_crtime = read_extra_u32(0x90);
+        let i_crtime_extra = read_extra_u32(0x94);
+        let i_version_hi = read_extra_u32(0x98);
+
+        let sb = &ext4.0.superblock;
+        let flags = FileAttributes::from_bits_retain(i_flags);
+
         let size_in_bytes = u64_from_hilo(i_size_high, i_size_lo);
         let uid = u32_from_hilo(l_i_uid_high, i_uid);
         let gid = u32_from_hilo(l_i_gid_high, i_gid);
         let checksum = u32_from_hilo(i_checksum_hi, l_i_checksum_lo);
         let mode = InodeMode::from_bits_retain(i_mode);
+        let version = u64_from_hilo(i_version_hi.unwrap_or(0), l_i_version);
 
-        let mut checksum_base =
-            Checksum::with_seed(ext4.0.superblock.checksum_seed);
+        // The high bits of the xattr block are only valid on 64-bit
+        // filesystems.
+        let file_acl = if sb
+            .incompatible_features
+            .contains(IncompatibleFeatures::IS_64BIT)
+        {
+            u64_from_hilo(u32::from(l_i_file_acl_high), i_file_acl_lo)
+        } else {
+            u64::from(i_file_acl_lo)
+        };
+
+        // Get the number of 512-byte units allocated to the file. See
+        // `ext4_inode_blocks` in the kernel.
+        let blocks = if sb
+            .read_only_compatible_features
+            .contains(ReadOnlyCompatibleFeatures::HUGE_FILES)
+        {
+            let blocks = u64_from_hilo(u32::from(l_i_blocks_high), i_blocks_lo);
+            if flags.contains(FileAttributes::HUGE_FILE) {
+                // The count is in filesystem blocks rather than
+                // 512-byte units. Saturate rather than fail, a
+                // corrupt count shouldn't prevent reading the file.
+                blocks.saturating_mul(sb.block_size.to_u64() / 512)
+            } else {
+                blocks
+            }
+        } else {
+            u64::from(i_blocks_lo)
+        };
+
+        let file_type = FileType::try_from(mode)
+            .map_err(|_| CorruptKind::InodeFileType { inode: index, mode })?;
+
+        // Device files store the device number in `i_block`.
+        let rdev = if file_type.is_char_dev() || file_type.is_block_dev() {
+            Some(DeviceNumber::from_i_block(i_block))
+        } else {
+            None
+        };
+
+        let mut checksum_base = Checksum::with_seed(sb.checksum_seed);
         checksum_base.update_u32_le(index.get());
         checksum_base.update_u32_le(i_generation);
 
         let file_size_in_blocks: u32 = size_in_bytes
             // Round up.
-            .div_ceil(ext4.0.superblock.block_size.to_u64())
+            .div_ceil(sb.block_size.to_u64())
             // Ext4 allows at most `2^32` blocks in a file.
             .try_into()
             .map_err(|_| CorruptKind::TooManyBlocksInFile)?;
@@ -200,11 +252,22 @@ impl Inode {
                     mode,
                     uid,
                     gid,
-                    file_type: FileType::try_from(mode).map_err(|_| {
-                        CorruptKind::InodeFileType { inode: index, mode }
-                    })?,
+                    file_type,
+                    atime: Timestamp::from_raw(i_atime, i_atime_extra),
+                    ctime: Timestamp::from_raw(i_ctime, i_ctime_extra),
+                    mtime: Timestamp::from_raw(i_mtime, i_mtime_extra),
+                    crtime: i_crtime.map(|i_crtime| {
+                        Timestamp::from_raw(i_crtime, i_crtime_extra)
+                    }),
+                    inode: index,
+                    links_count: i_links_count,
+                    blocks,
+                    generation: i_generation,
+                    version,
+                    file_acl,
+                    flags,
+                    rdev,
                 },
-                flags: InodeFlags::from_bits_retain(i_flags),
                 checksum_base,
                 file_size_in_blocks,
             },
@@ -212,21 +275,53 @@ impl Inode {
         ))
     }
 
-    /// Read an inode.
-    pub(crate) fn read(
+    /// Read the raw on-disk data of an inode.
+    ///
+    /// The data is not validated.
+    pub(crate) fn read_bytes(
         ext4: &Ext4,
         inode: InodeIndex,
-    ) -> Result<Self, Ext4Error> {
+    ) -> Result<Vec<u8>, Ext4Error> {
         let (block_index, offset_within_block) =
             get_inode_location(ext4, inode)?;
 
         let mut data = vec![0; usize::from(ext4.0.superblock.inode_size)];
         ext4.read_from_block(block_index, offset_within_block, &mut data)?;
+        Ok(data)
+    }
+
+    /// Get the offset of the in-inode extended attribute area within
+    /// the raw inode `data`. This area starts right after
+    /// `i_extra_isize` bytes of extra fields.
+    ///
+    /// Returns `None` if there is no room for the area's four-byte
+    /// magic number.
+    pub(crate) fn in_inode_xattr_offset(data: &[u8]) -> Option<usize> {
+        if data.len() <= Self::GOOD_OLD_INODE_SIZE {
+            return None;
+        }
+        let i_extra_isize =
+            usize::from(read_u16le(data, Self::I_EXTRA_ISIZE_OFFSET));
+        let offset = Self::GOOD_OLD_INODE_SIZE.checked_add(i_extra_isize)?;
+        if offset.checked_add(4)? <= data.len() {
+            Some(offset)
+        } else {
+            None
+        }
+    }
+
+    /// Read an inode.
+    pub(crate) fn read(
+        ext4: &Ext4,
+        inode: InodeIndex,
+    ) -> Result<Self, Ext4Error> {
+        let data = Self::read_bytes(ext4, inode)?;
 
         let (inode, expected_checksum) = Self::from_bytes(ext4, inode, &data)?;
 
         // Verify the inode checksum.
-        if ext4.has_metadata_checksums() {
+        if ext4.verify_checksums() {
+            ext4.record_checksum_verification();
             let mut checksum = inode.checksum_base.clone();
 
             // Hash all the inode data, but treat the two checksum
@@ -318,15 +413,15 @@ fn get_inode_location(
 
     let block_group_index = inode_minus_1 / sb.inodes_per_block_group;
 
-    let group = ext4
-        .0
-        .block_group_descriptors
-        .get(usize_from_u32(block_group_index))
-        .ok_or(CorruptKind::InodeBlockGroup {
+    if block_group_index >= sb.num_block_groups {
+        return Err(CorruptKind::InodeBlockGroup {
             inode,
             block_group: block_group_index,
-            num_block_groups: ext4.0.block_group_descriptors.len(),
-        })?;
+            num_block_groups: usize_from_u32(sb.num_block_groups),
+        }
+        .into());
+    }
+    let group = ext4.block_group_descriptor(block_group_index)?;
 
     let index_within_group = inode_minus_1 % sb.inodes_per_block_group;
 
@@ -7,6 +7,7 @@
 // except according to those terms.
 
 use crate::Ext4;
+use crate::block_index::{FileBlockIndex, FsBlockIndex};
 use crate::checksum::Checksum;
 use crate::error::{CorruptKind, Ext4Error};
 use crate::extent::Extent;
@@ -146,6 +147,44 @@ impl ToVisitItem {
         let end = start.checked_add(ENTRY_SIZE_IN_BYTES).unwrap();
         self.node.get(start..end)
     }
+
+    /// Get the number of entries whose first block is less than or
+    /// equal to `block`.
+    ///
+    /// Entries within a node are sorted by their first block, so this
+    /// is found with a binary search. The first block of an entry is
+    /// stored in the same place for both leaf and internal nodes.
+    fn num_entries_at_or_before(&self, block: FileBlockIndex) -> usize {
+        // OK to unwrap: `ToVisitItem::new` checked that the node
+        // contains at least the header.
+        let num_entries = (self.node.len() / ENTRY_SIZE_IN_BYTES)
+            .checked_sub(1)
+            .unwrap();
+        let first_block = |index: usize| {
+            // OK to unwrap: `index` is less than `num_entries`, so the
+            // offset is within the node, and the node size fits in a
+            // `usize`.
+            let offset = index
+                .checked_add(1)
+                .and_then(|i| i.checked_mul(ENTRY_SIZE_IN_BYTES))
+                .unwrap();
+            read_u32le(&self.node, offset)
+        };
+
+        let mut low = 0;
+        let mut high = num_entries;
+        while low < high {
+            let mid = low.midpoint(high);
+            if first_block(mid) <= block {
+                // OK to unwrap: `mid` is less than `high`, so adding
+                // one cannot overflow.
+                low = mid.checked_add(1).unwrap();
+            } else {
+                high = mid;
+            }
+        }
+        low
+    }
 }
 
 /// Iterator of an inode's extent tree.
@@ -171,6 +210,137 @@ impl Extents {
         })
     }
 
+    /// Create an iterator that starts at the extent containing
+    /// `block_within_file`. If that block is in a hole, the iterator
+    /// starts at the first extent after the hole.
+    ///
+    /// Rather than iterating over all the extents before
+    /// `block_within_file`, this descends directly through the tree,
+    /// reading one node per level.
+    pub(crate) fn new_at(
+        ext4: Ext4,
+        inode: &Inode,
+        block_within_file: FileBlockIndex,
+    ) -> Result<Self, Ext4Error> {
+        let mut extents = Self::new(ext4, inode)?;
+        extents.seek(block_within_file)?;
+        Ok(extents)
+    }
+
+    /// Position the iterator at `block`. This must only be called on
+    /// a new iterator.
+    fn seek(&mut self, block: FileBlockIndex) -> Result<(), Ext4Error> {
+        loop {
+            // OK to unwrap: the root node is added in `new`, and nodes
+            // are never removed in this loop.
+            let item = self.to_visit.last_mut().unwrap();
+            let num_before = item.num_entries_at_or_before(block);
+
+            if item.depth == 0 {
+                // OK to unwrap: the number of entries is at most
+                // `2^16-1`.
+                item.entry = u32::try_from(num_before).unwrap();
+
+                // Check if the last extent starting at or before
+                // `block` also contains it. If so, step back so that
+                // extent is the next one yielded. Otherwise `block` is
+                // in a hole, and the next extent yielded is the one
+                // after the hole.
+                //
+                // Entry 0 is the header, so there's nothing to check if
+                // all extents start after `block`.
+                if let Some(entry) = item.entry().filter(|_| num_before > 0) {
+                    let ee_block = read_u32le(entry, 0);
+                    let (num_blocks, _) =
+                        decode_extent_len(read_u16le(entry, 4));
+                    let contains_block = ee_block
+                        .checked_add(u32::from(num_blocks))
+                        .is_none_or(|end| block < end);
+                    if contains_block {
+                        // OK to unwrap: `num_before` is nonzero.
+                        item.entry = item.entry.checked_sub(1).unwrap();
+                    }
+                }
+
+                return Ok(());
+            }
+
+            // Descend into the last child that starts at or before
+            // `block`. If all children start after `block`, then
+            // `block` is in a hole at the start of this node, so
+            // descend into the first child.
+            //
+            // OK to unwrap: the number of entries is at most `2^16-1`.
+            item.entry = u32::try_from(num_before.max(1)).unwrap();
+            let Some(entry) = item.entry() else {
+                // Empty node; nothing to descend into.
+                return Ok(());
+            };
+            let child_block = child_block(entry);
+            let parent_depth = item.depth;
+            let child = self.read_child_node(child_block)?;
+
+            // Each level must be closer to the leaves than its parent,
+            // otherwise a corrupt tree could cause an infinite loop.
+            if child.depth >= parent_depth {
+                return Err(CorruptKind::ExtentDepth(self.inode).into());
+            }
+            self.to_visit.push(child);
+        }
+    }
+
+    /// Read the child node at `child_block`.
+    fn read_child_node(
+        &self,
+        child_block: FsBlockIndex,
+    ) -> Result<ToVisitItem, Ext4Error> {
+        // Read just the header of the child node. This is needed to
+        // find out how much data is in the full child node.
+        let mut child_header = [0; ENTRY_SIZE_IN_BYTES];
+        self.ext4
+            .read_from_block(child_block, 0, &mut child_header)?;
+        let child_header = NodeHeader::from_bytes(&child_header, self.inode)?;
+
+        // The checksum is written in the four bytes directly after
+        // the node.
+        let checksum_offset = child_header.checksum_offset();
+        let checksum_size = if self.ext4.has_metadata_checksums() {
+            4
+        } else {
+            0
+        };
+
+        // OK to unwrap: per `checksum_offset()` the maximum offset
+        // is 786432, so the maximum sum here is 786436, which fits
+        // in a `u32`. We assume `usize` is at least as big as a
+        // `u32`.
+        let child_node_size: usize =
+            checksum_offset.checked_add(checksum_size).unwrap();
+        // Extent nodes are not allowed to exceed the block size.
+        if child_node_size > self.ext4.0.superblock.block_size {
+            return Err(CorruptKind::ExtentNodeSize(self.inode).into());
+        }
+        let mut child_node = vec![0; child_node_size];
+        self.ext4.read_from_block(child_block, 0, &mut child_node)?;
+
+        // Validating the checksum here covers everything but the
+        // root node. The root node is embedded within the inode,
+        // which has its own checksum.
+        if self.ext4.verify_checksums() {
+            self.ext4.record_checksum_verification();
+            let expected_checksum = read_u32le(&child_node, checksum_offset);
+
+            let mut checksum = self.checksum_base.clone();
+            checksum.update(&child_node[..checksum_offset]);
+            let actual_checksum = checksum.finalize();
+            if expected_checksum != actual_checksum {
+                return Err(CorruptKind::ExtentChecksum(self.inode).into());
+            }
+        }
+
+        ToVisitItem::new(child_node, self.inode)
+    }
+
     // Step to the next entry.
     //
     // This is factored out of `Iterator::next` for clarity and ease of
@@ -215,64 +385,18 @@ impl Extents {
 
             let start_block =
                 u64_from_hilo(u32::from(ee_start_hi), ee_start_low);
+            let (num_blocks, is_unwritten) = decode_extent_len(ee_len);
 
             return Ok(Some(Extent {
                 block_within_file: ee_block,
                 start_block,
-                num_blocks: ee_len,
+                num_blocks,
+                is_unwritten,
             }));
         } else {
-            let ei_leaf_lo = read_u32le(entry, 4);
-            let ei_leaf_hi = read_u16le(entry, 8);
-            let child_block = u64_from_hilo(u32::from(ei_leaf_hi), ei_leaf_lo);
-
-            // Read just the header of the child node. This is needed to
-            // find out how much data is in the full child node.
-            let mut child_header = [0; ENTRY_SIZE_IN_BYTES];
-            self.ext4
-                .read_from_block(child_block, 0, &mut child_header)?;
-            let child_header =
-                NodeHeader::from_bytes(&child_header, self.inode)?;
-
-            // The checksum is written in the four bytes directly after
-            // the node.
-            let checksum_offset = child_header.checksum_offset();
-            let checksum_size = if self.ext4.has_metadata_checksums() {
-                4
-            } else {
-                0
-            };
-
-            // OK to unwrap: per `checksum_offset()` the maximum offset
-            // is 786432, so the maximum sum here is 786436, which fits
-            // in a `u32`. We assume `usize` is at least as big as a
-            // `u32`.
-            let child_node_size: usize =
-                checksum_offset.checked_add(checksum_size).unwrap();
-            // Extent nodes are not allowed to exceed the block size.
-            if child_node_size > self.ext4.0.superblock.block_size {
-                return Err(CorruptKind::ExtentNodeSize(self.inode).into());
-            }
-            let mut child_node = vec![0; child_node_size];
-            self.ext4.read_from_block(child_block, 0, &mut child_node)?;
-
-            // Validating the checksum here covers everything but the
-            // root node. The root node is embedded within the inode,
-            // which has its own checksum.
-            if self.ext4.has_metadata_checksums() {
-                let expected_checksum =
-                    read_u32le(&child_node, checksum_offset);
-
-                let mut checksum = self.checksum_base.clone();
-                checksum.update(&child_node[..checksum_offset]);
-                let actual_checksum = checksum.finalize();
-                if expected_checksum != actual_checksum {
-                    return Err(CorruptKind::ExtentChecksum(self.inode).into());
-                }
-            }
-
-            self.to_visit
-                .push(ToVisitItem::new(child_node, self.inode)?);
+            let child_block = child_block(entry);
+            let child = self.read_child_node(child_block)?;
+            self.to_visit.push(child);
         }
 
         // This does not indicate end of iteration, we just haven't
@@ -282,3 +406,42 @@ impl Extents {
 }
 
 impl_result_iter!(Extents, Extent);
+
+/// Get the block index of the child node that an internal node entry
+/// points to.
+fn child_block(entry: &[u8]) -> FsBlockIndex {
+    let ei_leaf_lo = read_u32le(entry, 4);
+    let ei_leaf_hi = read_u16le(entry, 8);
+    u64_from_hilo(u32::from(ei_leaf_hi), ei_leaf_lo)
+}
+
+/// Decode the `ee_len` field of a leaf node entry. Returns the number
+/// of blocks in the extent and whether the extent is unwritten.
+///
+/// An initialized extent can contain at most 32768 blocks. Values
+/// greater than that indicate an unwritten extent, with a length of
+/// `ee_len - 32768`.
+fn decode_extent_len(ee_len: u16) -> (u16, bool) {
+    const MAX_INITIALIZED_LEN: u16 = 32768;
+
+    if ee_len > MAX_INITIALIZED_LEN {
+        // OK to unwrap: just checked that `ee_len` is greater.
+        (ee_len.checked_sub(MAX_INITIALIZED_LEN).unwrap(), true)
+    } else {
+        (ee_len, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_extent_len() {
+        assert_eq!(decode_extent_len(0), (0, false));
+        assert_eq!(decode_extent_len(1), (1, false));
+        assert_eq!(decode_extent_len(32768), (32768, false));
+        assert_eq!(decode_extent_len(32769), (1, true));
+        assert_eq!(decode_extent_len(65535), (32767, true));
+    }
+}
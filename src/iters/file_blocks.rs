@@ -9,8 +9,9 @@
 mod block_map;
 mod extents_blocks;
 
-use crate::block_index::FsBlockIndex;
-use crate::inode::{Inode, InodeFlags};
+use crate::block_index::{FileBlockIndex, FsBlockIndex};
+use crate::file_attributes::FileAttributes;
+use crate::inode::Inode;
 use crate::{Ext4, Ext4Error};
 use block_map::BlockMap;
 use extents_blocks::ExtentsBlocks;
@@ -22,17 +23,24 @@ use extents_blocks::ExtentsBlocks;
 enum FileBlocksInner {
     ExtentsBlocks(ExtentsBlocks),
     BlockMap(BlockMap),
+
+    /// The file's data is stored inline in the inode, so there are no
+    /// blocks to iterate over.
+    InlineData,
 }
 
 /// Iterator over blocks in a file.
 ///
 /// The iterator produces absolute block indices. A block index of zero
-/// indicates a hole.
+/// indicates a hole, or a block that has been allocated but not yet
+/// written.
 pub(crate) struct FileBlocks(FileBlocksInner);
 
 impl FileBlocks {
     pub(crate) fn new(fs: Ext4, inode: &Inode) -> Result<Self, Ext4Error> {
-        if inode.flags.contains(InodeFlags::EXTENTS) {
+        if inode.metadata.flags.contains(FileAttributes::INLINE_DATA) {
+            Ok(Self(FileBlocksInner::InlineData))
+        } else if inode.metadata.flags.contains(FileAttributes::EXTENTS) {
             Ok(Self(FileBlocksInner::ExtentsBlocks(ExtentsBlocks::new(
                 fs, inode,
             )?)))
@@ -40,6 +48,52 @@ impl FileBlocks {
             Ok(Self(FileBlocksInner::BlockMap(BlockMap::new(fs, inode))))
         }
     }
+
+    /// Create an iterator that starts at `block_within_file`.
+    ///
+    /// This looks up the starting block directly, without iterating
+    /// over the blocks before it. The cost is one block read per level
+    /// of the extent tree or block map.
+    pub(crate) fn new_at(
+        fs: Ext4,
+        inode: &Inode,
+        block_within_file: FileBlockIndex,
+    ) -> Result<Self, Ext4Error> {
+        if inode.metadata.flags.contains(FileAttributes::INLINE_DATA) {
+            Ok(Self(FileBlocksInner::InlineData))
+        } else if inode.metadata.flags.contains(FileAttributes::EXTENTS) {
+            Ok(Self(FileBlocksInner::ExtentsBlocks(ExtentsBlocks::new_at(
+                fs,
+                inode,
+                block_within_file,
+            )?)))
+        } else {
+            Ok(Self(FileBlocksInner::BlockMap(BlockMap::new_at(
+                fs,
+                inode,
+                block_within_file,
+            )?)))
+        }
+    }
+
+    /// Get the number of blocks following the most recently yielded
+    /// block that are known to be physically contiguous with it.
+    ///
+    /// For files that use extents, this is the number of remaining
+    /// blocks in the current extent. For files that use block maps,
+    /// this is the number of following entries in the current block of
+    /// the map that continue the run.
+    pub(crate) fn contiguous_blocks_remaining(&self) -> u32 {
+        match self {
+            Self(FileBlocksInner::ExtentsBlocks(iter)) => {
+                iter.contiguous_blocks_remaining()
+            }
+            Self(FileBlocksInner::BlockMap(iter)) => {
+                iter.contiguous_blocks_remaining()
+            }
+            Self(FileBlocksInner::InlineData) => 0,
+        }
+    }
 }
 
 impl Iterator for FileBlocks {
@@ -50,6 +104,7 @@ impl Iterator for FileBlocks {
         match self {
             Self(FileBlocksInner::ExtentsBlocks(iter)) => iter.next(),
             Self(FileBlocksInner::BlockMap(iter)) => iter.next(),
+            Self(FileBlocksInner::InlineData) => None,
         }
     }
 }
@@ -6,12 +6,14 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use crate::block_index::FileBlockIndex;
 use crate::inode::Inode;
 use crate::iters::file_blocks::FsBlockIndex;
-use crate::util::read_u32le;
+use crate::util::{read_u32le, usize_from_u32};
 use crate::{Ext4, Ext4Error};
 use alloc::vec;
 use alloc::vec::Vec;
+use core::num::NonZero;
 
 /// Block map iterator.
 ///
@@ -55,6 +57,10 @@ pub(super) struct BlockMap {
     /// Number of blocks the iterator has yielded so far.
     num_blocks_yielded: u32,
 
+    /// The most recently yielded block, or `None` if no blocks have
+    /// been yielded since the iterator was created.
+    last_block: Option<FsBlockIndex>,
+
     /// Total number of blocks in the file.
     num_blocks_total: u32,
 
@@ -82,6 +88,7 @@ impl BlockMap {
             fs,
             level_0,
             num_blocks_yielded: 0,
+            last_block: None,
             num_blocks_total: inode.file_size_in_blocks(),
             level_0_index: 0,
             level_1: None,
@@ -91,6 +98,115 @@ impl BlockMap {
         }
     }
 
+    /// Create an iterator that starts at `block_within_file`.
+    ///
+    /// The position of the block within the map is calculated directly
+    /// from `block_within_file`, so at most one block is read for each
+    /// level of indirection.
+    pub(super) fn new_at(
+        fs: Ext4,
+        inode: &Inode,
+        block_within_file: FileBlockIndex,
+    ) -> Result<Self, Ext4Error> {
+        let mut block_map = Self::new(fs, inode);
+        block_map.seek(block_within_file)?;
+        Ok(block_map)
+    }
+
+    /// Position the iterator at `block`. This must only be called on
+    /// a new iterator.
+    fn seek(&mut self, block: FileBlockIndex) -> Result<(), Ext4Error> {
+        if block >= self.num_blocks_total {
+            self.num_blocks_yielded = self.num_blocks_total;
+            return Ok(());
+        }
+        self.num_blocks_yielded = block;
+
+        let num_direct: u64 = 12;
+        let n = entries_per_block(&self.fs);
+        // OK to unwrap: the largest block size is 64KiB, so `n` is at
+        // most 16384 and the square fits in a `u64`.
+        let n_squared = n.get().checked_mul(n.get()).unwrap();
+
+        let mut index = u64::from(block);
+        if index < num_direct {
+            // OK to unwrap: `index` is less than 12.
+            self.level_0_index = usize::try_from(index).unwrap();
+            return Ok(());
+        }
+
+        // OK to unwrap: just checked that `index` is at least
+        // `num_direct`.
+        index = index.checked_sub(num_direct).unwrap();
+        if index < n.get() {
+            self.level_0_index = 12;
+            self.level_1 = Some(IndirectBlockIter::new_at(
+                self.fs.clone(),
+                self.level_0[12],
+                index,
+            )?);
+            return Ok(());
+        }
+
+        // OK to unwrap: just checked that `index` is at least `n`.
+        index = index.checked_sub(n.get()).unwrap();
+        if index < n_squared {
+            self.level_0_index = 13;
+            self.level_2 = Some(DoubleIndirectBlockIter::new_at(
+                self.fs.clone(),
+                self.level_0[13],
+                index,
+            )?);
+            return Ok(());
+        }
+
+        // OK to unwrap: just checked that `index` is at least
+        // `n_squared`.
+        index = index.checked_sub(n_squared).unwrap();
+        self.level_0_index = 14;
+        self.level_3 = Some(TripleIndirectBlockIter::new_at(
+            self.fs.clone(),
+            self.level_0[14],
+            index,
+        )?);
+        Ok(())
+    }
+
+    /// Get the number of blocks following the most recently yielded
+    /// block that are physically contiguous with it.
+    ///
+    /// Only block indices that have already been read are checked, so
+    /// this stops at the end of the current direct or indirect block.
+    /// Returns zero if the most recently yielded block was a hole.
+    pub(super) fn contiguous_blocks_remaining(&self) -> u32 {
+        let Some(last_block) = self.last_block.filter(|b| *b != 0) else {
+            return 0;
+        };
+        let in_file = self
+            .num_blocks_total
+            .saturating_sub(self.num_blocks_yielded);
+
+        // Check the block indices that follow the most recently yielded
+        // block at the lowest level of the map.
+        let leaf = match self.level_0_index {
+            0..=11 => {
+                let next = self.level_0[self.level_0_index..12].iter().copied();
+                return count_contiguous(last_block, next, in_file);
+            }
+            12 => self.level_1.as_ref(),
+            13 => self.level_2.as_ref().and_then(|l| l.indirect_1.as_ref()),
+            14 => self
+                .level_3
+                .as_ref()
+                .and_then(|l| l.indirect_1.as_ref())
+                .and_then(|l| l.indirect_1.as_ref()),
+            _ => None,
+        };
+        leaf.map_or(0, |leaf| {
+            count_contiguous(last_block, leaf.remaining(), in_file)
+        })
+    }
+
     #[track_caller]
     fn increment_num_blocks_yielded(&mut self) {
         // OK to unwrap: `num_blocks_yielded` is less than
@@ -101,6 +217,14 @@ impl BlockMap {
     }
 
     fn next_impl(&mut self) -> Result<Option<FsBlockIndex>, Ext4Error> {
+        let block = self.next_in_map()?;
+        if block.is_some() {
+            self.last_block = block;
+        }
+        Ok(block)
+    }
+
+    fn next_in_map(&mut self) -> Result<Option<FsBlockIndex>, Ext4Error> {
         if self.num_blocks_yielded >= self.num_blocks_total {
             self.is_done = true;
             return Ok(None);
@@ -178,6 +302,34 @@ impl BlockMap {
 
 impl_result_iter!(BlockMap, FsBlockIndex);
 
+/// Count the leading blocks in `next` that physically follow
+/// `last_block`, checking at most `limit` blocks.
+fn count_contiguous(
+    last_block: FsBlockIndex,
+    next: impl Iterator<Item = u32>,
+    limit: u32,
+) -> u32 {
+    let mut num_blocks: u32 = 0;
+    for block in next.take(usize_from_u32(limit)) {
+        let expected = u64::from(num_blocks)
+            .checked_add(1)
+            .and_then(|n| last_block.checked_add(n));
+        if Some(FsBlockIndex::from(block)) != expected {
+            break;
+        }
+        // OK to unwrap: at most `limit` blocks are checked.
+        num_blocks = num_blocks.checked_add(1).unwrap();
+    }
+    num_blocks
+}
+
+/// Get the number of block indices that fit in one indirect block.
+fn entries_per_block(fs: &Ext4) -> NonZero<u64> {
+    // OK to unwrap: the block size is at least 1024, so dividing by
+    // four cannot produce zero.
+    NonZero::new(fs.0.superblock.block_size.to_u64() / 4).unwrap()
+}
+
 struct IndirectBlockIter {
     /// Indirect block data. The block contains an array of `u32`, each
     /// of which is a block number.
@@ -198,6 +350,32 @@ impl IndirectBlockIter {
             index_within_block: 0,
         })
     }
+
+    /// Create an iterator that starts at entry `index` within the
+    /// block. If `index` is past the end of the block, the iterator is
+    /// empty.
+    fn new_at(
+        fs: Ext4,
+        block_index: u32,
+        index: u64,
+    ) -> Result<Self, Ext4Error> {
+        let mut iter = Self::new(fs, block_index)?;
+        iter.index_within_block = usize::try_from(index)
+            .ok()
+            .and_then(|i| i.checked_mul(size_of::<u32>()))
+            .map_or(iter.block.len(), |i| i.min(iter.block.len()));
+        Ok(iter)
+    }
+}
+
+impl IndirectBlockIter {
+    /// Get the block indices that have not been yielded yet, without
+    /// advancing the iterator.
+    fn remaining(&self) -> impl Iterator<Item = u32> + '_ {
+        self.block[self.index_within_block..]
+            .chunks_exact(size_of::<u32>())
+            .map(|chunk| read_u32le(chunk, 0))
+    }
 }
 
 impl Iterator for IndirectBlockIter {
@@ -241,6 +419,34 @@ impl DoubleIndirectBlockIter {
         })
     }
 
+    /// Create an iterator that starts at entry `index` within the
+    /// doubly-indirect tree rooted at `block_index`.
+    fn new_at(
+        fs: Ext4,
+        block_index: u32,
+        index: u64,
+    ) -> Result<Self, Ext4Error> {
+        let n = entries_per_block(&fs);
+        let mut indirect_0 =
+            IndirectBlockIter::new_at(fs.clone(), block_index, index / n)?;
+        let indirect_1 = if let Some(block_index) = indirect_0.next() {
+            Some(IndirectBlockIter::new_at(
+                fs.clone(),
+                block_index,
+                index % n,
+            )?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            indirect_0,
+            indirect_1,
+            fs,
+            is_done: false,
+        })
+    }
+
     fn next_impl(&mut self) -> Result<Option<u32>, Ext4Error> {
         if let Some(indirect_1) = &mut self.indirect_1 {
             if let Some(block_index) = indirect_1.next() {
@@ -279,6 +485,40 @@ impl TripleIndirectBlockIter {
         })
     }
 
+    /// Create an iterator that starts at entry `index` within the
+    /// triply-indirect tree rooted at `block_index`.
+    fn new_at(
+        fs: Ext4,
+        block_index: u32,
+        index: u64,
+    ) -> Result<Self, Ext4Error> {
+        let n = entries_per_block(&fs);
+        // OK to unwrap: `n` is nonzero and at most 16384, so the square
+        // is nonzero and fits in a `u64`.
+        let n_squared = n.checked_mul(n).unwrap();
+        let mut indirect_0 = IndirectBlockIter::new_at(
+            fs.clone(),
+            block_index,
+            index / n_squared,
+        )?;
+        let indirect_1 = if let Some(block_index) = indirect_0.next() {
+            Some(DoubleIndirectBlockIter::new_at(
+                fs.clone(),
+                block_index,
+                index % n_squared,
+            )?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            indirect_0,
+            indirect_1,
+            fs,
+            is_done: false,
+        })
+    }
+
     fn next_impl(&mut self) -> Result<Option<u32>, Ext4Error> {
         if let Some(indirect_1) = &mut self.indirect_1 {
             if let Some(block_index) = indirect_1.next() {
@@ -16,7 +16,8 @@ use crate::iters::extents::Extents;
 /// Iterator over blocks in a file that uses extents.
 ///
 /// The iterator produces absolute block indices. A block index of zero
-/// indicates a hole.
+/// indicates a hole. Blocks in unwritten extents are also treated as
+/// holes.
 pub(super) struct ExtentsBlocks {
     /// Extent iterator.
     extents: Extents,
@@ -62,6 +63,52 @@ impl ExtentsBlocks {
         })
     }
 
+    /// Create an iterator that starts at `block_within_file`.
+    pub(super) fn new_at(
+        fs: Ext4,
+        inode: &Inode,
+        block_within_file: FileBlockIndex,
+    ) -> Result<Self, Ext4Error> {
+        let num_blocks_total = inode.file_size_in_blocks();
+
+        Ok(Self {
+            extents: Extents::new_at(fs, inode, block_within_file)?,
+            extent: None,
+            blocks_remaining_in_hole: 0,
+            block_within_file,
+            num_blocks_total,
+            block_within_extent: 0,
+            is_done: false,
+            inode: inode.index,
+        })
+    }
+
+    /// Get the number of blocks following the most recently yielded
+    /// block that are in the same extent, and are therefore physically
+    /// contiguous with it. Blocks past the end of the file are not
+    /// counted.
+    ///
+    /// Returns zero if the most recently yielded block was a hole.
+    pub(super) fn contiguous_blocks_remaining(&self) -> u32 {
+        if self.blocks_remaining_in_hole > 0 {
+            return 0;
+        }
+        let Some(extent) = &self.extent else {
+            return 0;
+        };
+        // If no blocks of the extent have been yielded yet, the most
+        // recently yielded block was in the hole before it.
+        if self.block_within_extent == 0 || extent.is_unwritten {
+            return 0;
+        }
+
+        let in_extent =
+            extent.num_blocks.saturating_sub(self.block_within_extent);
+        let in_file =
+            self.num_blocks_total.saturating_sub(self.block_within_file);
+        u32::from(in_extent).min(in_file)
+    }
+
     fn next_impl(&mut self) -> Result<Option<FsBlockIndex>, Ext4Error> {
         if self.block_within_file >= self.num_blocks_total {
             self.is_done = true;
@@ -101,8 +148,20 @@ impl ExtentsBlocks {
                             .unwrap();
                     }
 
+                    // If the extent starts before the current block
+                    // (which happens when the iterator was created with
+                    // `new_at`), skip the blocks before it. This is
+                    // capped at the extent length.
+                    self.block_within_extent = match self
+                        .block_within_file
+                        .checked_sub(extent.block_within_file)
+                    {
+                        Some(n) => u16::try_from(n)
+                            .unwrap_or(u16::MAX)
+                            .min(extent.num_blocks),
+                        None => 0,
+                    };
                     self.extent = Some(extent);
-                    self.block_within_extent = 0;
 
                     // If there is a hole, return early so that the hole
                     // is processed before the extent.
@@ -151,6 +210,10 @@ impl ExtentsBlocks {
             .checked_add(u64::from(self.block_within_extent))
             .ok_or(CorruptKind::ExtentBlock(self.inode))?;
 
+        // Blocks in an unwritten extent may contain stale data, so treat
+        // them as a hole.
+        let block = if extent.is_unwritten { 0 } else { block };
+
         // OK to unwrap: `block_within_extent` is less than `num_blocks`
         // (checked above) so adding `1` cannot fail.
         self.block_within_extent =
@@ -177,7 +240,11 @@ impl ExtentsBlocks {
 //   }
 //
 //   for block in extent.blocks {
-//     yield block;
+//     if extent is unwritten {
+//       yield 0;
+//     } else {
+//       yield block;
+//     }
 //   }
 // }
 //
@@ -221,4 +288,36 @@ mod tests {
 
         assert_eq!(is_hole, expected_is_hole);
     }
+
+    /// Test that `ExtentsBlocks::new_at` yields the same blocks as
+    /// skipping ahead with `ExtentsBlocks::new`, for every start
+    /// position in a file with holes.
+    #[test]
+    fn test_extents_blocks_new_at() {
+        let fs = load_test_disk1();
+
+        let inode = fs
+            .path_to_inode(Path::new("/holes"), FollowSymlinks::All)
+            .unwrap();
+
+        let all: Vec<_> = ExtentsBlocks::new(fs.clone(), &inode)
+            .unwrap()
+            .map(|block_index| block_index.unwrap())
+            .collect();
+        assert_eq!(all.len(), 10);
+
+        for start in 0..=11 {
+            let blocks: Vec<_> =
+                ExtentsBlocks::new_at(fs.clone(), &inode, start)
+                    .unwrap()
+                    .map(|block_index| block_index.unwrap())
+                    .collect();
+            let expected: Vec<_> = all
+                .iter()
+                .copied()
+                .skip(start.try_into().unwrap())
+                .collect();
+            assert_eq!(blocks, expected);
+        }
+    }
 }
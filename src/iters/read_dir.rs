@@ -12,10 +12,12 @@ use crate::checksum::Checksum;
 use crate::dir_block::DirBlock;
 use crate::dir_entry::DirEntry;
 use crate::error::{CorruptKind, Ext4Error};
-use crate::inode::{Inode, InodeFlags, InodeIndex};
+use crate::file_attributes::FileAttributes;
+use crate::inline_data::read_inline_dir;
+use crate::inode::{Inode, InodeIndex};
 use crate::iters::file_blocks::FileBlocks;
 use crate::path::PathBuf;
-use alloc::rc::Rc;
+use crate::sync::Shared;
 use alloc::vec;
 use alloc::vec::Vec;
 use core::fmt::{self, Debug, Formatter};
@@ -24,12 +26,13 @@ use core::fmt::{self, Debug, Formatter};
 pub struct ReadDir {
     fs: Ext4,
 
-    /// Path of the directory. This is stored in an `Rc` so that it can
-    /// be shared with each `DirEntry` without cloning the path data.
+    /// Path of the directory. This is stored in a `Shared` pointer so
+    /// that it can be shared with each `DirEntry` without cloning the
+    /// path data.
     ///
     /// Note that this path may be empty, e.g. if `read_dir` was called
     /// with an inode rather than a path.
-    path: Rc<PathBuf>,
+    path: Shared<PathBuf>,
 
     /// Iterator over the blocks of the directory.
     file_blocks: FileBlocks,
@@ -42,6 +45,9 @@ pub struct ReadDir {
     is_first_block: bool,
 
     /// The current block's data.
+    ///
+    /// For inline directories, this holds all of the directory's
+    /// entries instead.
     block: Vec<u8>,
 
     /// The current byte offset within the block data.
@@ -56,6 +62,10 @@ pub struct ReadDir {
     /// blocks have checksums.
     has_htree: bool,
 
+    /// Whether the directory's entries are stored inline in the inode
+    /// rather than in blocks.
+    is_inline: bool,
+
     /// Initial checksum using values from the directory's inode. This
     /// serves as the seed for directory block checksums.
     checksum_base: Checksum,
@@ -70,28 +80,73 @@ impl ReadDir {
         inode: &Inode,
         path: PathBuf,
     ) -> Result<Self, Ext4Error> {
-        let has_htree = inode.flags.contains(InodeFlags::DIRECTORY_HTREE);
+        let has_htree = inode
+            .metadata
+            .flags
+            .contains(FileAttributes::DIRECTORY_HTREE);
 
-        if inode.flags.contains(InodeFlags::DIRECTORY_ENCRYPTED) {
+        if inode.metadata.flags.contains(FileAttributes::ENCRYPTED) {
             return Err(Ext4Error::Encrypted);
         }
 
+        let is_inline =
+            inode.metadata.flags.contains(FileAttributes::INLINE_DATA);
+        let block = if is_inline {
+            read_inline_dir(&fs, inode)?
+        } else {
+            vec![0; fs.0.superblock.block_size.to_usize()]
+        };
+
         Ok(Self {
             fs: fs.clone(),
-            path: Rc::new(path),
+            path: Shared::new(path),
             file_blocks: FileBlocks::new(fs.clone(), inode)?,
             block_index: None,
             is_first_block: true,
-            block: vec![0; fs.0.superblock.block_size.to_usize()],
+            block,
             offset_within_block: 0,
             is_done: false,
             has_htree,
+            is_inline,
             checksum_base: inode.checksum_base.clone(),
             inode: inode.index,
         })
     }
 
     fn next_impl(&mut self) -> Result<Option<DirEntry>, Ext4Error> {
+        if self.is_inline {
+            // All entries are already loaded, so there are no blocks to
+            // read.
+            if self.offset_within_block >= self.block.len() {
+                self.is_done = true;
+                return Ok(None);
+            }
+        } else if !self.advance_block()? {
+            return Ok(None);
+        }
+
+        let (entry, entry_size) = DirEntry::from_bytes(
+            self.fs.clone(),
+            &self.block[self.offset_within_block..],
+            self.inode,
+            self.path.clone(),
+        )?;
+
+        self.offset_within_block = self
+            .offset_within_block
+            .checked_add(entry_size.get())
+            .ok_or(CorruptKind::DirEntry(self.inode))?;
+
+        Ok(entry)
+    }
+
+    /// Make sure `self.block` contains the data of the current block,
+    /// moving to the next block if needed.
+    ///
+    /// Returns `false` if there is no entry to read on this iteration,
+    /// either because the end of a block or the end of the directory
+    /// has been reached.
+    fn advance_block(&mut self) -> Result<bool, Ext4Error> {
         // Get the block index, or get the next one if not set.
         let block_index = if let Some(block_index) = self.block_index {
             block_index
@@ -106,7 +161,7 @@ impl ReadDir {
                 Some(Err(err)) => return Err(err),
                 None => {
                     self.is_done = true;
-                    return Ok(None);
+                    return Ok(false);
                 }
             }
         };
@@ -117,7 +172,7 @@ impl ReadDir {
         if self.offset_within_block >= block_size {
             self.is_first_block = false;
             self.block_index = None;
-            return Ok(None);
+            return Ok(false);
         }
 
         // If at the start of a new block, read it and verify the checksum.
@@ -133,19 +188,7 @@ impl ReadDir {
             .read(&mut self.block)?;
         }
 
-        let (entry, entry_size) = DirEntry::from_bytes(
-            self.fs.clone(),
-            &self.block[self.offset_within_block..],
-            self.inode,
-            self.path.clone(),
-        )?;
-
-        self.offset_within_block = self
-            .offset_within_block
-            .checked_add(entry_size.get())
-            .ok_or(CorruptKind::DirEntry(self.inode))?;
-
-        Ok(entry)
+        Ok(true)
     }
 }
 
@@ -13,11 +13,13 @@ mod descriptor_block;
 mod revocation_block;
 mod superblock;
 
-use crate::Ext4;
 use crate::block_index::FsBlockIndex;
-use crate::error::Ext4Error;
-use crate::inode::Inode;
+use crate::error::{CorruptKind, Ext4Error};
+use crate::features::IncompatibleFeatures;
+use crate::inode::{Inode, InodeIndex};
+use crate::{Ext4, JournalMode};
 use block_map::{BlockMap, load_block_map};
+use core::ops::Range;
 use superblock::JournalSuperblock;
 
 #[derive(Debug)]
@@ -35,19 +37,45 @@ impl Journal {
 
     /// Load a journal from the filesystem.
     ///
-    /// If the filesystem has no journal, an empty journal is returned.
+    /// If the filesystem has no journal, or if `mode` indicates that
+    /// the journal should not be replayed, an empty journal is
+    /// returned.
     ///
     /// Note: ext4 is all little-endian, except for the journal, which
     /// is all big-endian.
-    pub(crate) fn load(fs: &Ext4) -> Result<Self, Ext4Error> {
+    pub(crate) fn load(
+        fs: &Ext4,
+        mode: JournalMode,
+    ) -> Result<Self, Ext4Error> {
         let Some(journal_inode) = fs.0.superblock.journal_inode else {
             // Return an empty journal if this filesystem does not have
             // a journal.
             return Ok(Self::empty());
         };
 
+        let needs_recovery =
+            fs.0.superblock
+                .incompatible_features
+                .contains(IncompatibleFeatures::RECOVERY);
+        let replay = match mode {
+            JournalMode::Replay => true,
+            JournalMode::ReplayIfNeeded => needs_recovery,
+            JournalMode::Ignore => false,
+        };
+        if !replay {
+            return Ok(Self::empty());
+        }
+
+        let journal_inode =
+            InodeIndex::new(journal_inode).ok_or(CorruptKind::JournalInode)?;
         let journal_inode = Inode::read(fs, journal_inode)?;
         let superblock = JournalSuperblock::load(fs, &journal_inode)?;
+
+        // A start block of zero indicates that the journal is empty.
+        if superblock.start_block == 0 {
+            return Ok(Self::empty());
+        }
+
         let block_map = load_block_map(fs, &superblock, &journal_inode)?;
 
         Ok(Self { block_map })
@@ -63,12 +91,23 @@ impl Journal {
     ) -> FsBlockIndex {
         *self.block_map.get(&block_index).unwrap_or(&block_index)
     }
+
+    /// Get the blocks within `range` that the journal contains a
+    /// replacement for, along with the replacement block, in order.
+    pub(crate) fn remapped_blocks(
+        &self,
+        range: Range<FsBlockIndex>,
+    ) -> impl Iterator<Item = (FsBlockIndex, FsBlockIndex)> + '_ {
+        self.block_map
+            .range(range)
+            .map(|(block, mapped)| (*block, *mapped))
+    }
 }
 
 #[cfg(all(test, feature = "std"))]
 mod tests {
+    use crate::sync::Shared;
     use crate::test_util::load_compressed_filesystem;
-    use alloc::rc::Rc;
 
     #[test]
     fn test_journal() {
@@ -81,7 +120,11 @@ mod tests {
         assert!(fs.exists(test_dir).unwrap());
 
         // Clear the journal, and verify that the directory no longer exists.
-        Rc::get_mut(&mut fs.0).unwrap().journal.block_map.clear();
+        Shared::get_mut(&mut fs.0)
+            .unwrap()
+            .journal
+            .block_map
+            .clear();
         assert!(!fs.exists(test_dir).unwrap());
     }
 }
@@ -20,7 +20,7 @@ use crate::journal::descriptor_block::{
 use crate::journal::revocation_block::{
     read_revocation_block_table, validate_revocation_block_checksum,
 };
-use crate::journal::superblock::JournalSuperblock;
+use crate::journal::superblock::{JournalChecksumVersion, JournalSuperblock};
 use crate::util::usize_from_u32;
 use alloc::collections::BTreeMap;
 use alloc::vec;
@@ -188,6 +188,7 @@ impl<'a> BlockMapLoader<'a> {
 
         let tags = DescriptorBlockTagIter::new(
             &self.block[JournalBlockHeader::SIZE..],
+            self.superblock,
         );
 
         for tag in tags {
@@ -199,14 +200,27 @@ impl<'a> BlockMapLoader<'a> {
                 .ok_or(CorruptKind::JournalTruncated)??;
 
             // Check the data block checksum.
-            let mut checksum = Checksum::new();
-            checksum.update(self.superblock.uuid.as_bytes());
-            checksum.update_u32_be(self.sequence);
-            self.fs
-                .read_from_block(block_index, 0, &mut self.data_block)?;
-            checksum.update(&self.data_block);
-            if checksum.finalize() != tag.checksum {
-                return Err(CorruptKind::JournalDescriptorTagChecksum.into());
+            if let Some(checksum_version) = self.superblock.checksum_version {
+                let mut checksum = Checksum::new();
+                checksum.update(self.superblock.uuid.as_bytes());
+                checksum.update_u32_be(self.sequence);
+                self.fs.read_from_block(
+                    block_index,
+                    0,
+                    &mut self.data_block,
+                )?;
+                checksum.update(&self.data_block);
+                let mut actual_checksum = checksum.finalize();
+                // With v2 checksums, the tag only holds the lower 16
+                // bits of the checksum.
+                if checksum_version == JournalChecksumVersion::V2 {
+                    actual_checksum &= 0xffff;
+                }
+                if actual_checksum != tag.checksum {
+                    return Err(
+                        CorruptKind::JournalDescriptorTagChecksum.into()
+                    );
+                }
             }
 
             self.uncommitted_block_map
@@ -218,7 +232,11 @@ impl<'a> BlockMapLoader<'a> {
 
     fn process_revocation_block(&mut self) -> Result<(), Ext4Error> {
         validate_revocation_block_checksum(self.superblock, &self.block)?;
-        read_revocation_block_table(&self.block, &mut self.revoked_blocks)
+        read_revocation_block_table(
+            self.superblock,
+            &self.block,
+            &mut self.revoked_blocks,
+        )
     }
 
     /// Process a commit block.
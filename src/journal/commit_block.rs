@@ -14,15 +14,20 @@ use crate::util::read_u32be;
 /// Ensure a commit block's checksum is valid.
 ///
 /// The checksum covers the entire block. The checksum field is treated
-/// as zero for the checksum calculation.
+/// as zero for the checksum calculation. If the journal does not have
+/// checksums, this always succeeds.
 pub(super) fn validate_commit_block_checksum(
     superblock: &JournalSuperblock,
     block: &[u8],
 ) -> Result<(), Ext4Error> {
+    if !superblock.has_checksums() {
+        return Ok(());
+    }
+
     // The kernel documentation says that fields 0xc and 0xd contain the
     // checksum type and size, but this is not correct. If the
     // superblock features include `CHECKSUM_V3`, the type/size fields
-    // are both zero.
+    // are both zero. The same is true with `CHECKSUM_V2`.
 
     const CHECKSUM_OFFSET: usize = 16;
     const CHECKSUM_SIZE: usize = 4;
@@ -46,15 +51,18 @@ pub(super) fn validate_commit_block_checksum(
 mod tests {
     use super::*;
     use crate::Uuid;
+    use crate::journal::superblock::JournalChecksumVersion;
 
     /// Test success and failure cases of `validate_commit_block_checksum`.
     #[test]
     fn test_validate_commit_block_checksum() {
-        let superblock = JournalSuperblock {
+        let mut superblock = JournalSuperblock {
             block_size: 1024,
             sequence: 0,
             start_block: 0,
             uuid: Uuid([0; 16]),
+            is_64bit: true,
+            checksum_version: Some(JournalChecksumVersion::V3),
         };
 
         // Valid checksum.
@@ -68,5 +76,9 @@ mod tests {
             validate_commit_block_checksum(&superblock, &block).unwrap_err(),
             CorruptKind::JournalCommitBlockChecksum
         );
+
+        // No validation occurs if the journal doesn't have checksums.
+        superblock.checksum_version = None;
+        assert!(validate_commit_block_checksum(&superblock, &block).is_ok());
     }
 }
@@ -9,17 +9,22 @@
 use crate::block_index::FsBlockIndex;
 use crate::checksum::Checksum;
 use crate::error::{CorruptKind, Ext4Error, IncompatibleKind};
-use crate::journal::superblock::JournalSuperblock;
-use crate::util::{read_u32be, u64_from_hilo};
+use crate::journal::superblock::{JournalChecksumVersion, JournalSuperblock};
+use crate::util::{read_u16be, read_u32be, u64_from_hilo};
 use bitflags::bitflags;
 
 /// Ensure a descriptor block's checksum is valid.
 ///
-/// The checksum is stored in the last four bytes of the block.
+/// The checksum is stored in the last four bytes of the block. If the
+/// journal does not have checksums, this always succeeds.
 pub(super) fn validate_descriptor_block_checksum(
     superblock: &JournalSuperblock,
     block: &[u8],
 ) -> Result<(), Ext4Error> {
+    if !superblock.has_checksums() {
+        return Ok(());
+    }
+
     // OK to unwrap: minimum block length is 1024.
     let checksum_offset = block.len().checked_sub(4).unwrap();
     let expected_checksum = read_u32be(block, checksum_offset);
@@ -53,55 +58,90 @@ pub(super) struct DescriptorBlockTag {
     /// Note that this checksum is for the data block associated with
     /// this tag. The data in the tag itself is covered by the
     /// descriptor block checksum.
+    ///
+    /// With v2 checksums, only the lower 16 bits of the checksum are
+    /// stored. If the journal doesn't have checksums, this is zero.
     pub(super) checksum: u32,
 
     flags: DescriptorBlockTagFlags,
 }
 
 impl DescriptorBlockTag {
-    const SIZE_WITHOUT_UUID: usize = 16;
-    const SIZE_WITH_UUID: usize = 32;
+    /// Size (in bytes) of the tag's UUID field.
+    const UUID_SIZE: usize = 16;
+
+    /// Size (in bytes) of a tag without the UUID field.
+    ///
+    /// The tag format depends on feature flags in the journal
+    /// superblock. With `CHECKSUM_V3`, the tag is always 16 bytes. In
+    /// the older format, the tag is 8 bytes, plus 4 bytes for the high
+    /// half of the block index in 64-bit journals, plus 2 bytes with
+    /// `CHECKSUM_V2`.
+    fn size_without_uuid(superblock: &JournalSuperblock) -> usize {
+        match (superblock.checksum_version, superblock.is_64bit) {
+            (Some(JournalChecksumVersion::V3), _) => 16,
+            (Some(JournalChecksumVersion::V2), true) => 14,
+            (Some(JournalChecksumVersion::V2), false) => 10,
+            (None, true) => 12,
+            (None, false) => 8,
+        }
+    }
 
     /// Size (in bytes) of the tag when encoded in a block.
-    fn encoded_size(&self) -> usize {
+    fn encoded_size(&self, superblock: &JournalSuperblock) -> usize {
+        let size = Self::size_without_uuid(superblock);
         if self.flags.contains(DescriptorBlockTagFlags::UUID_OMITTED) {
-            Self::SIZE_WITHOUT_UUID
+            size
         } else {
-            Self::SIZE_WITH_UUID
+            // OK to unwrap: the maximum size is 32.
+            size.checked_add(Self::UUID_SIZE).unwrap()
         }
     }
 
     /// Read a tag from `bytes`.
     ///
     /// Returns `None` if there are not enough bytes to read the tag.
-    fn read_bytes(bytes: &[u8]) -> Option<Self> {
-        // Note: the tag format depends on feature flags in the journal
-        // superblock. The code in this function is only correct if the
-        // `CHECKSUM_V3` feature is enabled (this is checked when
-        // loading the superblock).
-
-        if bytes.len() < Self::SIZE_WITHOUT_UUID {
+    fn read_bytes(
+        bytes: &[u8],
+        superblock: &JournalSuperblock,
+    ) -> Option<Self> {
+        if bytes.len() < Self::size_without_uuid(superblock) {
             return None;
         }
 
         let t_blocknr = read_u32be(bytes, 0);
-        let t_flags = read_u32be(bytes, 4);
-        let t_blocknr_high = read_u32be(bytes, 8);
-        let t_checksum = read_u32be(bytes, 12);
+        let t_flags;
+        let t_blocknr_high;
+        let t_checksum;
+        if superblock.checksum_version == Some(JournalChecksumVersion::V3) {
+            t_flags = read_u32be(bytes, 4);
+            t_blocknr_high = read_u32be(bytes, 8);
+            t_checksum = read_u32be(bytes, 12);
+        } else {
+            // In the older tag format, the checksum and flags are
+            // 16-bit. The checksum is zero if the journal does not
+            // have v2 checksums.
+            t_checksum = u32::from(read_u16be(bytes, 4));
+            t_flags = u32::from(read_u16be(bytes, 6));
+            t_blocknr_high = if superblock.is_64bit {
+                read_u32be(bytes, 8)
+            } else {
+                0
+            };
+        }
 
-        let flags = DescriptorBlockTagFlags::from_bits_retain(t_flags);
+        let tag = Self {
+            block_index: u64_from_hilo(t_blocknr_high, t_blocknr),
+            flags: DescriptorBlockTagFlags::from_bits_retain(t_flags),
+            checksum: t_checksum,
+        };
 
-        if !flags.contains(DescriptorBlockTagFlags::UUID_OMITTED)
-            && bytes.len() < Self::SIZE_WITH_UUID
-        {
+        // Check that the UUID (if present) fits as well.
+        if bytes.len() < tag.encoded_size(superblock) {
             return None;
         }
 
-        Some(Self {
-            block_index: u64_from_hilo(t_blocknr_high, t_blocknr),
-            flags,
-            checksum: t_checksum,
-        })
+        Some(tag)
     }
 }
 
@@ -110,6 +150,10 @@ pub(super) struct DescriptorBlockTagIter<'a> {
     /// Remaining bytes in the block.
     bytes: &'a [u8],
 
+    /// Journal superblock. The features in the superblock control the
+    /// format of the tags.
+    superblock: &'a JournalSuperblock,
+
     /// Set to true after the last element (or an error) is
     /// returned. All future calls to `next` will return `None`.
     is_done: bool,
@@ -117,9 +161,13 @@ pub(super) struct DescriptorBlockTagIter<'a> {
 
 impl<'a> DescriptorBlockTagIter<'a> {
     /// Create a tag iterator from the raw bytes of a descriptor block.
-    pub(super) fn new(bytes: &'a [u8]) -> Self {
+    pub(super) fn new(
+        bytes: &'a [u8],
+        superblock: &'a JournalSuperblock,
+    ) -> Self {
         Self {
             bytes,
+            superblock,
             is_done: false,
         }
     }
@@ -133,7 +181,8 @@ impl Iterator for DescriptorBlockTagIter<'_> {
             return None;
         }
 
-        let tag = if let Some(tag) = DescriptorBlockTag::read_bytes(self.bytes)
+        let tag = if let Some(tag) =
+            DescriptorBlockTag::read_bytes(self.bytes, self.superblock)
         {
             tag
         } else {
@@ -160,7 +209,7 @@ impl Iterator for DescriptorBlockTagIter<'_> {
         }
 
         // Update the remaining bytes.
-        self.bytes = &self.bytes[tag.encoded_size()..];
+        self.bytes = &self.bytes[tag.encoded_size(self.superblock)..];
 
         Some(Ok(tag))
     }
@@ -181,15 +230,25 @@ mod tests {
     use super::*;
     use crate::Uuid;
 
-    /// Test success and failure cases of `validate_descriptor_block_checksum`.
-    #[test]
-    fn test_validate_descriptor_block_checksum() {
-        let superblock = JournalSuperblock {
+    fn create_test_superblock(
+        is_64bit: bool,
+        checksum_version: Option<JournalChecksumVersion>,
+    ) -> JournalSuperblock {
+        JournalSuperblock {
             block_size: 1024,
             sequence: 0,
             start_block: 0,
             uuid: Uuid([0; 16]),
-        };
+            is_64bit,
+            checksum_version,
+        }
+    }
+
+    /// Test success and failure cases of `validate_descriptor_block_checksum`.
+    #[test]
+    fn test_validate_descriptor_block_checksum() {
+        let superblock =
+            create_test_superblock(true, Some(JournalChecksumVersion::V3));
         let mut block = vec![0; 1024];
         assert_eq!(
             validate_descriptor_block_checksum(&superblock, &block)
@@ -201,6 +260,17 @@ mod tests {
         assert!(
             validate_descriptor_block_checksum(&superblock, &block).is_ok()
         );
+
+        // No validation occurs if the journal doesn't have checksums.
+        let superblock = create_test_superblock(true, None);
+        let block = vec![0; 1024];
+        assert!(
+            validate_descriptor_block_checksum(&superblock, &block).is_ok()
+        );
+    }
+
+    fn push_u16be(bytes: &mut Vec<u8>, value: u32) {
+        bytes.extend(&u16::try_from(value).unwrap().to_be_bytes());
     }
 
     fn push_u32be(bytes: &mut Vec<u8>, value: u32) {
@@ -211,6 +281,8 @@ mod tests {
     /// no UUID, the second tag does have a UUID.
     #[test]
     fn test_descriptor_block_tag_iter() {
+        let superblock =
+            create_test_superblock(true, Some(JournalChecksumVersion::V3));
         let mut bytes = vec![];
 
         // Block number low.
@@ -234,7 +306,7 @@ mod tests {
         bytes.extend([0; 16]);
 
         assert_eq!(
-            DescriptorBlockTagIter::new(&bytes)
+            DescriptorBlockTagIter::new(&bytes, &superblock)
                 .map(Result::unwrap)
                 .collect::<Vec<_>>(),
             [
@@ -252,12 +324,105 @@ mod tests {
         );
     }
 
+    /// Test `DescriptorBlockTagIter` with the tag format used by
+    /// 32-bit journals without checksums.
+    #[test]
+    fn test_descriptor_block_tag_iter_32bit_no_checksum() {
+        let superblock = create_test_superblock(false, None);
+        let mut bytes = vec![];
+
+        // Block number.
+        push_u32be(&mut bytes, 0x1000);
+        // Checksum (unused) and flags.
+        push_u16be(&mut bytes, 0);
+        push_u16be(&mut bytes, DescriptorBlockTagFlags::UUID_OMITTED.bits());
+
+        // Block number.
+        push_u32be(&mut bytes, 0x2000);
+        // Checksum (unused) and flags.
+        push_u16be(&mut bytes, 0);
+        push_u16be(&mut bytes, DescriptorBlockTagFlags::LAST_TAG.bits());
+        // UUID.
+        bytes.extend([0; 16]);
+
+        assert_eq!(
+            DescriptorBlockTagIter::new(&bytes, &superblock)
+                .map(Result::unwrap)
+                .collect::<Vec<_>>(),
+            [
+                DescriptorBlockTag {
+                    block_index: 0x1000,
+                    flags: DescriptorBlockTagFlags::UUID_OMITTED,
+                    checksum: 0,
+                },
+                DescriptorBlockTag {
+                    block_index: 0x2000,
+                    flags: DescriptorBlockTagFlags::LAST_TAG,
+                    checksum: 0,
+                }
+            ]
+        );
+    }
+
+    /// Test `DescriptorBlockTagIter` with the tag format used by
+    /// journals with v2 checksums.
+    #[test]
+    fn test_descriptor_block_tag_iter_checksum_v2() {
+        for is_64bit in [false, true] {
+            let superblock = create_test_superblock(
+                is_64bit,
+                Some(JournalChecksumVersion::V2),
+            );
+            let mut bytes = vec![];
+
+            for (block, flags) in [
+                (0x1000, DescriptorBlockTagFlags::UUID_OMITTED),
+                (0x2000, DescriptorBlockTagFlags::LAST_TAG),
+            ] {
+                // Block number low.
+                push_u32be(&mut bytes, block);
+                // Checksum and flags.
+                push_u16be(&mut bytes, 0x123);
+                push_u16be(&mut bytes, flags.bits());
+                // Block number high.
+                if is_64bit {
+                    push_u32be(&mut bytes, 0xa000);
+                }
+                // Padding.
+                push_u16be(&mut bytes, 0);
+            }
+            // UUID.
+            bytes.extend([0; 16]);
+
+            let high = if is_64bit { 0xa000_0000_0000 } else { 0 };
+            assert_eq!(
+                DescriptorBlockTagIter::new(&bytes, &superblock)
+                    .map(Result::unwrap)
+                    .collect::<Vec<_>>(),
+                [
+                    DescriptorBlockTag {
+                        block_index: high | 0x1000,
+                        flags: DescriptorBlockTagFlags::UUID_OMITTED,
+                        checksum: 0x123,
+                    },
+                    DescriptorBlockTag {
+                        block_index: high | 0x2000,
+                        flags: DescriptorBlockTagFlags::LAST_TAG,
+                        checksum: 0x123,
+                    }
+                ]
+            );
+        }
+    }
+
     /// Test `DescriptorBlockTagFlags` on empty input.
     #[test]
     fn test_descriptor_block_tag_iter_empty() {
+        let superblock =
+            create_test_superblock(true, Some(JournalChecksumVersion::V3));
         let bytes = vec![];
         assert_eq!(
-            DescriptorBlockTagIter::new(&bytes)
+            DescriptorBlockTagIter::new(&bytes, &superblock)
                 .next()
                 .unwrap()
                 .unwrap_err(),
@@ -269,6 +434,8 @@ mod tests {
     /// truncated input.
     #[test]
     fn test_descriptor_block_tag_iter_missing_uuid() {
+        let superblock =
+            create_test_superblock(true, Some(JournalChecksumVersion::V3));
         let mut bytes = vec![];
 
         // Block number low.
@@ -283,7 +450,7 @@ mod tests {
         // Intentionally leave out the UUID bytes to produce an error.
 
         assert_eq!(
-            DescriptorBlockTagIter::new(&bytes)
+            DescriptorBlockTagIter::new(&bytes, &superblock)
                 .next()
                 .unwrap()
                 .unwrap_err(),
@@ -295,6 +462,8 @@ mod tests {
     /// an escaped block is present.
     #[test]
     fn test_descriptor_block_tag_iter_escaped_error() {
+        let superblock =
+            create_test_superblock(true, Some(JournalChecksumVersion::V3));
         let mut bytes = vec![];
 
         // Block number low.
@@ -313,7 +482,7 @@ mod tests {
         push_u32be(&mut bytes, 0x456);
 
         assert_eq!(
-            DescriptorBlockTagIter::new(&bytes)
+            DescriptorBlockTagIter::new(&bytes, &superblock)
                 .next()
                 .unwrap()
                 .unwrap_err(),
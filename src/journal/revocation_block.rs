@@ -17,11 +17,16 @@ use alloc::vec::Vec;
 
 /// Ensure a revocation block's checksum is valid.
 ///
-/// The checksum is stored in the last four bytes of the block.
+/// The checksum is stored in the last four bytes of the block. If the
+/// journal does not have checksums, this always succeeds.
 pub(super) fn validate_revocation_block_checksum(
     superblock: &JournalSuperblock,
     block: &[u8],
 ) -> Result<(), Ext4Error> {
+    if !superblock.has_checksums() {
+        return Ok(());
+    }
+
     // OK to unwrap: minimum block length is 1024.
     let checksum_offset = block.len().checked_sub(4).unwrap();
     let expected_checksum = read_u32be(block, checksum_offset);
@@ -41,24 +46,32 @@ pub(super) fn validate_revocation_block_checksum(
 ///
 /// The entries are appended to the end of `table`.
 pub(super) fn read_revocation_block_table(
+    superblock: &JournalSuperblock,
     block: &[u8],
     table: &mut Vec<FsBlockIndex>,
 ) -> Result<(), Ext4Error> {
-    // Note: if this library adds support for 32-bit journals, this
-    // size will need to be conditionally set to either 4 or 8.
-    const BLOCK_INDEX_SIZE_IN_BYTES: usize = 8;
+    // Block indices are 64-bit if the journal is 64-bit, otherwise
+    // they are 32-bit.
+    let block_index_size_in_bytes = if superblock.is_64bit {
+        size_of::<u64>()
+    } else {
+        size_of::<u32>()
+    };
+
+    // The block ends with a checksum if the journal has checksums.
+    let tail_size = if superblock.has_checksums() { 4 } else { 0 };
 
     // Skip past the block header bytes, and remove the trailing
     // checksum bytes.
     let data = &block[JournalBlockHeader::SIZE..
                // OK to unwrap: minimum block length is 1024.
-               block.len().checked_sub(4).unwrap()];
+               block.len().checked_sub(tail_size).unwrap()];
 
     // Get the size (in bytes) of the block-index array.
     let num_bytes = usize_from_u32(read_u32be(data, 0));
 
     // Ensure that the table size is an even multiple of the index size.
-    if num_bytes % BLOCK_INDEX_SIZE_IN_BYTES != 0 {
+    if num_bytes.checked_rem(block_index_size_in_bytes) != Some(0) {
         return Err(CorruptKind::JournalRevocationBlockInvalidTableSize(
             num_bytes,
         )
@@ -75,15 +88,18 @@ pub(super) fn read_revocation_block_table(
 
     // Read each entry and append to `table`.
     while !data.is_empty() {
-        let block_index = u64::from_be_bytes(
-            // OK to unwrap: `BLOCK_INDEX_SIZE_IN_BYTES` matches the
-            // size of `u64`.
-            data[..BLOCK_INDEX_SIZE_IN_BYTES].try_into().unwrap(),
-        );
+        let block_index = if superblock.is_64bit {
+            u64::from_be_bytes(
+                // OK to unwrap: the slice is the size of a `u64`.
+                data[..block_index_size_in_bytes].try_into().unwrap(),
+            )
+        } else {
+            u64::from(read_u32be(data, 0))
+        };
 
         table.push(block_index);
 
-        data = &data[BLOCK_INDEX_SIZE_IN_BYTES..];
+        data = &data[block_index_size_in_bytes..];
     }
 
     Ok(())
@@ -92,17 +108,24 @@ pub(super) fn read_revocation_block_table(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::journal::superblock::JournalChecksumVersion;
     use crate::uuid::Uuid;
 
-    /// Test success and failure cases of `validate_revocation_block_checksum`.
-    #[test]
-    fn test_validate_revocation_block_checksum() {
-        let superblock = JournalSuperblock {
+    fn create_test_superblock(is_64bit: bool) -> JournalSuperblock {
+        JournalSuperblock {
             block_size: 1024,
             sequence: 0,
             start_block: 0,
             uuid: Uuid([0; 16]),
-        };
+            is_64bit,
+            checksum_version: Some(JournalChecksumVersion::V3),
+        }
+    }
+
+    /// Test success and failure cases of `validate_revocation_block_checksum`.
+    #[test]
+    fn test_validate_revocation_block_checksum() {
+        let mut superblock = create_test_superblock(true);
         let mut block = vec![0; 1024];
         assert_eq!(
             validate_revocation_block_checksum(&superblock, &block)
@@ -114,6 +137,13 @@ mod tests {
         assert!(
             validate_revocation_block_checksum(&superblock, &block).is_ok()
         );
+
+        // No validation occurs if the journal doesn't have checksums.
+        superblock.checksum_version = None;
+        let block = vec![0; 1024];
+        assert!(
+            validate_revocation_block_checksum(&superblock, &block).is_ok()
+        );
     }
 
     fn create_test_revocation_block() -> Vec<u8> {
@@ -142,23 +172,56 @@ mod tests {
     /// Test a successful call to `read_revocation_block_table`.
     #[test]
     fn test_read_revocation_block_table_success() {
+        let superblock = create_test_superblock(true);
         let block = create_test_revocation_block();
         let mut table = Vec::new();
-        read_revocation_block_table(&block, &mut table).unwrap();
+        read_revocation_block_table(&superblock, &block, &mut table).unwrap();
+        assert_eq!(table, [100, 101, 102]);
+    }
+
+    /// Test a successful call to `read_revocation_block_table` with
+    /// 32-bit block indices and no checksum.
+    #[test]
+    fn test_read_revocation_block_table_32bit() {
+        let mut superblock = create_test_superblock(false);
+        superblock.checksum_version = None;
+
+        let mut block = Vec::new();
+        block.extend([0; JournalBlockHeader::SIZE]);
+        // Size field (three 4-byte entries).
+        block.extend(12u32.to_be_bytes());
+        block.extend(100u32.to_be_bytes());
+        block.extend(101u32.to_be_bytes());
+        block.extend(102u32.to_be_bytes());
+        block.resize(1024usize, 0u8);
+
+        let mut table = Vec::new();
+        read_revocation_block_table(&superblock, &block, &mut table).unwrap();
         assert_eq!(table, [100, 101, 102]);
+
+        // The table can use the last four bytes of the block, since
+        // there is no checksum.
+        block[JournalBlockHeader::SIZE
+            ..JournalBlockHeader::SIZE + size_of::<u32>()]
+            .copy_from_slice(&1008u32.to_be_bytes());
+        table.clear();
+        read_revocation_block_table(&superblock, &block, &mut table).unwrap();
+        assert_eq!(table.len(), 252);
     }
 
     /// Test that `read_revocation_block_table` rejects a table size
     /// that is not an even multiple of the table entry size.
     #[test]
     fn test_read_revocation_block_table_uneven_size() {
+        let superblock = create_test_superblock(true);
         let mut block = create_test_revocation_block();
         block[JournalBlockHeader::SIZE
             ..JournalBlockHeader::SIZE + size_of::<u32>()]
             .copy_from_slice(&7u32.to_be_bytes());
         let mut table = Vec::new();
         assert_eq!(
-            read_revocation_block_table(&block, &mut table).unwrap_err(),
+            read_revocation_block_table(&superblock, &block, &mut table)
+                .unwrap_err(),
             CorruptKind::JournalRevocationBlockInvalidTableSize(7)
         );
     }
@@ -167,13 +230,15 @@ mod tests {
     /// that is bigger than the available space in the block.
     #[test]
     fn test_read_revocation_block_table_size_too_large() {
+        let superblock = create_test_superblock(true);
         let mut block = create_test_revocation_block();
         block[JournalBlockHeader::SIZE
             ..JournalBlockHeader::SIZE + size_of::<u32>()]
             .copy_from_slice(&1008u32.to_be_bytes());
         let mut table = Vec::new();
         assert_eq!(
-            read_revocation_block_table(&block, &mut table).unwrap_err(),
+            read_revocation_block_table(&superblock, &block, &mut table)
+                .unwrap_err(),
             CorruptKind::JournalRevocationBlockInvalidTableSize(1008)
         );
     }
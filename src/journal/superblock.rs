@@ -32,14 +32,28 @@ const SUPERBLOCK_UUID_OFFSET: usize = 0x30;
 const SUPERBLOCK_CHECKSUM_TYPE_OFFSET: usize = 0x50;
 const SUPERBLOCK_CHECKSUM_OFFSET: usize = 0xfc;
 
-/// Features that must be present for this library to read the journal.
-const REQUIRED_FEATURES: JournalIncompatibleFeatures =
-    JournalIncompatibleFeatures::IS_64BIT
+/// Features that may be present.
+const ALLOWED_FEATURES: JournalIncompatibleFeatures =
+    JournalIncompatibleFeatures::BLOCK_REVOCATIONS
+        .union(JournalIncompatibleFeatures::IS_64BIT)
+        .union(JournalIncompatibleFeatures::CHECKSUM_V2)
         .union(JournalIncompatibleFeatures::CHECKSUM_V3);
 
-/// Features that may be present, but are not required.
-const ALLOWED_FEATURES: JournalIncompatibleFeatures =
-    JournalIncompatibleFeatures::BLOCK_REVOCATIONS;
+/// Format of the checksums stored in the journal.
+///
+/// Journals without either of these versions may still have the older
+/// v1 checksums (the `COMPAT_CHECKSUM` feature), which only cover the
+/// commit block. Those are not validated.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(super) enum JournalChecksumVersion {
+    /// Checksums use the `CHECKSUM_V2` format. Descriptor block tags
+    /// contain the lower 16 bits of the data block's checksum.
+    V2,
+
+    /// Checksums use the `CHECKSUM_V3` format. Descriptor block tags
+    /// contain the full 32-bit checksum of the data block.
+    V3,
+}
 
 #[derive(Debug, Eq, PartialEq)]
 pub(super) struct JournalSuperblock {
@@ -56,9 +70,22 @@ pub(super) struct JournalSuperblock {
 
     /// Journal UUID used for checksums.
     pub(super) uuid: Uuid,
+
+    /// Whether block indices in the journal are 64-bit. If not, only
+    /// the lower 32 bits are stored.
+    pub(super) is_64bit: bool,
+
+    /// Checksum format, or `None` if the journal does not have
+    /// checksums.
+    pub(super) checksum_version: Option<JournalChecksumVersion>,
 }
 
 impl JournalSuperblock {
+    /// Whether the journal's blocks have checksums.
+    pub(super) fn has_checksums(&self) -> bool {
+        self.checksum_version.is_some()
+    }
+
     /// Load the journal superblock from the filesystem.
     ///
     /// An error is returned if:
@@ -105,41 +132,65 @@ impl JournalSuperblock {
         let header = JournalBlockHeader::read_bytes(bytes)
             .ok_or(CorruptKind::JournalMagic)?;
 
-        // For now only superblock v2 is supported.
-        if header.block_type != JournalBlockType::SUPERBLOCK_V2 {
+        let is_v2 = if header.block_type == JournalBlockType::SUPERBLOCK_V2 {
+            true
+        } else if header.block_type == JournalBlockType::SUPERBLOCK_V1 {
+            false
+        } else {
             return Err(IncompatibleKind::JournalSuperblockType(
                 header.block_type.0,
             )
             .into());
-        }
+        };
 
         let s_blocksize = read_u32be(bytes, SUPERBLOCK_BLOCKSIZE_OFFSET);
         let s_sequence = read_u32be(bytes, SUPERBLOCK_SEQUENCE_OFFSET);
         let s_start = read_u32be(bytes, SUPERBLOCK_START_OFFSET);
-        let s_feature_incompat =
-            read_u32be(bytes, SUPERBLOCK_FEATURE_INCOMPAT_OFFSET);
+        // The v1 superblock does not have any feature fields, so
+        // treat all features as disabled.
+        let s_feature_incompat = if is_v2 {
+            read_u32be(bytes, SUPERBLOCK_FEATURE_INCOMPAT_OFFSET)
+        } else {
+            0
+        };
         let s_uuid =
             &bytes[SUPERBLOCK_UUID_OFFSET..SUPERBLOCK_UUID_OFFSET + 16];
         let s_checksum_type = bytes[SUPERBLOCK_CHECKSUM_TYPE_OFFSET];
         let s_checksum = read_u32be(bytes, SUPERBLOCK_CHECKSUM_OFFSET);
 
-        check_incompat_features(s_feature_incompat)?;
+        let features = check_incompat_features(s_feature_incompat)?;
+
+        let checksum_version = if features
+            .contains(JournalIncompatibleFeatures::CHECKSUM_V3)
+        {
+            Some(JournalChecksumVersion::V3)
+        } else if features.contains(JournalIncompatibleFeatures::CHECKSUM_V2) {
+            Some(JournalChecksumVersion::V2)
+        } else {
+            None
+        };
+
+        // The superblock checksum is only present with v2 or v3
+        // checksums.
+        if checksum_version.is_some() {
+            // For now only one checksum type is supported.
+            if s_checksum_type != CHECKSUM_TYPE_CRC32C {
+                return Err(IncompatibleKind::JournalChecksumType(
+                    s_checksum_type,
+                )
+                .into());
+            }
 
-        // For now only one checksum type is supported.
-        if s_checksum_type != CHECKSUM_TYPE_CRC32C {
-            return Err(
-                IncompatibleKind::JournalChecksumType(s_checksum_type).into()
+            // Validate the superblock checksum.
+            let mut checksum = Checksum::new();
+            checksum.update(&bytes[..SUPERBLOCK_CHECKSUM_OFFSET]);
+            checksum.update_u32_le(0);
+            checksum.update(
+                &bytes[SUPERBLOCK_CHECKSUM_OFFSET + 4..SUPERBLOCK_SIZE],
             );
-        }
-
-        // Validate the superblock checksum.
-        let mut checksum = Checksum::new();
-        checksum.update(&bytes[..SUPERBLOCK_CHECKSUM_OFFSET]);
-        checksum.update_u32_le(0);
-        checksum
-            .update(&bytes[SUPERBLOCK_CHECKSUM_OFFSET + 4..SUPERBLOCK_SIZE]);
-        if checksum.finalize() != s_checksum {
-            return Err(CorruptKind::JournalSuperblockChecksum.into());
+            if checksum.finalize() != s_checksum {
+                return Err(CorruptKind::JournalSuperblockChecksum.into());
+            }
         }
 
         // OK to unwrap: `s_uuid` is always 16 bytes.
@@ -150,6 +201,8 @@ impl JournalSuperblock {
             sequence: s_sequence,
             start_block: s_start,
             uuid,
+            is_64bit: features.contains(JournalIncompatibleFeatures::IS_64BIT),
+            checksum_version,
         })
     }
 }
@@ -166,39 +219,43 @@ bitflags! {
     }
 }
 
-/// Check that journal features required by this library are present,
-/// and that no unsupported features are present.
+/// Check that no unsupported journal features are present.
+///
+/// On success, the known features are returned.
 fn check_incompat_features(
     s_feature_incompat: u32,
-) -> Result<(), IncompatibleKind> {
+) -> Result<JournalIncompatibleFeatures, Ext4Error> {
     let present =
         JournalIncompatibleFeatures::from_bits_retain(s_feature_incompat);
 
-    let present_required = present & REQUIRED_FEATURES;
-    if present_required != REQUIRED_FEATURES {
-        return Err(IncompatibleKind::MissingRequiredJournalFeatures(
-            REQUIRED_FEATURES.difference(present).bits(),
-        ));
-    }
-
     // Note: the `bits` conversion is needed because otherwise the `!`
     // would only negate "known" bits specified in the bitflags
     // definition. Convert to raw bits first to correct this.
-    let unsupported = !((REQUIRED_FEATURES | ALLOWED_FEATURES).bits());
+    let unsupported = !ALLOWED_FEATURES.bits();
 
     let present_unsupported = present.bits() & unsupported;
     if present_unsupported != 0 {
         return Err(IncompatibleKind::UnsupportedJournalFeatures(
             present_unsupported,
-        ));
+        )
+        .into());
     }
 
-    Ok(())
+    // The v2 and v3 checksum formats are mutually exclusive.
+    if present.contains(
+        JournalIncompatibleFeatures::CHECKSUM_V2
+            | JournalIncompatibleFeatures::CHECKSUM_V3,
+    ) {
+        return Err(CorruptKind::JournalChecksumFeatures.into());
+    }
+
+    Ok(present)
 }
 
 #[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
+    use crate::inode::InodeIndex;
     use crate::test_util::load_compressed_filesystem;
 
     #[test]
@@ -206,7 +263,8 @@ mod tests {
         let fs =
             load_compressed_filesystem("test_disk_4k_block_journal.bin.zst");
         let journal_inode =
-            Inode::read(&fs, fs.0.superblock.journal_inode.unwrap()).unwrap();
+            InodeIndex::new(fs.0.superblock.journal_inode.unwrap()).unwrap();
+        let journal_inode = Inode::read(&fs, journal_inode).unwrap();
         let superblock = JournalSuperblock::load(&fs, &journal_inode).unwrap();
         assert_eq!(
             superblock,
@@ -218,6 +276,8 @@ mod tests {
                     0xd2, 0x28, 0xa8, 0x78, 0xb9, 0xa7, 0x49, 0xe4, 0x9e, 0x3d,
                     0xbb, 0xee, 0xd5, 0x60, 0x1c, 0xd3
                 ]),
+                is_64bit: true,
+                checksum_version: Some(JournalChecksumVersion::V3),
             }
         );
     }
@@ -260,6 +320,8 @@ mod tests {
                 sequence: 123,
                 start_block: 456,
                 uuid: Uuid([0xab; 16]),
+                is_64bit: true,
+                checksum_version: Some(JournalChecksumVersion::V3),
             }
         );
     }
@@ -286,17 +348,53 @@ mod tests {
         );
     }
 
+    /// Test a journal without checksums or 64-bit block indices. The
+    /// checksum fields are ignored in this case.
     #[test]
-    fn test_journal_superblock_missing_required_features() {
+    fn test_journal_superblock_no_checksums() {
         let mut block = create_test_superblock();
         write_u32be(&mut block, SUPERBLOCK_FEATURE_INCOMPAT_OFFSET, 0);
+        block[SUPERBLOCK_CHECKSUM_TYPE_OFFSET] = 0;
+        write_u32be(&mut block, SUPERBLOCK_CHECKSUM_OFFSET, 0);
+        assert_eq!(
+            JournalSuperblock::read_bytes(&block).unwrap(),
+            JournalSuperblock {
+                block_size: 4096,
+                sequence: 123,
+                start_block: 456,
+                uuid: Uuid([0xab; 16]),
+                is_64bit: false,
+                checksum_version: None,
+            }
+        );
+
+        // The v1 superblock has no features, so the feature field is
+        // ignored.
+        write_u32be(&mut block, 4, 3);
+        write_u32be(&mut block, SUPERBLOCK_FEATURE_INCOMPAT_OFFSET, 0x12);
+        let superblock = JournalSuperblock::read_bytes(&block).unwrap();
+        assert!(!superblock.is_64bit);
+        assert!(!superblock.has_checksums());
+    }
+
+    /// Test a journal with v2 checksums.
+    #[test]
+    fn test_journal_superblock_checksum_v2() {
+        let mut block = create_test_superblock();
+        write_u32be(&mut block, SUPERBLOCK_FEATURE_INCOMPAT_OFFSET, 0x8);
+        write_u32be(&mut block, SUPERBLOCK_CHECKSUM_OFFSET, 0x6f51_8e73);
+        let superblock = JournalSuperblock::read_bytes(&block).unwrap();
+        assert!(!superblock.is_64bit);
+        assert_eq!(
+            superblock.checksum_version,
+            Some(JournalChecksumVersion::V2)
+        );
+
+        // Both checksum versions cannot be enabled at the same time.
+        write_u32be(&mut block, SUPERBLOCK_FEATURE_INCOMPAT_OFFSET, 0x18);
         assert_eq!(
             JournalSuperblock::read_bytes(&block).unwrap_err(),
-            IncompatibleKind::MissingRequiredJournalFeatures(
-                (JournalIncompatibleFeatures::IS_64BIT
-                    | JournalIncompatibleFeatures::CHECKSUM_V3)
-                    .bits()
-            ),
+            CorruptKind::JournalChecksumFeatures
         );
     }
 
@@ -306,7 +404,8 @@ mod tests {
         write_u32be(
             &mut block,
             SUPERBLOCK_FEATURE_INCOMPAT_OFFSET,
-            (REQUIRED_FEATURES
+            (JournalIncompatibleFeatures::IS_64BIT
+                | JournalIncompatibleFeatures::CHECKSUM_V3
                 // Known but unsupported features.
                 | JournalIncompatibleFeatures::FAST_COMMITS
                 | JournalIncompatibleFeatures::ASYNC_COMMITS)
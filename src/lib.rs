@@ -65,6 +65,23 @@
 //! Note that the underlying data should never be changed while the
 //! filesystem is in use.
 //!
+//! If the data can only be read asynchronously, implement
+//! [`AsyncExt4Read`] instead, and use [`AsyncExt4`] to access the
+//! filesystem.
+//!
+//! # Threads
+//!
+//! By default, [`Ext4`] and the types that refer to it (such as
+//! [`File`], [`ReadDir`], and [`DirEntry`]) can only be used from a
+//! single thread. Enable the `sync` feature to make [`Ext4`] `Send` and
+//! `Sync`, and the other types `Send`. With that feature, a single
+//! loaded filesystem can be cloned and shared between threads. The
+//! `sync` feature also enables `std`, and requires [`Ext4Read`]
+//! implementations to be `Send`.
+//!
+//! Reads from the underlying storage and accesses to the block cache
+//! are serialized with a lock.
+//!
 //! # Paths
 //!
 //! Paths in the filesystem are represented by [`Path`] and
@@ -107,11 +124,13 @@
 
 extern crate alloc;
 
+mod async_ext4;
 mod block_cache;
 mod block_group;
 mod block_index;
 mod block_size;
 mod checksum;
+mod device_number;
 mod dir;
 mod dir_block;
 mod dir_entry;
@@ -119,61 +138,100 @@ mod dir_entry_hash;
 mod dir_htree;
 mod error;
 mod extent;
+mod extent_map;
 mod features;
 mod file;
+mod file_attributes;
 mod file_type;
 mod format;
+mod inline_data;
 mod inode;
 mod iters;
 mod journal;
 mod label;
 mod metadata;
+mod options;
 mod path;
+mod read_ahead;
 mod reader;
 mod resolve;
+mod stats;
 mod superblock;
+mod sync;
+mod timestamp;
 mod util;
 mod uuid;
+mod xattr;
 
 #[cfg(all(test, feature = "std"))]
 mod test_util;
 
 use alloc::boxed::Box;
-use alloc::rc::Rc;
+use alloc::collections::BTreeMap;
 use alloc::string::String;
 use alloc::vec;
 use alloc::vec::Vec;
 use block_cache::BlockCache;
-use block_group::BlockGroupDescriptor;
+use block_group::{BlockGroupDescriptor, BlockGroupIndex};
 use block_index::FsBlockIndex;
-use core::cell::RefCell;
 use core::fmt::{self, Debug, Formatter};
+use core::mem;
 use error::CorruptKind;
 use features::ReadOnlyCompatibleFeatures;
 use inode::{Inode, InodeIndex};
+use iters::file_blocks::FileBlocks;
 use journal::Journal;
 use resolve::FollowSymlinks;
+use stats::Stats;
 use superblock::Superblock;
+use sync::{Lock, Shared};
 use util::usize_from_u32;
 
+pub use async_ext4::{AsyncDirEntry, AsyncExt4, AsyncFile};
+pub use block_cache::{
+    BlockCacheBackend, BlockCacheKey, BlockKind, LruBlockCache,
+    SharedBlockCache, TwoQueueBlockCache,
+};
+pub use device_number::DeviceNumber;
 pub use dir_entry::{DirEntry, DirEntryName, DirEntryNameError};
 pub use error::{Corrupt, Ext4Error, Incompatible};
+pub use extent_map::{FileExtent, FileExtentFlags};
 pub use features::IncompatibleFeatures;
 pub use file::File;
+pub use file_attributes::FileAttributes;
 pub use file_type::FileType;
 pub use format::BytesDisplay;
 pub use iters::read_dir::ReadDir;
 pub use label::Label;
 pub use metadata::Metadata;
+pub use options::{Ext4Options, JournalMode};
 pub use path::{Component, Components, Path, PathBuf, PathError};
-pub use reader::{Ext4Read, MemIoError};
+pub use read_ahead::Advice;
+pub use reader::{AsyncExt4Read, Ext4Read, MemIoError};
+pub use stats::Ext4Stats;
+pub use timestamp::Timestamp;
 pub use uuid::Uuid;
 
 struct Ext4Inner {
     superblock: Superblock,
-    block_group_descriptors: Vec<BlockGroupDescriptor>,
+
+    /// Block group descriptors that have been loaded so far.
+    ///
+    /// Descriptors are read through the block cache (and their
+    /// checksums verified) the first time they are needed, so loading
+    /// the filesystem takes the same time regardless of the number of
+    /// block groups.
+    block_group_descriptors:
+        Lock<BTreeMap<BlockGroupIndex, BlockGroupDescriptor>>,
     journal: Journal,
-    block_cache: RefCell<BlockCache>,
+    block_cache: Lock<BlockCache>,
+
+    /// Options the filesystem was loaded with.
+    options: Ext4Options,
+
+    /// I/O statistics. Block cache statistics are stored in
+    /// `block_cache`.
+    stats: Stats,
 
     /// Reader providing access to the underlying storage.
     ///
@@ -183,25 +241,82 @@ struct Ext4Inner {
     /// The `Ext4Read::read` method takes `&mut self`, because readers
     /// like `std::fs::File` are mutable. However, the `Ext4` API is
     /// logically const -- it provides read-only access to the
-    /// filesystem. So the box is wrapped in `RefCell` to allow the
+    /// filesystem. So the box is wrapped in `Lock` to allow the
     /// mutable method to be called with an immutable `&Ext4Inner`
-    /// reference. `RefCell` enforces at runtime that only one mutable
-    /// borrow exists at a time.
-    reader: RefCell<Box<dyn Ext4Read>>,
+    /// reference. `Lock` ensures that only one mutable borrow exists
+    /// at a time.
+    reader: Lock<Box<dyn Ext4Read>>,
+}
+
+/// Part of a read passed to [`Ext4::read_from_blocks_direct`].
+struct DirectRead<'a> {
+    /// Absolute index of the first block, after journal remapping.
+    block_index: FsBlockIndex,
+
+    /// Absolute index of the first block, before journal
+    /// remapping. Just used for errors.
+    original_block_index: FsBlockIndex,
+
+    /// Byte offset within the first block to start reading from.
+    offset_within_block: u32,
+
+    /// Buffer to read into. This also controls the length of the read,
+    /// which may span any number of contiguous blocks.
+    dst: &'a mut [u8],
+}
+
+/// A run of contiguous blocks holding part of a file's data.
+struct BlockRun {
+    /// Byte offset of the run within the file.
+    offset: usize,
+
+    /// Length of the run in bytes. This is less than `num_blocks`
+    /// times the block size if the run is at the end of the file.
+    len: usize,
+
+    /// Absolute index of the first block, after journal remapping.
+    block_index: FsBlockIndex,
+
+    /// Absolute index of the first block, before journal
+    /// remapping. Just used for errors.
+    original_block_index: FsBlockIndex,
+
+    /// Number of blocks in the run.
+    num_blocks: u64,
 }
 
 /// Read-only access to an [ext4] filesystem.
 ///
+/// Cloning an `Ext4` is cheap; all clones share the same underlying
+/// reader and block cache. If the `sync` feature is enabled, clones
+/// can be sent to other threads.
+///
 /// [ext4]: https://en.wikipedia.org/wiki/Ext4
 #[derive(Clone)]
-pub struct Ext4(Rc<Ext4Inner>);
+pub struct Ext4(Shared<Ext4Inner>);
 
 impl Ext4 {
     /// Load an `Ext4` instance from the given `reader`.
     ///
-    /// This reads and validates the superblock, block group
-    /// descriptors, and journal. No other data is read.
-    pub fn load(mut reader: Box<dyn Ext4Read>) -> Result<Self, Ext4Error> {
+    /// This reads and validates the superblock and journal. Block group
+    /// descriptors are read and validated when first needed.
+    ///
+    /// This uses the default options; see [`Ext4::load_with_options`]
+    /// for more control.
+    pub fn load(reader: Box<dyn Ext4Read>) -> Result<Self, Ext4Error> {
+        Self::load_with_options(reader, Ext4Options::default())
+    }
+
+    /// Load an `Ext4` instance from the given `reader`, using
+    /// `options` to control how the filesystem is loaded.
+    ///
+    /// This reads and validates the superblock and (depending on the
+    /// [`JournalMode`]) the journal. Block group descriptors are read
+    /// and validated when first needed.
+    pub fn load_with_options(
+        mut reader: Box<dyn Ext4Read>,
+        options: Ext4Options,
+    ) -> Result<Self, Ext4Error> {
         // The first 1024 bytes are reserved for "weird" stuff like x86
         // boot sectors.
         let superblock_start = 1024;
@@ -209,35 +324,44 @@ impl Ext4 {
         reader
             .read(superblock_start, &mut data)
             .map_err(Ext4Error::Io)?;
+        let stats = Stats::new();
+        stats.record_reads(1, data.len());
+
+        let superblock =
+            Superblock::from_bytes(&data, options.verify_checksums)?;
+        let block_cache = BlockCache::new(
+            superblock.block_size,
+            superblock.blocks_count,
+            &options,
+        )?;
 
-        let superblock = Superblock::from_bytes(&data)?;
-        let block_cache =
-            BlockCache::new(superblock.block_size, superblock.blocks_count)?;
-
-        let mut fs = Self(Rc::new(Ext4Inner {
-            block_group_descriptors: BlockGroupDescriptor::read_all(
-                &superblock,
-                &mut *reader,
-            )?,
-            reader: RefCell::new(reader),
+        let mut fs = Self(Shared::new(Ext4Inner {
+            block_group_descriptors: Lock::new(BTreeMap::new()),
+            reader: Lock::new(reader),
             superblock,
             // Initialize with an empty journal, because loading the
             // journal requires a valid `Ext4` object.
             journal: Journal::empty(),
-            block_cache: RefCell::new(block_cache),
+            block_cache: Lock::new(block_cache),
+            options,
+            stats,
         }));
 
         // Load the actual journal, if present.
-        let journal = Journal::load(&fs)?;
-        Rc::get_mut(&mut fs.0).unwrap().journal = journal;
+        let journal = Journal::load(&fs, fs.0.options.journal_mode)?;
+        let inner = Shared::get_mut(&mut fs.0).unwrap();
+        inner.journal = journal;
+        // Descriptors loaded while reading the journal may be stale if
+        // the journal contains updates to the descriptor table.
+        inner.block_group_descriptors.lock().clear();
 
         Ok(fs)
     }
 
     /// Load an `Ext4` filesystem from the given `path`.
     ///
-    /// This reads and validates the superblock and block group
-    /// descriptors. No other data is read.
+    /// This reads and validates the superblock and journal. No other
+    /// data is read.
     #[cfg(feature = "std")]
     pub fn load_from_path<P: AsRef<std::path::Path>>(
         path: P,
@@ -263,6 +387,53 @@ impl Ext4 {
         self.0.superblock.uuid
     }
 
+    /// Get I/O and cache statistics for the filesystem.
+    ///
+    /// The counters start at zero when the filesystem is loaded (so
+    /// they include the reads performed while loading), and can be
+    /// reset with [`Ext4::reset_stats`]. All clones of an `Ext4` share
+    /// the same counters.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # fn example(fs: &ext4_view::Ext4) -> Result<(), ext4_view::Ext4Error> {
+    /// fs.reset_stats();
+    /// let data = fs.read("/some/file")?;
+    /// let stats = fs.stats();
+    /// println!(
+    ///     "{} reads, {} bytes, {} cache hits",
+    ///     stats.reads(),
+    ///     stats.bytes_read(),
+    ///     stats.cache_hits(),
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn stats(&self) -> Ext4Stats {
+        let cache_counters = self.0.block_cache.lock().counters();
+        self.0.stats.snapshot(cache_counters)
+    }
+
+    /// Reset all statistics counters to zero. See [`Ext4::stats`].
+    pub fn reset_stats(&self) {
+        self.0.stats.reset();
+        self.0.block_cache.lock().reset_counters();
+    }
+
+    /// Remap `block_index` through the journal, recording any remap in
+    /// the statistics. This should only be called when the block is
+    /// about to be read; use `self.0.journal.map_block_index` to look
+    /// up a block without reading it.
+    fn map_block_index(&self, block_index: FsBlockIndex) -> FsBlockIndex {
+        let mapped = self.0.journal.map_block_index(block_index);
+        if mapped != block_index {
+            self.0.stats.record_journal_remaps(1);
+        }
+        mapped
+    }
+
     /// Return true if the filesystem has metadata checksums enabled,
     /// false otherwise.
     fn has_metadata_checksums(&self) -> bool {
@@ -272,12 +443,78 @@ impl Ext4 {
             .contains(ReadOnlyCompatibleFeatures::METADATA_CHECKSUMS)
     }
 
+    /// Return true if metadata checksums should be verified. This is
+    /// the case if the filesystem has metadata checksums, and
+    /// verification has not been disabled with
+    /// [`Ext4Options::verify_checksums`].
+    fn verify_checksums(&self) -> bool {
+        self.has_metadata_checksums() && self.0.options.verify_checksums
+    }
+
+    /// Record a metadata checksum verification in the statistics.
+    fn record_checksum_verification(&self) {
+        self.0.stats.record_checksum_verifications(1);
+    }
+
+    /// Get the descriptor of block group `index`, reading it if it
+    /// hasn't been loaded yet.
+    fn block_group_descriptor(
+        &self,
+        index: BlockGroupIndex,
+    ) -> Result<BlockGroupDescriptor, Ext4Error> {
+        if let Some(bgd) = self.0.block_group_descriptors.lock().get(&index) {
+            return Ok(*bgd);
+        }
+
+        // The lock is not held while reading, since reading may need to
+        // look up other descriptors.
+        let bgd = BlockGroupDescriptor::read(self, index)?;
+        self.0.block_group_descriptors.lock().insert(index, bgd);
+        Ok(bgd)
+    }
+
     /// Read the inode of the root `/` directory.
     fn read_root_inode(&self) -> Result<Inode, Ext4Error> {
         let root_inode_index = InodeIndex::new(2).unwrap();
         Inode::read(self, root_inode_index)
     }
 
+    /// Read metadata from a block. See [`Ext4::read_block_of_kind`].
+    fn read_from_block(
+        &self,
+        original_block_index: FsBlockIndex,
+        offset_within_block: u32,
+        dst: &mut [u8],
+    ) -> Result<(), Ext4Error> {
+        self.read_block_of_kind(
+            original_block_index,
+            offset_within_block,
+            dst,
+            BlockKind::Metadata,
+            u32::MAX,
+        )
+    }
+
+    /// Read file data from a block. See [`Ext4::read_block_of_kind`].
+    ///
+    /// If the block is not cached, up to `max_blocks` blocks starting
+    /// with this one are read into the cache.
+    fn read_data_from_block(
+        &self,
+        original_block_index: FsBlockIndex,
+        offset_within_block: u32,
+        dst: &mut [u8],
+        max_blocks: u32,
+    ) -> Result<(), Ext4Error> {
+        self.read_block_of_kind(
+            original_block_index,
+            offset_within_block,
+            dst,
+            BlockKind::Data,
+            max_blocks,
+        )
+    }
+
     /// Read data from a block.
     ///
     /// `block_index`: an absolute block within the filesystem.
@@ -297,20 +534,52 @@ impl Ext4 {
     ///
     /// If any of these conditions are violated, a `CorruptKind::BlockRead`
     /// error is returned.
-    fn read_from_block(
+    ///
+    /// `kind`: what the block holds. This is passed to the block cache.
+    ///
+    /// `max_blocks`: the maximum number of blocks to read into the
+    /// block cache if the block is not already cached. The number is
+    /// also limited by [`Ext4Options::read_ahead_size`].
+    fn read_block_of_kind(
         &self,
         original_block_index: FsBlockIndex,
         offset_within_block: u32,
         dst: &mut [u8],
+        kind: BlockKind,
+        max_blocks: u32,
     ) -> Result<(), Ext4Error> {
-        let block_index = self.0.journal.map_block_index(original_block_index);
+        let block_index = self.map_block_index(original_block_index);
+        self.read_mapped_block_of_kind(
+            block_index,
+            original_block_index,
+            offset_within_block,
+            dst,
+            kind,
+            max_blocks,
+        )
+    }
 
+    /// Read data from a block that has already been remapped through
+    /// the journal. See [`Ext4::read_block_of_kind`].
+    ///
+    /// `original_block_index`: the block index before journal
+    /// remapping. Just used for errors.
+    fn read_mapped_block_of_kind(
+        &self,
+        block_index: FsBlockIndex,
+        original_block_index: FsBlockIndex,
+        offset_within_block: u32,
+        dst: &mut [u8],
+        kind: BlockKind,
+        max_blocks: u32,
+    ) -> Result<(), Ext4Error> {
+        let read_len = dst.len();
         let err = || {
             Ext4Error::from(CorruptKind::BlockRead {
                 block_index,
                 original_block_index,
                 offset_within_block,
-                read_len: dst.len(),
+                read_len,
             })
         };
 
@@ -340,9 +609,12 @@ impl Ext4 {
             return Err(err());
         }
 
-        let mut block_cache = self.0.block_cache.borrow_mut();
-        let cached_block = block_cache.get_or_insert_blocks(
+        self.0.block_cache.lock().read(
             block_index,
+            offset_within_block,
+            dst,
+            kind,
+            max_blocks,
             |buf: &mut [u8]| {
                 // Get the absolute byte to start reading from.
                 let start_byte = block_index
@@ -350,15 +622,200 @@ impl Ext4 {
                     .ok_or_else(err)?;
                 self.0
                     .reader
-                    .borrow_mut()
+                    .lock()
                     .read(start_byte, buf)
-                    .map_err(Ext4Error::Io)
+                    .map_err(Ext4Error::Io)?;
+                self.0.stats.record_reads(1, buf.len());
+                Ok(())
             },
-        )?;
+        )
+    }
+
+    /// Read data from runs of contiguous blocks.
+    ///
+    /// Blocks that are already in the block cache are copied from
+    /// there. The remaining blocks are read directly from the reader,
+    /// bypassing the block cache. Each span of uncached blocks is a
+    /// single read, and all of the reads are passed to
+    /// [`Ext4Read::read_many`] at once.
+    ///
+    /// If any run is not within the filesystem, a
+    /// `CorruptKind::BlockRead` error is returned.
+    fn read_from_blocks_direct(
+        &self,
+        runs: Vec<DirectRead<'_>>,
+    ) -> Result<(), Ext4Error> {
+        let block_size = self.0.superblock.block_size;
+        let fs_size = self
+            .0
+            .superblock
+            .blocks_count
+            .checked_mul(block_size.to_u64());
+
+        let mut reads: Vec<(u64, &mut [u8])> = Vec::new();
+        for run in runs {
+            let DirectRead {
+                block_index,
+                original_block_index,
+                offset_within_block,
+                dst,
+            } = run;
+            let err = || {
+                Ext4Error::from(CorruptKind::BlockRead {
+                    block_index,
+                    original_block_index,
+                    offset_within_block,
+                    read_len: dst.len(),
+                })
+            };
+
+            // The first 1024 bytes are reserved for non-filesystem data.
+            if block_index == 0 && offset_within_block < 1024 {
+                return Err(err());
+            }
+
+            // The start of the read must be less than the block size.
+            if offset_within_block >= block_size {
+                return Err(err());
+            }
+
+            // Get the absolute bytes to start and end reading at.
+            let start_byte = block_index
+                .checked_mul(block_size.to_u64())
+                .and_then(|b| b.checked_add(u64::from(offset_within_block)))
+                .ok_or_else(err)?;
+            let end_byte = u64::try_from(dst.len())
+                .ok()
+                .and_then(|len| start_byte.checked_add(len))
+                .ok_or_else(err)?;
+
+            // The end of the read must be within the filesystem.
+            if end_byte > fs_size.ok_or_else(err)? {
+                return Err(err());
+            }
+
+            // Copy blocks that are already in the cache, and split the
+            // rest of `dst` into spans of uncached blocks. `remaining`
+            // starts with the current span, which is `span_len` bytes
+            // long, and begins at `span_start_byte` on disk.
+            let mut remaining: &mut [u8] = dst;
+            let mut span_len: usize = 0;
+            let mut span_start_byte = start_byte;
+            let mut block_index = block_index;
+            let mut offset_within_block = offset_within_block;
+            while span_len < remaining.len() {
+                // OK to unwrap: `offset_within_block` is less than the
+                // block size, and `span_len` is less than
+                // `remaining.len()`.
+                let chunk_len = usize_from_u32(
+                    block_size
+                        .to_u32()
+                        .checked_sub(offset_within_block)
+                        .unwrap(),
+                )
+                .min(remaining.len().checked_sub(span_len).unwrap());
+                // OK to unwrap: `chunk_len` is at most
+                // `remaining.len() - span_len`.
+                let chunk_end = span_len.checked_add(chunk_len).unwrap();
+
+                if self.0.block_cache.lock().read_cached(
+                    block_index,
+                    offset_within_block,
+                    &mut remaining[span_len..chunk_end],
+                ) {
+                    let (span, rest) =
+                        mem::take(&mut remaining).split_at_mut(span_len);
+                    if !span.is_empty() {
+                        reads.push((span_start_byte, span));
+                    }
+                    remaining = &mut rest[chunk_len..];
+                    // OK to unwrap: the read is within the filesystem,
+                    // which was checked above.
+                    span_start_byte = span_start_byte
+                        .checked_add(u64::try_from(chunk_end).unwrap())
+                        .unwrap();
+                    span_len = 0;
+                } else {
+                    span_len = chunk_end;
+                }
+
+                offset_within_block = 0;
+                // OK to unwrap: the run is within the filesystem.
+                block_index = block_index.checked_add(1).unwrap();
+            }
+            if !remaining.is_empty() {
+                reads.push((span_start_byte, remaining));
+            }
+        }
 
-        dst.copy_from_slice(
-            &cached_block[usize_from_u32(offset_within_block)..read_end],
+        if reads.is_empty() {
+            return Ok(());
+        }
+        self.0
+            .reader
+            .lock()
+            .read_many(&mut reads)
+            .map_err(Ext4Error::Io)?;
+        self.0.stats.record_reads(
+            reads.len(),
+            reads.iter().map(|(_, buf)| buf.len()).sum(),
         );
+        Ok(())
+    }
+
+    /// Read file data from a run of contiguous blocks into `dst`,
+    /// through the block cache.
+    ///
+    /// `block_index`: the absolute index of the first block in the
+    /// run. This must already be remapped through the journal.
+    ///
+    /// `original_block_index`: the block index before journal
+    /// remapping. Just used for errors.
+    ///
+    /// `offset_within_block`: the byte offset within the first block to
+    /// start reading from.
+    ///
+    /// `dst`: byte buffer to read into. This also controls the length
+    /// of the read, which may span any number of blocks.
+    ///
+    /// `max_blocks`: the maximum number of blocks, starting with the
+    /// first block of the run, to read into the block cache when a
+    /// block is not cached.
+    fn read_data_from_run(
+        &self,
+        mut block_index: FsBlockIndex,
+        original_block_index: FsBlockIndex,
+        mut offset_within_block: u32,
+        mut dst: &mut [u8],
+        mut max_blocks: u32,
+    ) -> Result<(), Ext4Error> {
+        let block_size = self.0.superblock.block_size;
+
+        while !dst.is_empty() {
+            // The offset is only nonzero for the first block. If it is
+            // not less than the block size, the read fails below.
+            let bytes_remaining_in_block =
+                block_size.to_u32().saturating_sub(offset_within_block);
+            let chunk_len =
+                dst.len().min(usize_from_u32(bytes_remaining_in_block));
+            let (chunk, rest) = mem::take(&mut dst).split_at_mut(chunk_len);
+
+            self.read_mapped_block_of_kind(
+                block_index,
+                original_block_index,
+                offset_within_block,
+                chunk,
+                BlockKind::Data,
+                max_blocks,
+            )?;
+
+            dst = rest;
+            offset_within_block = 0;
+            // OK to unwrap: the block was just read, so it is less than
+            // the number of blocks in the filesystem.
+            block_index = block_index.checked_add(1).unwrap();
+            max_blocks = max_blocks.saturating_sub(1);
+        }
 
         Ok(())
     }
@@ -367,27 +824,142 @@ impl Ext4 {
     ///
     /// Holes are filled with zero.
     ///
+    /// The file's data is read directly from the reader rather than
+    /// through the block cache. Each run of contiguous blocks is read
+    /// with a single request, and all requests are passed to
+    /// [`Ext4Read::read_many`] at once.
+    ///
     /// Fails with `FileTooLarge` if the size of the file is too large
     /// to fit in a [`usize`].
     fn read_inode_file(&self, inode: &Inode) -> Result<Vec<u8>, Ext4Error> {
         // Get the file size and initialize the output vector.
         let file_size_in_bytes = usize::try_from(inode.metadata.size_in_bytes)
             .map_err(|_| Ext4Error::FileTooLarge)?;
+
+        if inode.metadata.flags.contains(FileAttributes::INLINE_DATA) {
+            return inline_data::read_inline_data(self, inode);
+        }
+
         let mut dst = vec![0; file_size_in_bytes];
 
-        // Use `File` to read the data in chunks.
-        let mut file = File::open_inode(self, inode.clone())?;
-        let mut remaining = dst.as_mut();
-        loop {
-            let bytes_read = file.read_bytes(remaining)?;
-            if bytes_read == 0 {
-                break;
+        let runs = self.get_block_runs(inode)?;
+
+        // Split `dst` into one buffer per run. Holes are skipped, since
+        // `dst` is already zeroed.
+        let block_size = self.0.superblock.block_size;
+        let mut reads: Vec<(u64, &mut [u8])> = Vec::with_capacity(runs.len());
+        let mut remaining: &mut [u8] = &mut dst;
+        let mut remaining_start: usize = 0;
+        for run in runs {
+            let err = || {
+                Ext4Error::from(CorruptKind::BlockRead {
+                    block_index: run.block_index,
+                    original_block_index: run.original_block_index,
+                    offset_within_block: 0,
+                    read_len: run.len,
+                })
+            };
+
+            // Check that the run is within the filesystem.
+            let end_block = run
+                .block_index
+                .checked_add(run.num_blocks)
+                .ok_or_else(err)?;
+            if end_block > self.0.superblock.blocks_count {
+                return Err(err());
             }
-            remaining = &mut remaining[bytes_read..];
+
+            // OK to unwrap: runs are in increasing order and do not
+            // overlap, and each run ends within the file.
+            let skip = run.offset.checked_sub(remaining_start).unwrap();
+            let (chunk, rest) =
+                mem::take(&mut remaining)[skip..].split_at_mut(run.len);
+            remaining = rest;
+            remaining_start = run.offset.checked_add(run.len).unwrap();
+
+            let start_byte = run
+                .block_index
+                .checked_mul(block_size.to_u64())
+                .ok_or_else(err)?;
+            reads.push((start_byte, chunk));
         }
+
+        self.0
+            .reader
+            .lock()
+            .read_many(&mut reads)
+            .map_err(Ext4Error::Io)?;
+        self.0.stats.record_reads(
+            reads.len(),
+            reads.iter().map(|(_, chunk)| chunk.len()).sum(),
+        );
+
         Ok(dst)
     }
 
+    /// Get the runs of contiguous blocks that hold a file's data,
+    /// ordered by position within the file. Holes are not included.
+    ///
+    /// Block indices are remapped through the journal before checking
+    /// if they are contiguous.
+    fn get_block_runs(
+        &self,
+        inode: &Inode,
+    ) -> Result<Vec<BlockRun>, Ext4Error> {
+        // OK to unwrap: the caller has checked that the file size fits
+        // in a `usize`.
+        let file_size_in_bytes =
+            usize::try_from(inode.metadata.size_in_bytes).unwrap();
+        let block_size = self.0.superblock.block_size.to_usize();
+
+        let mut runs: Vec<BlockRun> = Vec::new();
+        let mut offset: usize = 0;
+        for original_block_index in FileBlocks::new(self.clone(), inode)? {
+            let original_block_index = original_block_index?;
+            let block_start = offset;
+            // OK to unwrap: `FileBlocks` stops at the end of the file,
+            // so `offset` is less than the file size.
+            offset = offset
+                .checked_add(block_size)
+                .unwrap()
+                .min(file_size_in_bytes);
+            // OK to unwrap: `offset` was just advanced past
+            // `block_start`.
+            let len = offset.checked_sub(block_start).unwrap();
+
+            // Skip holes.
+            if original_block_index == 0 {
+                continue;
+            }
+            let block_index = self.map_block_index(original_block_index);
+
+            // Extend the previous run if this block directly follows it.
+            if let Some(run) = runs.last_mut() {
+                if run.offset.checked_add(run.len) == Some(block_start)
+                    && run.block_index.checked_add(run.num_blocks)
+                        == Some(block_index)
+                {
+                    // OK to unwrap: the run is within the file, so its
+                    // length fits in a `usize`, and the number of blocks
+                    // fits in a `u64`.
+                    run.len = run.len.checked_add(len).unwrap();
+                    run.num_blocks = run.num_blocks.checked_add(1).unwrap();
+                    continue;
+                }
+            }
+
+            runs.push(BlockRun {
+                offset: block_start,
+                len,
+                block_index,
+                original_block_index,
+                num_blocks: 1,
+            });
+        }
+
+        Ok(runs)
+    }
+
     /// Follow a path to get an inode.
     fn path_to_inode(
         &self,
@@ -629,6 +1201,82 @@ impl Ext4 {
     }
 }
 
+/// These methods provide access to extended attributes.
+impl Ext4 {
+    /// Get the names of all extended attributes of the file at `path`.
+    ///
+    /// Names include the namespace prefix, for example
+    /// `user.some_name` or `security.selinux`.
+    ///
+    /// If `path` is a symlink, it is followed, like `listxattr(2)`.
+    ///
+    /// # Errors
+    ///
+    /// An error will be returned if:
+    /// * `path` is not absolute.
+    /// * `path` does not exist.
+    ///
+    /// This is not an exhaustive list of errors, see the
+    /// [crate documentation](crate#errors).
+    pub fn list_xattrs<'p, P>(&self, path: P) -> Result<Vec<Vec<u8>>, Ext4Error>
+    where
+        P: TryInto<Path<'p>>,
+    {
+        fn inner(fs: &Ext4, path: Path<'_>) -> Result<Vec<Vec<u8>>, Ext4Error> {
+            let inode = fs.path_to_inode(path, FollowSymlinks::All)?;
+            xattr::list_xattrs(fs, &inode)
+        }
+
+        inner(self, path.try_into().map_err(|_| Ext4Error::MalformedPath)?)
+    }
+
+    /// Get the value of the extended attribute `name` of the file at
+    /// `path`.
+    ///
+    /// `name` must include the namespace prefix, for example
+    /// `user.some_name` or `security.selinux`. Returns `Ok(None)` if
+    /// the attribute does not exist.
+    ///
+    /// POSIX ACLs (`system.posix_acl_access` and
+    /// `system.posix_acl_default`) are returned in the same format as
+    /// `getxattr(2)`, not the ext4 on-disk format.
+    ///
+    /// If `path` is a symlink, it is followed, like `getxattr(2)`.
+    ///
+    /// # Errors
+    ///
+    /// An error will be returned if:
+    /// * `path` is not absolute.
+    /// * `path` does not exist.
+    ///
+    /// This is not an exhaustive list of errors, see the
+    /// [crate documentation](crate#errors).
+    pub fn get_xattr<'p, P, N>(
+        &self,
+        path: P,
+        name: N,
+    ) -> Result<Option<Vec<u8>>, Ext4Error>
+    where
+        P: TryInto<Path<'p>>,
+        N: AsRef<[u8]>,
+    {
+        fn inner(
+            fs: &Ext4,
+            path: Path<'_>,
+            name: &[u8],
+        ) -> Result<Option<Vec<u8>>, Ext4Error> {
+            let inode = fs.path_to_inode(path, FollowSymlinks::All)?;
+            xattr::get_xattr(fs, &inode, name)
+        }
+
+        inner(
+            self,
+            path.try_into().map_err(|_| Ext4Error::MalformedPath)?,
+            name.as_ref(),
+        )
+    }
+}
+
 impl Debug for Ext4 {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         // Exclude the reader field, which does not impl Debug. Even if
@@ -636,7 +1284,6 @@ impl Debug for Ext4 {
         // is a Vec it might contain many megabytes of data).
         f.debug_struct("Ext4")
             .field("superblock", &self.0.superblock)
-            .field("block_group_descriptors", &self.0.block_group_descriptors)
             .finish_non_exhaustive()
     }
 }
@@ -661,19 +1308,53 @@ mod tests {
             CorruptKind::SuperblockMagic
         );
 
-        // Not enough data to read the block group descriptors.
+        // Not enough data to read the block group descriptors. The
+        // descriptors aren't read until needed, so loading succeeds.
         let mut fs_data = vec![0; 2048];
         fs_data[1024..2048]
             .copy_from_slice(include_bytes!("../test_data/raw_superblock.bin"));
-        assert!(matches!(
-            Ext4::load(Box::new(fs_data.clone())).unwrap_err(),
-            Ext4Error::Io(_)
-        ));
+        let fs = Ext4::load(Box::new(fs_data.clone())).unwrap();
+        assert!(matches!(fs.metadata("/").unwrap_err(), Ext4Error::Io(_)));
 
         // Invalid block group descriptor checksum.
-        fs_data.resize(3048usize, 0u8);
+        fs_data.resize(128 * 1024, 0u8);
+        let fs = Ext4::load(Box::new(fs_data.clone())).unwrap();
+        assert_eq!(
+            fs.metadata("/").unwrap_err(),
+            CorruptKind::BlockGroupDescriptorChecksum(0)
+        );
+    }
+
+    /// Test that block group descriptors are read when first used,
+    /// rather than when the filesystem is loaded.
+    #[test]
+    fn test_lazy_block_group_descriptors() {
+        let mut fs_data =
+            test_util::read_compressed_filesystem("test_disk1.bin.zst");
+        let fs = Ext4::load(Box::new(fs_data.clone())).unwrap();
+
+        // Only the superblock is read during loading.
+        assert_eq!(fs.stats().reads(), 1);
+        assert!(fs.0.block_group_descriptors.lock().is_empty());
+
+        // Looking up the root inode loads its block group descriptor.
+        fs.metadata("/").unwrap();
+        assert_eq!(fs.0.block_group_descriptors.lock().len(), 1);
+        assert!(fs.stats().checksum_verifications() > 0);
+
+        // Corrupt the checksum of the first descriptor. Loading still
+        // succeeds, but the error is reported when the descriptor is
+        // used.
+        let sb = &fs.0.superblock;
+        let bgd_start = if sb.block_size == 1024 {
+            2048
+        } else {
+            sb.block_size.to_usize()
+        };
+        fs_data[bgd_start + 0x1e] ^= 0xff;
+        let fs = Ext4::load(Box::new(fs_data)).unwrap();
         assert_eq!(
-            Ext4::load(Box::new(fs_data.clone())).unwrap_err(),
+            fs.metadata("/").unwrap_err(),
             CorruptKind::BlockGroupDescriptorChecksum(0)
         );
     }
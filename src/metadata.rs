@@ -6,8 +6,11 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use crate::device_number::DeviceNumber;
+use crate::file_attributes::FileAttributes;
 use crate::file_type::FileType;
-use crate::inode::InodeMode;
+use crate::inode::{InodeIndex, InodeMode};
+use crate::timestamp::Timestamp;
 
 /// Metadata information about a file.
 #[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
@@ -26,6 +29,42 @@ pub struct Metadata {
 
     /// Owner group ID.
     pub(crate) gid: u32,
+
+    /// Last access time.
+    pub(crate) atime: Timestamp,
+
+    /// Last inode change time.
+    pub(crate) ctime: Timestamp,
+
+    /// Last data modification time.
+    pub(crate) mtime: Timestamp,
+
+    /// Creation time. Only present in inodes larger than 128 bytes.
+    pub(crate) crtime: Option<Timestamp>,
+
+    /// Inode number.
+    pub(crate) inode: InodeIndex,
+
+    /// Number of hard links.
+    pub(crate) links_count: u16,
+
+    /// Number of 512-byte units allocated to the file.
+    pub(crate) blocks: u64,
+
+    /// Inode generation number.
+    pub(crate) generation: u32,
+
+    /// Inode version.
+    pub(crate) version: u64,
+
+    /// Block containing extended attributes, or zero if none.
+    pub(crate) file_acl: u64,
+
+    /// Inode flags.
+    pub(crate) flags: FileAttributes,
+
+    /// Device number, if the file is a character or block device.
+    pub(crate) rdev: Option<DeviceNumber>,
 }
 
 impl Metadata {
@@ -91,4 +130,98 @@ impl Metadata {
     pub fn gid(&self) -> u32 {
         self.gid
     }
+
+    /// Last modification time of the file's data (`mtime`).
+    #[must_use]
+    pub fn modified(&self) -> Timestamp {
+        self.mtime
+    }
+
+    /// Last access time (`atime`).
+    ///
+    /// Note that filesystems are often mounted with options such as
+    /// `relatime` or `noatime`, so this value may not be updated on
+    /// every access.
+    #[must_use]
+    pub fn accessed(&self) -> Timestamp {
+        self.atime
+    }
+
+    /// Last change time of the file's metadata (`ctime`).
+    #[must_use]
+    pub fn changed(&self) -> Timestamp {
+        self.ctime
+    }
+
+    /// Creation time (`crtime`).
+    ///
+    /// Returns `None` if the inode is too small to store the creation
+    /// time (e.g. a filesystem with 128-byte inodes).
+    #[must_use]
+    pub fn created(&self) -> Option<Timestamp> {
+        self.crtime
+    }
+
+    /// Inode number.
+    #[must_use]
+    pub fn ino(&self) -> u32 {
+        self.inode.get()
+    }
+
+    /// Number of hard links to the file.
+    ///
+    /// Directories with more than 65,000 subdirectories (only allowed
+    /// with the `dir_nlink` feature) report a link count of 1.
+    #[must_use]
+    pub fn nlink(&self) -> u16 {
+        self.links_count
+    }
+
+    /// Number of 512-byte units allocated to the file, matching
+    /// `st_blocks` in [stat(2)][stat].
+    ///
+    /// This includes blocks used for metadata such as extent tree
+    /// nodes, and does not include holes.
+    ///
+    /// [stat]: https://www.man7.org/linux/man-pages/man2/stat.2.html
+    #[must_use]
+    pub fn blocks(&self) -> u64 {
+        self.blocks
+    }
+
+    /// Inode generation number, used by NFS to detect reuse of an
+    /// inode number.
+    #[must_use]
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+
+    /// Inode version, incremented when the inode changes (if the
+    /// filesystem is mounted with `i_version`).
+    #[must_use]
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Absolute index of the block containing extended attributes, or
+    /// zero if the file has no extended attribute block.
+    #[must_use]
+    pub fn file_acl(&self) -> u64 {
+        self.file_acl
+    }
+
+    /// Get the file's attributes, as shown by [lsattr].
+    ///
+    /// [lsattr]: https://www.man7.org/linux/man-pages/man1/lsattr.1.html
+    #[must_use]
+    pub fn attributes(&self) -> FileAttributes {
+        self.flags
+    }
+
+    /// Get the device number, if the file is a character or block
+    /// device. For other file types, returns `None`.
+    #[must_use]
+    pub fn rdev(&self) -> Option<DeviceNumber> {
+        self.rdev
+    }
 }
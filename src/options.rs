@@ -0,0 +1,185 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::block_cache::SharedBlockCache;
+
+/// How the filesystem's journal is handled when loading.
+///
+/// The journal contains changes that have not yet been written to
+/// their final location in the filesystem. When the journal is
+/// replayed, reads of blocks that are in the journal return the
+/// journaled data. The underlying storage is never modified.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum JournalMode {
+    /// Always replay the journal, even if the superblock indicates
+    /// that recovery is not needed.
+    Replay,
+
+    /// Replay the journal only if the superblock indicates that
+    /// recovery is needed. This is what the kernel does when mounting
+    /// the filesystem.
+    #[default]
+    ReplayIfNeeded,
+
+    /// Ignore the journal, reading the filesystem exactly as it is on
+    /// disk.
+    ///
+    /// This is useful for comparing the on-disk state to the journaled
+    /// state, or for accessing a filesystem with a corrupt journal.
+    Ignore,
+}
+
+/// Options for loading an [`Ext4`] filesystem.
+///
+/// Pass to [`Ext4::load_with_options`]. The default options are the
+/// same as those used by [`Ext4::load`].
+///
+/// # Example
+///
+/// ```
+/// use ext4_view::{Ext4Options, JournalMode};
+///
+/// let options = Ext4Options::new()
+///     .journal_mode(JournalMode::Ignore)
+///     .cache_entries(16)
+///     .read_ahead_size(4096)
+///     .max_symlinks(8);
+/// ```
+///
+/// [`Ext4`]: crate::Ext4
+/// [`Ext4::load`]: crate::Ext4::load
+/// [`Ext4::load_with_options`]: crate::Ext4::load_with_options
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Ext4Options {
+    pub(crate) journal_mode: JournalMode,
+    pub(crate) cache_entries: Option<usize>,
+    pub(crate) read_ahead_size: u32,
+    pub(crate) verify_checksums: bool,
+    pub(crate) max_symlinks: usize,
+    pub(crate) block_cache: Option<SharedBlockCache>,
+}
+
+impl Ext4Options {
+    /// Default value for [`Ext4Options::read_ahead_size`].
+    pub const DEFAULT_READ_AHEAD_SIZE: u32 = 8 * 4096;
+
+    /// Default value for [`Ext4Options::max_symlinks`]. This matches
+    /// the limit used by Linux.
+    pub const DEFAULT_MAX_SYMLINKS: usize = 40;
+
+    /// Upper bound for [`Ext4Options::max_symlinks`]. Larger values
+    /// are clamped to this.
+    pub const MAX_SYMLINKS_LIMIT: usize = 1024;
+
+    /// Create options with default values.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set how the journal is handled. Defaults to
+    /// [`JournalMode::ReplayIfNeeded`].
+    #[must_use]
+    pub fn journal_mode(mut self, journal_mode: JournalMode) -> Self {
+        self.journal_mode = journal_mode;
+        self
+    }
+
+    /// Set the number of blocks held in the block cache.
+    ///
+    /// By default, the cache holds eight times the number of blocks
+    /// read at once (see [`Ext4Options::read_ahead_size`]). If the
+    /// value is smaller than the number of blocks read at once, that
+    /// number is used instead.
+    ///
+    /// Each entry uses one block of memory. Entries are allocated as
+    /// blocks are added to the cache.
+    ///
+    /// This has no effect if a shared cache is set with
+    /// [`Ext4Options::block_cache`].
+    #[must_use]
+    pub fn cache_entries(mut self, num_entries: usize) -> Self {
+        self.cache_entries = Some(num_entries);
+        self
+    }
+
+    /// Set the maximum number of bytes read from the underlying storage
+    /// at once when a block is not in the cache. The value is rounded
+    /// down to a multiple of the block size, but at least one block is
+    /// always read.
+    ///
+    /// For file data, the number of blocks read also depends on the
+    /// layout of the file and how it is being accessed; see
+    /// [`File::advise`].
+    ///
+    /// Defaults to [`Ext4Options::DEFAULT_READ_AHEAD_SIZE`].
+    ///
+    /// [`File::advise`]: crate::File::advise
+    #[must_use]
+    pub fn read_ahead_size(mut self, num_bytes: u32) -> Self {
+        self.read_ahead_size = num_bytes;
+        self
+    }
+
+    /// Set whether metadata checksums are verified. Defaults to `true`.
+    ///
+    /// If `false`, checksums in the superblock, block group
+    /// descriptors, inodes, extent trees, directory blocks, and
+    /// extended attribute blocks are not checked. This can be used to
+    /// access a filesystem with damaged metadata. Note that corrupt
+    /// metadata may still cause other errors.
+    ///
+    /// Journal checksums are always verified, since they are used to
+    /// detect incomplete journal transactions.
+    #[must_use]
+    pub fn verify_checksums(mut self, verify: bool) -> Self {
+        self.verify_checksums = verify;
+        self
+    }
+
+    /// Set the maximum number of symlinks followed when resolving a
+    /// path. If the limit is exceeded, [`Ext4Error::TooManySymlinks`]
+    /// is returned.
+    ///
+    /// Defaults to [`Ext4Options::DEFAULT_MAX_SYMLINKS`]. Values
+    /// larger than [`Ext4Options::MAX_SYMLINKS_LIMIT`] are clamped to
+    /// that limit, so that path resolution is always bounded.
+    ///
+    /// [`Ext4Error::TooManySymlinks`]: crate::Ext4Error::TooManySymlinks
+    #[must_use]
+    pub fn max_symlinks(mut self, max_symlinks: usize) -> Self {
+        self.max_symlinks = max_symlinks.min(Self::MAX_SYMLINKS_LIMIT);
+        self
+    }
+
+    /// Store cached blocks in `cache`, which may be shared with other
+    /// filesystems.
+    ///
+    /// By default, each filesystem has its own [`TwoQueueBlockCache`]
+    /// with the size set by [`Ext4Options::cache_entries`].
+    ///
+    /// [`TwoQueueBlockCache`]: crate::TwoQueueBlockCache
+    #[must_use]
+    pub fn block_cache(mut self, cache: SharedBlockCache) -> Self {
+        self.block_cache = Some(cache);
+        self
+    }
+}
+
+impl Default for Ext4Options {
+    fn default() -> Self {
+        Self {
+            journal_mode: JournalMode::default(),
+            cache_entries: None,
+            read_ahead_size: Self::DEFAULT_READ_AHEAD_SIZE,
+            verify_checksums: true,
+            max_symlinks: Self::DEFAULT_MAX_SYMLINKS,
+            block_cache: None,
+        }
+    }
+}
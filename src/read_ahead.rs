@@ -0,0 +1,158 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/// Hint about how a file will be accessed.
+///
+/// See [`File::advise`].
+///
+/// [`File::advise`]: crate::File::advise
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub enum Advice {
+    /// No particular access pattern. Read-ahead starts small, grows
+    /// while the file is read sequentially, and shrinks when the file
+    /// is accessed randomly.
+    #[default]
+    Normal,
+
+    /// The file will be read sequentially. Read-ahead always uses the
+    /// maximum size (see [`Ext4Options::read_ahead_size`]).
+    ///
+    /// [`Ext4Options::read_ahead_size`]: crate::Ext4Options::read_ahead_size
+    Sequential,
+
+    /// The file will be read in a random order. Read-ahead is disabled,
+    /// so only the requested block is read into the block cache.
+    Random,
+
+    /// The given range will be needed soon. It is read into the block
+    /// cache immediately. This does not change the access pattern set
+    /// by the other variants.
+    WillNeed,
+}
+
+/// Number of blocks read at once when a file with [`Advice::Normal`] is
+/// first accessed.
+const INITIAL_WINDOW: u32 = 4;
+
+/// Per-file read-ahead state.
+///
+/// This tracks how many blocks should be read into the block cache
+/// when a block of the file is not cached. The block cache further
+/// limits that to the read-ahead size of the filesystem, and callers
+/// limit it to the blocks that are physically contiguous within the
+/// file.
+pub(crate) struct ReadAhead {
+    /// Current access pattern. This is never `WillNeed`.
+    advice: Advice,
+
+    /// Number of blocks to read for `Advice::Normal`. This doubles on
+    /// each sequential access, and drops to one on a random access.
+    window: u32,
+
+    /// Index within the file of the most recently accessed block.
+    last_block: Option<u64>,
+}
+
+impl ReadAhead {
+    pub(crate) fn new() -> Self {
+        Self {
+            advice: Advice::Normal,
+            window: INITIAL_WINDOW,
+            last_block: None,
+        }
+    }
+
+    /// Set the access pattern. `Advice::WillNeed` is ignored.
+    pub(crate) fn set_advice(&mut self, advice: Advice) {
+        if advice != Advice::WillNeed {
+            self.advice = advice;
+            self.window = INITIAL_WINDOW;
+        }
+    }
+
+    /// Maximum number of blocks to read, based on previous accesses.
+    pub(crate) fn window(&self) -> u32 {
+        match self.advice {
+            Advice::Normal => self.window,
+            Advice::Sequential | Advice::WillNeed => u32::MAX,
+            Advice::Random => 1,
+        }
+    }
+
+    /// Record an access to `block_within_file`, and return the maximum
+    /// number of blocks to read, starting with that block, if the block
+    /// is not cached.
+    pub(crate) fn access(&mut self, block_within_file: u64) -> u32 {
+        match self.last_block {
+            // First access, or another access within the same block.
+            None => {}
+            Some(last) if last == block_within_file => {}
+            // Sequential access.
+            Some(last) if last.checked_add(1) == Some(block_within_file) => {
+                self.window = self.window.saturating_mul(2);
+            }
+            // Random access.
+            Some(_) => self.window = 1,
+        }
+        self.last_block = Some(block_within_file);
+        self.window()
+    }
+
+    /// Record an access to the blocks `first_block..=last_block` of the
+    /// file. This is the same as [`ReadAhead::access`] for
+    /// `first_block`, except that a later access to the block after
+    /// `last_block` is treated as sequential.
+    pub(crate) fn access_run(
+        &mut self,
+        first_block: u64,
+        last_block: u64,
+    ) -> u32 {
+        let window = self.access(first_block);
+        self.last_block = Some(last_block);
+        window
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_ahead() {
+        let mut ra = ReadAhead::new();
+        assert_eq!(ra.access(0), 4);
+        assert_eq!(ra.access(0), 4);
+        assert_eq!(ra.access(1), 8);
+        assert_eq!(ra.access(2), 16);
+
+        // Random access shrinks the window.
+        assert_eq!(ra.access(10), 1);
+        assert_eq!(ra.access(11), 2);
+        assert_eq!(ra.access(5), 1);
+        assert_eq!(ra.window(), 1);
+
+        ra.set_advice(Advice::Sequential);
+        assert_eq!(ra.access(100), u32::MAX);
+
+        ra.set_advice(Advice::Random);
+        assert_eq!(ra.access(101), 1);
+        assert_eq!(ra.access(102), 1);
+
+        // `WillNeed` doesn't change the access pattern.
+        ra.set_advice(Advice::WillNeed);
+        assert_eq!(ra.window(), 1);
+
+        ra.set_advice(Advice::Normal);
+        assert_eq!(ra.access(103), 8);
+
+        // A run of blocks counts as one access.
+        assert_eq!(ra.access_run(104, 110), 16);
+        assert_eq!(ra.access(111), 32);
+        assert_eq!(ra.access_run(0, 3), 1);
+    }
+}
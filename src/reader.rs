@@ -11,6 +11,7 @@ use alloc::boxed::Box;
 use alloc::vec::Vec;
 use core::error::Error;
 use core::fmt::{self, Display, Formatter};
+use core::future::Future;
 
 #[cfg(feature = "std")]
 use {
@@ -18,11 +19,62 @@ use {
     std::io::{Seek, SeekFrom},
 };
 
-/// Interface used by [`Ext4`] to read the filesystem data from a storage
-/// file or device.
+/// Define the `Ext4Read` trait. This is a macro so that `Send` can be
+/// added as a supertrait when the `sync` feature is enabled.
+macro_rules! define_ext4_read {
+    ($($supertrait:tt)*) => {
+        /// Interface used by [`Ext4`] to read the filesystem data from
+        /// a storage file or device.
+        ///
+        /// If the `sync` feature is enabled, implementations must also
+        /// be [`Send`], so that [`Ext4`] can be shared between threads.
+        ///
+        /// [`Ext4`]: crate::Ext4
+        pub trait Ext4Read $($supertrait)* {
+            /// Read bytes into `dst`, starting at `start_byte`.
+            ///
+            /// Exactly `dst.len()` bytes will be read; an error will be
+            /// returned if there is not enough data to fill `dst`, or if
+            /// the data cannot be read for any reason.
+            fn read(
+                &mut self,
+                start_byte: u64,
+                dst: &mut [u8],
+            ) -> Result<(), BoxedError>;
+
+            /// Perform multiple reads. Each element of `reads` is a
+            /// start byte and a buffer to read into, with the same
+            /// requirements as [`Ext4Read::read`].
+            ///
+            /// The reads may be performed in any order. If an error
+            /// occurs, the contents of all buffers are unspecified.
+            ///
+            /// The default implementation calls [`Ext4Read::read`] for
+            /// each element. Readers with high per-request latency can
+            /// override this to submit all of the reads at once.
+            fn read_many(
+                &mut self,
+                reads: &mut [(u64, &mut [u8])],
+            ) -> Result<(), BoxedError> {
+                for (start_byte, dst) in reads {
+                    self.read(*start_byte, dst)?;
+                }
+                Ok(())
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "sync"))]
+define_ext4_read!();
+#[cfg(feature = "sync")]
+define_ext4_read!(: Send);
+
+/// Asynchronous version of [`Ext4Read`], used by [`AsyncExt4`] to read
+/// the filesystem data from a storage file or device.
 ///
-/// [`Ext4`]: crate::Ext4
-pub trait Ext4Read {
+/// [`AsyncExt4`]: crate::AsyncExt4
+pub trait AsyncExt4Read {
     /// Read bytes into `dst`, starting at `start_byte`.
     ///
     /// Exactly `dst.len()` bytes will be read; an error will be
@@ -32,7 +84,7 @@ pub trait Ext4Read {
         &mut self,
         start_byte: u64,
         dst: &mut [u8],
-    ) -> Result<(), BoxedError>;
+    ) -> impl Future<Output = Result<(), BoxedError>>;
 }
 
 #[cfg(feature = "std")]
@@ -50,7 +102,8 @@ impl Ext4Read for File {
     }
 }
 
-/// Error type used by the [`Vec<u8>`] impl of [`Ext4Read`].
+/// Error type used by the [`Vec<u8>`] impls of [`Ext4Read`] and
+/// [`AsyncExt4Read`].
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub struct MemIoError {
     start: u64,
@@ -87,6 +140,16 @@ impl Ext4Read for Vec<u8> {
     }
 }
 
+impl AsyncExt4Read for Vec<u8> {
+    async fn read(
+        &mut self,
+        start_byte: u64,
+        dst: &mut [u8],
+    ) -> Result<(), BoxedError> {
+        Ext4Read::read(self, start_byte, dst)
+    }
+}
+
 fn read_from_bytes(src: &[u8], start_byte: u64, dst: &mut [u8]) -> Option<()> {
     let start = usize::try_from(start_byte).ok()?;
     let end = start.checked_add(dst.len())?;
@@ -105,14 +168,14 @@ mod tests {
         let mut src = vec![1, 2, 3];
 
         let mut dst = [0; 3];
-        src.read(0, &mut dst).unwrap();
+        Ext4Read::read(&mut src, 0, &mut dst).unwrap();
         assert_eq!(dst, [1, 2, 3]);
 
         let mut dst = [0; 2];
-        src.read(1, &mut dst).unwrap();
+        Ext4Read::read(&mut src, 1, &mut dst).unwrap();
         assert_eq!(dst, [2, 3]);
 
-        let err = src.read(4, &mut dst).unwrap_err();
+        let err = Ext4Read::read(&mut src, 4, &mut dst).unwrap_err();
         assert_eq!(
             format!("{err}"),
             format!(
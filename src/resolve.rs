@@ -50,22 +50,23 @@ pub(crate) enum FollowSymlinks {
 /// Non-exhaustive list of error conditions:
 /// * `path` is not absolute.
 /// * `path` does not exist.
-/// * Over 40 symlinks are encountered.
+/// * More symlinks are encountered than allowed by
+///   [`Ext4Options::max_symlinks`] (40 by default).
 /// * Path length ever exceeds 4096 bytes.
 ///
 /// # Panics
 ///
-/// This function panics if path resolution takes over 1000
-/// iterations. This should never occur in practice due to other
-/// restrictions, this is just a hedge against unforeseen bugs.
+/// This function panics if path resolution takes over 1000 iterations
+/// (plus one per allowed symlink). This should never occur in practice
+/// due to other restrictions, this is just a hedge against unforeseen
+/// bugs.
+///
+/// [`Ext4Options::max_symlinks`]: crate::Ext4Options::max_symlinks
 pub(crate) fn resolve_path(
     fs: &Ext4,
     path: Path<'_>,
     follow: FollowSymlinks,
 ) -> Result<(Inode, PathBuf), Ext4Error> {
-    // Maximum number of symlinks to resolve (for the whole path, not
-    // individual components).
-    const MAX_SYMLINKS: usize = 40;
     // Maximum path length in bytes. In general this library does not
     // enforce a path length limit, but during path resolution the
     // length can grow quite a bit due to symlinks.
@@ -75,6 +76,13 @@ pub(crate) fn resolve_path(
     // could cause an infinite loop.
     const MAX_ITERATIONS: usize = 1000;
 
+    // Maximum number of symlinks to resolve (for the whole path, not
+    // individual components).
+    let max_symlinks = fs.0.options.max_symlinks;
+    // Each symlink adds at least one iteration, so raise the iteration
+    // limit along with the symlink limit.
+    let max_iterations = MAX_ITERATIONS.saturating_add(max_symlinks);
+
     if !path.is_absolute() {
         return Err(Ext4Error::NotAbsolute);
     }
@@ -102,13 +110,13 @@ pub(crate) fn resolve_path(
     while index < path.len() {
         // Guard against infinite loops. Max iterations should never be
         // reachable in practice due to the other restrictions
-        // (MAX_SYMLINKS and MAX_PATH_LEN), so panic rather than
+        // (`max_symlinks` and `MAX_PATH_LEN`), so panic rather than
         // returning an error.
         //
-        // OK to unwrap: never exceeds `MAX_ITERATIONS`, which is much
-        // less than `usize::MAX`.
+        // OK to unwrap: never exceeds `max_iterations`, which is at
+        // most `usize::MAX`.
         num_iterations = num_iterations.checked_add(1).unwrap();
-        assert!(num_iterations <= MAX_ITERATIONS);
+        assert!(num_iterations <= max_iterations);
 
         // Find the end of the component. This is either the next '/',
         // or the end of the path.
@@ -163,10 +171,10 @@ pub(crate) fn resolve_path(
         {
             // Resolve symlink, unless this is the last component and `follow != All`.
 
-            // OK to unwrap: never exceeds `MAX_SYMLINKS`, which is much
-            // less than `usize::MAX`.
+            // OK to unwrap: never exceeds `max_iterations`, which is
+            // at most `usize::MAX`.
             num_symlinks = num_symlinks.checked_add(1).unwrap();
-            if num_symlinks > MAX_SYMLINKS {
+            if num_symlinks > max_symlinks {
                 return Err(Ext4Error::TooManySymlinks);
             }
 
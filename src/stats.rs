@@ -0,0 +1,145 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::sync::Lock;
+
+/// I/O and cache statistics for a filesystem.
+///
+/// See [`Ext4::stats`].
+///
+/// [`Ext4::stats`]: crate::Ext4::stats
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Ext4Stats {
+    reads: u64,
+    bytes_read: u64,
+    cache_hits: u64,
+    cache_misses: u64,
+    cache_evictions: u64,
+    journal_remaps: u64,
+    checksum_verifications: u64,
+}
+
+impl Ext4Stats {
+    /// Number of successful reads from the [`Ext4Read`] reader. Each
+    /// read passed to [`Ext4Read::read_many`] is counted separately.
+    ///
+    /// [`Ext4Read`]: crate::Ext4Read
+    /// [`Ext4Read::read_many`]: crate::Ext4Read::read_many
+    #[must_use]
+    pub fn reads(&self) -> u64 {
+        self.reads
+    }
+
+    /// Total number of bytes successfully read from the [`Ext4Read`]
+    /// reader.
+    ///
+    /// [`Ext4Read`]: crate::Ext4Read
+    #[must_use]
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+
+    /// Number of block reads served from the block cache.
+    #[must_use]
+    pub fn cache_hits(&self) -> u64 {
+        self.cache_hits
+    }
+
+    /// Number of block reads that were not in the block cache, and
+    /// required reading from the reader.
+    #[must_use]
+    pub fn cache_misses(&self) -> u64 {
+        self.cache_misses
+    }
+
+    /// Number of blocks evicted from the block cache to make room for
+    /// blocks of this filesystem. If the cache is shared, the evicted
+    /// blocks may belong to other filesystems.
+    #[must_use]
+    pub fn cache_evictions(&self) -> u64 {
+        self.cache_evictions
+    }
+
+    /// Number of block reads that were remapped to a block in the
+    /// journal. Each block is counted once per read, whether it is read
+    /// from the reader or from the block cache.
+    #[must_use]
+    pub fn journal_remaps(&self) -> u64 {
+        self.journal_remaps
+    }
+
+    /// Number of metadata checksums verified. This includes block group
+    /// descriptors, inodes, extent tree nodes, directory blocks, and
+    /// extended attribute blocks; checksums in the journal are not
+    /// counted.
+    #[must_use]
+    pub fn checksum_verifications(&self) -> u64 {
+        self.checksum_verifications
+    }
+}
+
+/// Block cache counters. These are stored in the block cache, which is
+/// already behind a lock, rather than in [`Stats`].
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct CacheCounters {
+    pub(crate) hits: u64,
+    pub(crate) misses: u64,
+    pub(crate) evictions: u64,
+}
+
+/// Counters shared by all clones of an `Ext4`. The block cache fields
+/// are unused; those counters are stored in the block cache.
+pub(crate) struct Stats(Lock<Ext4Stats>);
+
+impl Stats {
+    /// Create a new `Stats` with all counters set to zero.
+    pub(crate) fn new() -> Self {
+        Self(Lock::new(Ext4Stats::default()))
+    }
+
+    /// Record `num_reads` reads from the reader, totalling `num_bytes`.
+    pub(crate) fn record_reads(&self, num_reads: usize, num_bytes: usize) {
+        let mut stats = self.0.lock();
+        add(&mut stats.reads, num_reads);
+        add(&mut stats.bytes_read, num_bytes);
+    }
+
+    /// Record `num` blocks read from the journal instead of their
+    /// original location.
+    pub(crate) fn record_journal_remaps(&self, num: usize) {
+        add(&mut self.0.lock().journal_remaps, num);
+    }
+
+    /// Record `num` checksum verifications.
+    pub(crate) fn record_checksum_verifications(&self, num: usize) {
+        add(&mut self.0.lock().checksum_verifications, num);
+    }
+
+    /// Get a snapshot of the counters, combined with the block cache
+    /// counters.
+    pub(crate) fn snapshot(&self, cache: CacheCounters) -> Ext4Stats {
+        Ext4Stats {
+            cache_hits: cache.hits,
+            cache_misses: cache.misses,
+            cache_evictions: cache.evictions,
+            ..*self.0.lock()
+        }
+    }
+
+    /// Reset all counters to zero.
+    pub(crate) fn reset(&self) {
+        *self.0.lock() = Ext4Stats::default();
+    }
+}
+
+/// Add `num` to `counter`. The counter wraps on overflow.
+pub(crate) fn add(counter: &mut u64, num: usize) {
+    // A `usize` always fits in a `u64` on supported targets.
+    let num = u64::try_from(num).unwrap_or(u64::MAX);
+    *counter = counter.wrapping_add(num);
+}
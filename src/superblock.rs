@@ -12,7 +12,6 @@ use crate::error::{CorruptKind, Ext4Error, IncompatibleKind};
 use crate::features::{
     CompatibleFeatures, IncompatibleFeatures, ReadOnlyCompatibleFeatures,
 };
-use crate::inode::InodeIndex;
 use crate::util::{read_u16le, read_u32le, u64_from_hilo};
 use crate::{Label, Uuid};
 use core::num::NonZero;
@@ -22,15 +21,28 @@ use core::num::NonZero;
 pub(crate) struct Superblock {
     pub(crate) block_size: BlockSize,
     pub(crate) blocks_count: u64,
+    pub(crate) first_data_block: u32,
+    pub(crate) blocks_per_group: NonZero<u32>,
     pub(crate) inode_size: u16,
     pub(crate) inodes_per_block_group: NonZero<u32>,
     pub(crate) block_group_descriptor_size: u16,
     pub(crate) num_block_groups: u32,
+    /// First block of the descriptor table that uses the `meta_bg`
+    /// layout. Only used if `META_BLOCK_GROUPS` is enabled.
+    pub(crate) first_meta_bg: u32,
+    /// Block groups containing superblock backups, if the
+    /// `sparse_super2` feature is enabled. A value of zero means no
+    /// backup.
+    pub(crate) backup_block_groups: Option<[u32; 2]>,
     pub(crate) incompatible_features: IncompatibleFeatures,
     pub(crate) read_only_compatible_features: ReadOnlyCompatibleFeatures,
     pub(crate) checksum_seed: u32,
     pub(crate) htree_hash_seed: [u32; 4],
-    pub(crate) journal_inode: Option<InodeIndex>,
+    /// Raw journal inode number, if the filesystem has a journal. This
+    /// is only validated when the journal is loaded, so that an invalid
+    /// value doesn't prevent loading a filesystem whose journal is not
+    /// used.
+    pub(crate) journal_inode: Option<u32>,
     pub(crate) label: Label,
     pub(crate) uuid: Uuid,
 }
@@ -41,11 +53,17 @@ impl Superblock {
 
     /// Construct `Superblock` from bytes.
     ///
+    /// If `verify_checksum` is false, the superblock checksum is not
+    /// checked.
+    ///
     /// # Panics
     ///
     /// Panics if the length of `bytes` is less than
     /// [`Self::SIZE_IN_BYTES_ON_DISK`].
-    pub(crate) fn from_bytes(bytes: &[u8]) -> Result<Self, Ext4Error> {
+    pub(crate) fn from_bytes(
+        bytes: &[u8],
+        verify_checksum: bool,
+    ) -> Result<Self, Ext4Error> {
         assert!(bytes.len() >= Self::SIZE_IN_BYTES_ON_DISK);
 
         // OK to unwrap: already checked the length.
@@ -70,7 +88,9 @@ impl Superblock {
             read_u32le(bytes, S_HASH_SEED_OFFSET + 12),
         ];
         let s_desc_size = read_u16le(bytes, 0xfe);
+        let s_first_meta_bg = read_u32le(bytes, 0x104);
         let s_blocks_count_hi = read_u32le(bytes, 0x150);
+        let s_backup_bgs = [read_u32le(bytes, 0x24c), read_u32le(bytes, 0x250)];
         let s_checksum_seed = read_u32le(bytes, 0x270);
         const S_CHECKSUM_OFFSET: usize = 0x3fc;
         let s_checksum = read_u32le(bytes, S_CHECKSUM_OFFSET);
@@ -123,9 +143,12 @@ impl Superblock {
             return Err(CorruptKind::InodeSize.into());
         }
 
+        let backup_block_groups = compatible_features
+            .contains(CompatibleFeatures::SPARSE_SUPER2)
+            .then_some(s_backup_bgs);
+
         let journal_inode = if compatible_features
             .contains(CompatibleFeatures::HAS_JOURNAL)
-            && incompatible_features.contains(IncompatibleFeatures::RECOVERY)
         {
             // For now a separate journal device is not supported, so
             // assert that feature is not present. This assert cannot
@@ -136,17 +159,15 @@ impl Superblock {
                     .contains(IncompatibleFeatures::SEPARATE_JOURNAL_DEVICE)
             );
 
-            Some(
-                InodeIndex::new(s_journal_inum)
-                    .ok_or(CorruptKind::JournalInode)?,
-            )
+            Some(s_journal_inum)
         } else {
             None
         };
 
         // Validate the superblock checksum.
-        if read_only_compatible_features
-            .contains(ReadOnlyCompatibleFeatures::METADATA_CHECKSUMS)
+        if verify_checksum
+            && read_only_compatible_features
+                .contains(ReadOnlyCompatibleFeatures::METADATA_CHECKSUMS)
         {
             let mut checksum = Checksum::new();
             checksum.update(&bytes[..S_CHECKSUM_OFFSET]);
@@ -174,10 +195,14 @@ impl Superblock {
         Ok(Self {
             block_size,
             blocks_count,
+            first_data_block: s_first_data_block,
+            blocks_per_group,
             inode_size: s_inode_size,
             inodes_per_block_group,
             block_group_descriptor_size,
             num_block_groups,
+            first_meta_bg: s_first_meta_bg,
+            backup_block_groups,
             incompatible_features,
             read_only_compatible_features,
             checksum_seed,
@@ -206,12 +231,9 @@ fn check_incompat_features(
     let required_features = IncompatibleFeatures::FILE_TYPE_IN_DIR_ENTRY;
     let disallowed_features = IncompatibleFeatures::COMPRESSION
         | IncompatibleFeatures::SEPARATE_JOURNAL_DEVICE
-        | IncompatibleFeatures::META_BLOCK_GROUPS
         | IncompatibleFeatures::MULTIPLE_MOUNT_PROTECTION
-        | IncompatibleFeatures::LARGE_EXTENDED_ATTRIBUTES_IN_INODES
         | IncompatibleFeatures::DATA_IN_DIR_ENTRY
-        | IncompatibleFeatures::LARGE_DIRECTORIES
-        | IncompatibleFeatures::DATA_IN_INODE;
+        | IncompatibleFeatures::LARGE_DIRECTORIES;
 
     let present_required = actual & required_features;
     if present_required != required_features {
@@ -235,16 +257,20 @@ mod tests {
     #[test]
     fn test_superblock() {
         let data = include_bytes!("../test_data/raw_superblock.bin");
-        let sb = Superblock::from_bytes(data).unwrap();
+        let sb = Superblock::from_bytes(data, true).unwrap();
         assert_eq!(
             sb,
             Superblock {
                 block_size: BlockSize::from_superblock_value(0).unwrap(),
                 blocks_count: 128,
+                first_data_block: 1,
+                blocks_per_group: NonZero::new(8192).unwrap(),
                 inode_size: 256,
                 inodes_per_block_group: NonZero::new(16).unwrap(),
                 block_group_descriptor_size: 64,
                 num_block_groups: 1,
+                first_meta_bg: 0,
+                backup_block_groups: None,
                 incompatible_features:
                     IncompatibleFeatures::FILE_TYPE_IN_DIR_ENTRY
                         | IncompatibleFeatures::EXTENTS
@@ -308,7 +334,7 @@ mod tests {
         checksum.update(&data[..0x3fc]);
         data[0x3fc..].copy_from_slice(&checksum.finalize().to_le_bytes());
 
-        let sb = Superblock::from_bytes(&data).unwrap();
+        let sb = Superblock::from_bytes(&data, true).unwrap();
         // Check that the correct seed was calculated.
         assert_eq!(sb.checksum_seed, expected_seed);
     }
@@ -321,7 +347,7 @@ mod tests {
         // `num_block_groups` no longer fits in a `u32`.
         data[0x150..0x154].copy_from_slice(&[0xff; 4]);
         assert_eq!(
-            Superblock::from_bytes(&data).unwrap_err(),
+            Superblock::from_bytes(&data, true).unwrap_err(),
             CorruptKind::TooManyBlockGroups
         );
     }
@@ -332,7 +358,7 @@ mod tests {
             include_bytes!("../test_data/raw_superblock.bin").to_vec();
         data[0x58..0x5a].copy_from_slice(&1025u16.to_le_bytes());
         assert_eq!(
-            Superblock::from_bytes(&data).unwrap_err(),
+            Superblock::from_bytes(&data, true).unwrap_err(),
             CorruptKind::InodeSize
         );
     }
@@ -345,7 +371,7 @@ mod tests {
         // it is still part of the checksum.
         data[0x284] = 0xff;
         assert_eq!(
-            Superblock::from_bytes(&data).unwrap_err(),
+            Superblock::from_bytes(&data, true).unwrap_err(),
             CorruptKind::SuperblockChecksum
         );
     }
@@ -358,7 +384,7 @@ mod tests {
             include_bytes!("../test_data/raw_superblock.bin").to_vec();
         data[0x62] |= 0x02;
         assert_eq!(
-            Superblock::from_bytes(&data).unwrap_err(),
+            Superblock::from_bytes(&data, true).unwrap_err(),
             IncompatibleKind::UnsupportedFeatures(
                 IncompatibleFeatures::from_bits_retain(0x2_0000)
             )
@@ -377,7 +403,7 @@ mod tests {
         data[0x22] = 0;
         data[0x23] = 0;
         assert_eq!(
-            Superblock::from_bytes(&data).unwrap_err(),
+            Superblock::from_bytes(&data, true).unwrap_err(),
             CorruptKind::BlocksPerGroup
         );
     }
@@ -0,0 +1,75 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Types for shared ownership and interior mutability.
+//!
+//! By default these are the single-threaded `Rc` and `RefCell`. If the
+//! `sync` feature is enabled, `Arc` and `Mutex` are used instead, so
+//! that `Ext4` and the types that hold it are `Send` and `Sync`.
+
+#[cfg(not(feature = "sync"))]
+pub(crate) use alloc::rc::Rc as Shared;
+#[cfg(feature = "sync")]
+pub(crate) use alloc::sync::Arc as Shared;
+
+#[cfg(not(feature = "sync"))]
+use core::cell::RefCell as Inner;
+#[cfg(feature = "sync")]
+use std::sync::Mutex as Inner;
+#[cfg(feature = "sync")]
+use std::sync::PoisonError;
+
+/// Guard returned by [`Lock::lock`]. The lock is released when the
+/// guard is dropped.
+#[cfg(not(feature = "sync"))]
+pub(crate) type LockGuard<'a, T> = core::cell::RefMut<'a, T>;
+/// Guard returned by [`Lock::lock`]. The lock is released when the
+/// guard is dropped.
+#[cfg(feature = "sync")]
+pub(crate) type LockGuard<'a, T> = std::sync::MutexGuard<'a, T>;
+
+/// Provides mutable access to a value through a shared reference.
+///
+/// This is a `RefCell` by default, or a `Mutex` if the `sync` feature
+/// is enabled.
+pub(crate) struct Lock<T>(Inner<T>);
+
+impl<T> Lock<T> {
+    /// Create a new `Lock` holding `val`.
+    pub(crate) fn new(val: T) -> Self {
+        Self(Inner::new(val))
+    }
+
+    /// Get mutable access to the value.
+    ///
+    /// # Panics
+    ///
+    /// Without the `sync` feature, panics if the value is already
+    /// borrowed. With the `sync` feature, blocks until the value is
+    /// available.
+    #[track_caller]
+    pub(crate) fn lock(&self) -> LockGuard<'_, T> {
+        #[cfg(not(feature = "sync"))]
+        {
+            self.0.borrow_mut()
+        }
+
+        // The lock is poisoned if a thread panicked while holding it.
+        // That can happen due to a panic in user code, such as an
+        // `Ext4Read` or `BlockCacheBackend` implementation, so the
+        // poison is ignored rather than making every later operation
+        // panic. A panic in an `Ext4Read` implementation doesn't leave
+        // the block cache partially updated, because blocks are only
+        // inserted after the read has succeeded. A `BlockCacheBackend`
+        // is required by its docs to stay consistent if it panics.
+        #[cfg(feature = "sync")]
+        {
+            self.0.lock().unwrap_or_else(PoisonError::into_inner)
+        }
+    }
+}
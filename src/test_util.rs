@@ -11,8 +11,8 @@
 
 use super::Ext4;
 
-/// Decompress a file with zstd, then load it into an `Ext4`.
-pub(crate) fn load_compressed_filesystem(name: &str) -> Ext4 {
+/// Decompress a file with zstd and return the raw filesystem data.
+pub(crate) fn read_compressed_filesystem(name: &str) -> Vec<u8> {
     // This function executes quickly, so don't bother caching.
     let output = std::process::Command::new("zstd")
         .args([
@@ -24,7 +24,12 @@ pub(crate) fn load_compressed_filesystem(name: &str) -> Ext4 {
         .output()
         .unwrap();
     assert!(output.status.success());
-    Ext4::load(Box::new(output.stdout)).unwrap()
+    output.stdout
+}
+
+/// Decompress a file with zstd, then load it into an `Ext4`.
+pub(crate) fn load_compressed_filesystem(name: &str) -> Ext4 {
+    Ext4::load(Box::new(read_compressed_filesystem(name))).unwrap()
 }
 
 pub(crate) fn load_test_disk1() -> Ext4 {
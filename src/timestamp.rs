@@ -0,0 +1,147 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/// Point in time, relative to the Unix epoch.
+///
+/// Ext4 stores timestamps as a signed 32-bit number of seconds. Inodes
+/// larger than 128 bytes can also store an extra 32-bit field for each
+/// timestamp, containing two additional bits of seconds (extending the
+/// range to the year 2446) and the nanoseconds.
+///
+/// If the `std` feature is enabled, this type can be converted to
+/// [`std::time::SystemTime`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Timestamp {
+    /// Seconds since the Unix epoch. Negative values are before the
+    /// epoch.
+    seconds: i64,
+
+    /// Nanoseconds within the second.
+    nanoseconds: u32,
+}
+
+impl Timestamp {
+    /// Mask for the epoch bits in the extra field.
+    const EPOCH_MASK: u32 = 0b11;
+
+    /// Create a `Timestamp` from the raw on-disk fields.
+    ///
+    /// `seconds` is the base timestamp field (e.g. `i_mtime`). `extra`
+    /// is the corresponding extra field (e.g. `i_mtime_extra`), or
+    /// `None` if the inode is too small to contain it.
+    pub(crate) fn from_raw(seconds: u32, extra: Option<u32>) -> Self {
+        // The base field is interpreted as signed, which allows
+        // timestamps before 1970.
+        let mut ts = Self {
+            seconds: i64::from(i32::from_le_bytes(seconds.to_le_bytes())),
+            nanoseconds: 0,
+        };
+
+        if let Some(extra) = extra {
+            // The lower two bits of `extra` extend the seconds
+            // value. This is added to the signed base value, matching
+            // the kernel's `ext4_decode_extra_time`.
+            //
+            // OK to unwrap: the base value is at most `2^31-1` and the
+            // epoch adjustment is at most `3*2^32`, so the sum fits in
+            // an `i64`.
+            let epoch = i64::from(extra & Self::EPOCH_MASK) << 32;
+            ts.seconds = ts.seconds.checked_add(epoch).unwrap();
+
+            // The upper 30 bits of `extra` are nanoseconds.
+            ts.nanoseconds = extra >> 2;
+        }
+
+        ts
+    }
+
+    /// Seconds since the Unix epoch. Negative values are before the
+    /// epoch.
+    #[must_use]
+    pub const fn seconds(&self) -> i64 {
+        self.seconds
+    }
+
+    /// Nanoseconds within the second.
+    ///
+    /// This is always zero for timestamps read from 128-byte inodes.
+    #[must_use]
+    pub const fn nanoseconds(&self) -> u32 {
+        self.nanoseconds
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<Timestamp> for std::time::SystemTime {
+    fn from(ts: Timestamp) -> Self {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let seconds = Duration::from_secs(ts.seconds.unsigned_abs());
+        let nanoseconds = Duration::from_nanos(u64::from(ts.nanoseconds));
+
+        // OK to unwrap: ext4 timestamps are within the years 1901 to
+        // 2446, which `SystemTime` can represent on all supported
+        // platforms.
+        if ts.seconds >= 0 {
+            UNIX_EPOCH.checked_add(seconds).unwrap()
+        } else {
+            UNIX_EPOCH.checked_sub(seconds).unwrap()
+        }
+        .checked_add(nanoseconds)
+        .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timestamp_from_raw() {
+        // No extra field.
+        let ts = Timestamp::from_raw(1_700_000_000, None);
+        assert_eq!(ts.seconds(), 1_700_000_000);
+        assert_eq!(ts.nanoseconds(), 0);
+
+        // Nanoseconds in the extra field.
+        let ts = Timestamp::from_raw(1_700_000_000, Some(123_456_789 << 2));
+        assert_eq!(ts.seconds(), 1_700_000_000);
+        assert_eq!(ts.nanoseconds(), 123_456_789);
+
+        // One second before the epoch.
+        let ts = Timestamp::from_raw(0xffff_ffff, Some(0));
+        assert_eq!(ts.seconds(), -1);
+
+        // Earliest representable time (1901-12-13).
+        let ts = Timestamp::from_raw(0x8000_0000, Some(0));
+        assert_eq!(ts.seconds(), -(1 << 31));
+
+        // First second after the 32-bit signed overflow (2038-01-19).
+        let ts = Timestamp::from_raw(0x8000_0000, Some(1));
+        assert_eq!(ts.seconds(), 1 << 31);
+
+        // Latest representable time (2446-05-10).
+        let ts = Timestamp::from_raw(0x7fff_ffff, Some(3));
+        assert_eq!(ts.seconds(), (1 << 31) - 1 + (3 << 32));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_timestamp_to_system_time() {
+        use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+        let ts = Timestamp::from_raw(10, Some(5 << 2));
+        assert_eq!(SystemTime::from(ts), UNIX_EPOCH + Duration::new(10, 5));
+
+        let ts = Timestamp::from_raw(0xffff_ffff, Some(5 << 2));
+        assert_eq!(
+            SystemTime::from(ts),
+            UNIX_EPOCH - Duration::from_secs(1) + Duration::from_nanos(5)
+        );
+    }
+}
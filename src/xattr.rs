@@ -0,0 +1,637 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Extended attributes (xattrs) are stored in two places: in the inode
+// itself after `i_extra_isize`, and in a separate block pointed to by
+// `i_file_acl`. Both locations use the same entry format. With the
+// `ea_inode` feature, large values may be stored in a separate inode.
+//
+// https://www.kernel.org/doc/html/latest/filesystems/ext4/attributes.html
+
+use crate::Ext4;
+use crate::checksum::Checksum;
+use crate::error::{CorruptKind, Ext4Error};
+use crate::features::IncompatibleFeatures;
+use crate::file_attributes::FileAttributes;
+use crate::inode::{Inode, InodeIndex};
+use crate::util::{read_u16le, read_u32le, usize_from_u32};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Magic number at the start of the in-inode xattr area, and at the
+/// start of an xattr block.
+const XATTR_MAGIC: u32 = 0xea02_0000;
+
+/// Size of the xattr block header. Entries start immediately after.
+const BLOCK_HEADER_SIZE: usize = 32;
+
+/// Offset of the checksum field within the xattr block header.
+const BLOCK_CHECKSUM_OFFSET: usize = 0x10;
+
+/// Size of the fixed part of an xattr entry. The name follows
+/// immediately after.
+const ENTRY_HEADER_SIZE: usize = 16;
+
+/// Name index for `system.posix_acl_access`.
+const INDEX_POSIX_ACL_ACCESS: u8 = 2;
+
+/// Name index for `system.posix_acl_default`.
+const INDEX_POSIX_ACL_DEFAULT: u8 = 3;
+
+/// Get the name prefix for an entry's name index.
+///
+/// Returns `None` for indices that are not exposed as xattrs (for
+/// example, the encryption context).
+fn name_prefix(name_index: u8) -> Option<&'static [u8]> {
+    match name_index {
+        1 => Some(b"user."),
+        INDEX_POSIX_ACL_ACCESS => Some(b"system.posix_acl_access"),
+        INDEX_POSIX_ACL_DEFAULT => Some(b"system.posix_acl_default"),
+        4 => Some(b"trusted."),
+        6 => Some(b"security."),
+        7 => Some(b"system."),
+        8 => Some(b"system.richacl"),
+        _ => None,
+    }
+}
+
+/// Location of an xattr value.
+#[derive(Debug, Eq, PartialEq)]
+enum XattrValue<'a> {
+    /// Raw value, stored in the same area as the entry.
+    Inline(&'a [u8]),
+
+    /// Value stored as the file data of a separate EA inode. This
+    /// requires the `ea_inode` feature.
+    Inode {
+        /// Index of the EA inode.
+        inode: InodeIndex,
+
+        /// Size of the value in bytes.
+        size: u32,
+    },
+}
+
+/// Raw xattr entry.
+#[derive(Debug, Eq, PartialEq)]
+struct XattrEntry<'a> {
+    /// Index of the name prefix.
+    name_index: u8,
+
+    /// Name, without the prefix.
+    name: &'a [u8],
+
+    /// Location of the value.
+    value: XattrValue<'a>,
+
+    /// Hash of the entry's name and value.
+    hash: u32,
+}
+
+impl XattrEntry<'_> {
+    /// Get the full name of the entry, including its prefix. Returns
+    /// `None` if the entry is not exposed as an xattr.
+    fn full_name(&self) -> Option<Vec<u8>> {
+        let prefix = name_prefix(self.name_index)?;
+        let mut full_name = prefix.to_vec();
+        full_name.extend_from_slice(self.name);
+        Some(full_name)
+    }
+}
+
+/// Parse the xattr entries in `data`.
+///
+/// Entries start at `entries_start` and continue until a four-byte zero
+/// terminator. Value offsets are relative to `values_start`.
+fn parse_entries(
+    inode: InodeIndex,
+    data: &[u8],
+    entries_start: usize,
+    values_start: usize,
+) -> Result<Vec<XattrEntry<'_>>, Ext4Error> {
+    let mut entries = Vec::new();
+    let mut offset = entries_start;
+
+    loop {
+        // The list of entries is terminated by four zero bytes. The
+        // terminator must be present even if the entries fill the
+        // available space.
+        let end = offset
+            .checked_add(4)
+            .ok_or(CorruptKind::XattrEntry(inode))?;
+        if end > data.len() {
+            return Err(CorruptKind::XattrEntry(inode).into());
+        }
+        if read_u32le(data, offset) == 0 {
+            break;
+        }
+
+        let header = offset
+            .checked_add(ENTRY_HEADER_SIZE)
+            .and_then(|end| data.get(offset..end))
+            .ok_or(CorruptKind::XattrEntry(inode))?;
+        let e_name_len = header[0];
+        let e_name_index = header[1];
+        let e_value_offs = read_u16le(header, 0x2);
+        let e_value_inum = read_u32le(header, 0x4);
+        let e_value_size = read_u32le(header, 0x8);
+        let e_hash = read_u32le(header, 0xc);
+
+        // OK to unwrap: `offset` is less than `data.len()`, and the
+        // header size and name length are small.
+        let name_start = offset.checked_add(ENTRY_HEADER_SIZE).unwrap();
+        let name_end = name_start.checked_add(usize::from(e_name_len)).unwrap();
+        let name = data
+            .get(name_start..name_end)
+            .ok_or(CorruptKind::XattrEntry(inode))?;
+
+        let value = if let Some(value_inum) = InodeIndex::new(e_value_inum) {
+            XattrValue::Inode {
+                inode: value_inum,
+                size: e_value_size,
+            }
+        } else {
+            let value_start = values_start
+                .checked_add(usize::from(e_value_offs))
+                .ok_or(CorruptKind::XattrValue(inode))?;
+            let value_end = value_start
+                .checked_add(usize_from_u32(e_value_size))
+                .ok_or(CorruptKind::XattrValue(inode))?;
+            XattrValue::Inline(
+                data.get(value_start..value_end)
+                    .ok_or(CorruptKind::XattrValue(inode))?,
+            )
+        };
+
+        entries.push(XattrEntry {
+            name_index: e_name_index,
+            name,
+            value,
+            hash: e_hash,
+        });
+
+        // Entries are padded to a multiple of four bytes.
+        offset = name_end.next_multiple_of(4);
+    }
+
+    Ok(entries)
+}
+
+/// Read the raw xattr data stored in the inode, after `i_extra_isize`.
+///
+/// Returns the data and the offset of the first entry, or `None` if the
+/// inode does not contain any xattrs.
+fn read_in_inode_data(
+    ext4: &Ext4,
+    inode: &Inode,
+) -> Result<Option<(Vec<u8>, usize)>, Ext4Error> {
+    let data = Inode::read_bytes(ext4, inode.index)?;
+
+    let Some(header_offset) = Inode::in_inode_xattr_offset(&data) else {
+        return Ok(None);
+    };
+
+    if read_u32le(&data, header_offset) != XATTR_MAGIC {
+        return Ok(None);
+    }
+    // OK to unwrap: `in_inode_xattr_offset` checks that the magic
+    // number fits within the inode.
+    let entries_start = header_offset.checked_add(4).unwrap();
+
+    Ok(Some((data, entries_start)))
+}
+
+/// Read the xattr block pointed to by `i_file_acl`.
+///
+/// Returns `None` if the inode does not have an xattr block.
+fn read_block_data(
+    ext4: &Ext4,
+    inode: &Inode,
+) -> Result<Option<Vec<u8>>, Ext4Error> {
+    let block_index = inode.metadata.file_acl;
+    if block_index == 0 {
+        return Ok(None);
+    }
+
+    let mut block = vec![0; ext4.0.superblock.block_size.to_usize()];
+    ext4.read_from_block(block_index, 0, &mut block)?;
+
+    // Check the header. Only single-block xattr areas are valid.
+    let h_magic = read_u32le(&block, 0x0);
+    let h_blocks = read_u32le(&block, 0x8);
+    if h_magic != XATTR_MAGIC || h_blocks != 1 {
+        return Err(CorruptKind::XattrBlockHeader(inode.index).into());
+    }
+
+    if ext4.verify_checksums() {
+        ext4.record_checksum_verification();
+        let expected_checksum = read_u32le(&block, BLOCK_CHECKSUM_OFFSET);
+        let actual_checksum = block_checksum(
+            ext4.0.superblock.checksum_seed,
+            block_index,
+            &block,
+        );
+        if actual_checksum != expected_checksum {
+            return Err(CorruptKind::XattrBlockChecksum(inode.index).into());
+        }
+    }
+
+    Ok(Some(block))
+}
+
+/// Calculate the checksum of an xattr block.
+///
+/// The checksum covers the block's absolute index, followed by the
+/// block data with the checksum field treated as zero.
+fn block_checksum(seed: u32, block_index: u64, block: &[u8]) -> u32 {
+    let mut checksum = Checksum::with_seed(seed);
+    checksum.update(&block_index.to_le_bytes());
+    checksum.update(&block[..BLOCK_CHECKSUM_OFFSET]);
+    checksum.update_u32_le(0);
+    checksum.update(&block[BLOCK_CHECKSUM_OFFSET + 4..]);
+    checksum.finalize()
+}
+
+/// Call `f` for each xattr entry of `inode`, first for those in the
+/// inode, then for those in the xattr block.
+///
+/// Iteration stops early if `f` returns `Some`, and that value is
+/// returned.
+fn find_map_entries<T, F>(
+    ext4: &Ext4,
+    inode: &Inode,
+    mut f: F,
+) -> Result<Option<T>, Ext4Error>
+where
+    F: FnMut(&XattrEntry) -> Result<Option<T>, Ext4Error>,
+{
+    if let Some((data, entries_start)) = read_in_inode_data(ext4, inode)? {
+        // Values in the inode are relative to the first entry.
+        for entry in
+            parse_entries(inode.index, &data, entries_start, entries_start)?
+        {
+            if let Some(val) = f(&entry)? {
+                return Ok(Some(val));
+            }
+        }
+    }
+
+    if let Some(block) = read_block_data(ext4, inode)? {
+        // Values in the block are relative to the start of the block.
+        for entry in parse_entries(inode.index, &block, BLOCK_HEADER_SIZE, 0)? {
+            if let Some(val) = f(&entry)? {
+                return Ok(Some(val));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Get the full names of all xattrs of `inode`.
+pub(crate) fn list_xattrs(
+    ext4: &Ext4,
+    inode: &Inode,
+) -> Result<Vec<Vec<u8>>, Ext4Error> {
+    let mut names = Vec::new();
+    find_map_entries(ext4, inode, |entry| {
+        if let Some(name) = entry.full_name() {
+            names.push(name);
+        }
+        Ok(None::<()>)
+    })?;
+    Ok(names)
+}
+
+/// Get the value of the xattr of `inode` named `name`.
+///
+/// Returns `None` if the xattr does not exist.
+pub(crate) fn get_xattr(
+    ext4: &Ext4,
+    inode: &Inode,
+    name: &[u8],
+) -> Result<Option<Vec<u8>>, Ext4Error> {
+    find_map_entries(ext4, inode, |entry| {
+        if entry.full_name().as_deref() != Some(name) {
+            return Ok(None);
+        }
+
+        let value = match entry.value {
+            XattrValue::Inline(value) => value.to_vec(),
+            XattrValue::Inode {
+                inode: ea_inode,
+                size,
+            } => read_ea_inode_value(ext4, inode, entry, ea_inode, size)?,
+        };
+
+        if matches!(
+            entry.name_index,
+            INDEX_POSIX_ACL_ACCESS | INDEX_POSIX_ACL_DEFAULT
+        ) {
+            acl_from_disk(inode.index, &value).map(Some)
+        } else {
+            Ok(Some(value))
+        }
+    })
+}
+
+/// Read an xattr value stored in the EA inode `ea_inode`.
+///
+/// The value is the EA inode's file data. The EA inode stores a hash of
+/// the value in its `i_atime` field, and the entry's hash covers the
+/// entry name and the EA inode's hash. Both hashes are verified. See
+/// `ext4_xattr_inode_get` in the kernel.
+fn read_ea_inode_value(
+    ext4: &Ext4,
+    parent: &Inode,
+    entry: &XattrEntry,
+    ea_inode: InodeIndex,
+    size: u32,
+) -> Result<Vec<u8>, Ext4Error> {
+    let err = || CorruptKind::XattrInode {
+        inode: parent.index,
+        ea_inode,
+    };
+
+    // EA inodes require the `ea_inode` feature.
+    if !ext4
+        .0
+        .superblock
+        .incompatible_features
+        .contains(IncompatibleFeatures::LARGE_EXTENDED_ATTRIBUTES_IN_INODES)
+    {
+        return Err(CorruptKind::XattrEntry(parent.index).into());
+    }
+
+    let ea = Inode::read(ext4, ea_inode)?;
+    if !ea.metadata.flags.contains(FileAttributes::EA_INODE)
+        || ea.metadata.size_in_bytes != u64::from(size)
+    {
+        return Err(err().into());
+    }
+
+    let value = ext4.read_inode_file(&ea)?;
+
+    // EA inodes created by Lustre store a reference to the parent inode
+    // instead of a hash. See `ext4_xattr_inode_iget` in the kernel.
+    let is_lustre = ea.metadata.mtime.seconds()
+        == i64::from(parent.index.get())
+        && ea.metadata.generation == parent.metadata.generation;
+    if is_lustre {
+        return Ok(value);
+    }
+
+    // The hash is the lower 32 bits of the `i_atime` seconds.
+    let ea_hash = read_u32le(&ea.metadata.atime.seconds().to_le_bytes(), 0);
+
+    let mut checksum = Checksum::with_seed(ext4.0.superblock.checksum_seed);
+    checksum.update(&value);
+    if checksum.finalize() != ea_hash {
+        return Err(CorruptKind::XattrInodeHash {
+            inode: parent.index,
+            ea_inode,
+        }
+        .into());
+    }
+
+    // Older kernels calculated the entry hash with signed chars, so
+    // accept either variant. See `ext4_xattr_inode_verify_hashes` in
+    // the kernel.
+    if entry_hash(entry.name, &[ea_hash], false) != entry.hash
+        && entry_hash(entry.name, &[ea_hash], true) != entry.hash
+    {
+        return Err(CorruptKind::XattrInodeHash {
+            inode: parent.index,
+            ea_inode,
+        }
+        .into());
+    }
+
+    Ok(value)
+}
+
+/// Calculate the hash of an xattr entry from its name (without prefix)
+/// and value. The value is hashed in 32-bit words.
+///
+/// If `signed` is true, name bytes are treated as signed chars. This
+/// matches a bug in older kernels on platforms where `char` is signed.
+///
+/// See `ext4_xattr_hash_entry` in the kernel.
+fn entry_hash(name: &[u8], value: &[u32], signed: bool) -> u32 {
+    const NAME_HASH_SHIFT: u32 = 5;
+    const VALUE_HASH_SHIFT: u32 = 16;
+
+    let mut hash: u32 = 0;
+    for byte in name {
+        let byte = if signed {
+            // Sign extend to 32 bits.
+            u32::from_le_bytes(
+                i32::from(i8::from_le_bytes([*byte])).to_le_bytes(),
+            )
+        } else {
+            u32::from(*byte)
+        };
+        hash = hash.rotate_left(NAME_HASH_SHIFT) ^ byte;
+    }
+    for word in value {
+        hash = hash.rotate_left(VALUE_HASH_SHIFT) ^ word;
+    }
+    hash
+}
+
+/// Convert a POSIX ACL from the ext4 on-disk format to the format used
+/// by the xattr API (e.g. `getxattr(2)`).
+///
+/// On disk, the ACL starts with a version (1). Entries for a specific
+/// user or group contain a tag, permissions, and an ID. Other entries
+/// omit the ID.
+///
+/// The xattr format starts with a different version (2), and every
+/// entry contains an ID. The ID is set to `u32::MAX` for entries that
+/// don't have one.
+///
+/// See `ext4_acl_from_disk` and `posix_acl_to_xattr` in the kernel.
+fn acl_from_disk(inode: InodeIndex, data: &[u8]) -> Result<Vec<u8>, Ext4Error> {
+    const DISK_VERSION: u32 = 1;
+    const XATTR_VERSION: u32 = 2;
+    const ACL_USER: u16 = 0x02;
+    const ACL_GROUP: u16 = 0x08;
+    const ACL_UNDEFINED_ID: u32 = u32::MAX;
+
+    if data.len() < 4 || read_u32le(data, 0) != DISK_VERSION {
+        return Err(CorruptKind::XattrAcl(inode).into());
+    }
+
+    let mut out = XATTR_VERSION.to_le_bytes().to_vec();
+    let mut offset = 4;
+    while offset < data.len() {
+        let short_end =
+            offset.checked_add(4).ok_or(CorruptKind::XattrAcl(inode))?;
+        let short_entry = data
+            .get(offset..short_end)
+            .ok_or(CorruptKind::XattrAcl(inode))?;
+        let tag = read_u16le(short_entry, 0);
+        let perm = read_u16le(short_entry, 2);
+
+        let id = if matches!(tag, ACL_USER | ACL_GROUP) {
+            let long_end = short_end
+                .checked_add(4)
+                .ok_or(CorruptKind::XattrAcl(inode))?;
+            let id = data
+                .get(short_end..long_end)
+                .ok_or(CorruptKind::XattrAcl(inode))?;
+            offset = long_end;
+            read_u32le(id, 0)
+        } else {
+            offset = short_end;
+            ACL_UNDEFINED_ID
+        };
+
+        out.extend(tag.to_le_bytes());
+        out.extend(perm.to_le_bytes());
+        out.extend(id.to_le_bytes());
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::num::NonZero;
+
+    fn inode() -> InodeIndex {
+        NonZero::new(12).unwrap()
+    }
+
+    /// Create raw entry data with an empty header, entries in the
+    /// first part, and values in the second part.
+    fn make_entry(
+        name_index: u8,
+        name: &[u8],
+        value_offs: u16,
+        value_size: u32,
+    ) -> Vec<u8> {
+        let mut entry = Vec::new();
+        entry.push(u8::try_from(name.len()).unwrap());
+        entry.push(name_index);
+        entry.extend(value_offs.to_le_bytes());
+        entry.extend(0u32.to_le_bytes());
+        entry.extend(value_size.to_le_bytes());
+        entry.extend(0u32.to_le_bytes());
+        entry.extend(name);
+        entry.resize(entry.len().next_multiple_of(4), 0);
+        entry
+    }
+
+    #[test]
+    fn test_parse_entries() {
+        let mut data = Vec::new();
+        data.extend(make_entry(1, b"abc", 64, 3));
+        data.extend(make_entry(9, b"c", 68, 1));
+        data.extend([0; 4]);
+        data.resize(64, 0);
+        data.extend(b"xyz\0");
+        data.extend(b"q");
+
+        let entries = parse_entries(inode(), &data, 0, 0).unwrap();
+        assert_eq!(
+            entries,
+            [
+                XattrEntry {
+                    name_index: 1,
+                    name: b"abc",
+                    value: XattrValue::Inline(b"xyz"),
+                    hash: 0,
+                },
+                XattrEntry {
+                    name_index: 9,
+                    name: b"c",
+                    value: XattrValue::Inline(b"q"),
+                    hash: 0,
+                }
+            ]
+        );
+        assert_eq!(entries[0].full_name().unwrap(), b"user.abc");
+        // Index 9 (encryption context) is not exposed.
+        assert_eq!(entries[1].full_name(), None);
+
+        // Empty list.
+        assert!(parse_entries(inode(), &[0; 4], 0, 0).unwrap().is_empty());
+
+        // Missing terminator.
+        assert_eq!(
+            parse_entries(inode(), &[], 0, 0).unwrap_err(),
+            CorruptKind::XattrEntry(inode())
+        );
+
+        // Truncated name.
+        let mut data = make_entry(1, b"abc", 0, 0);
+        data.truncate(ENTRY_HEADER_SIZE + 1);
+        assert_eq!(
+            parse_entries(inode(), &data, 0, 0).unwrap_err(),
+            CorruptKind::XattrEntry(inode())
+        );
+
+        // Value out of bounds.
+        let mut data = make_entry(1, b"abc", 20, 100);
+        data.extend([0; 4]);
+        assert_eq!(
+            parse_entries(inode(), &data, 0, 0).unwrap_err(),
+            CorruptKind::XattrValue(inode())
+        );
+    }
+
+    #[test]
+    fn test_entry_hash() {
+        assert_eq!(entry_hash(b"", &[], false), 0);
+        assert_eq!(entry_hash(b"a", &[], false), 0x61);
+        assert_eq!(entry_hash(b"ab", &[], false), (0x61 << 5) ^ 0x62);
+        assert_eq!(entry_hash(b"a", &[1], false), (0x61 << 16) ^ 1);
+
+        // ASCII names hash the same whether signed or not.
+        assert_eq!(
+            entry_hash(b"large", &[123], false),
+            entry_hash(b"large", &[123], true)
+        );
+
+        // Non-ASCII bytes are sign extended.
+        assert_eq!(entry_hash(&[0x80], &[], false), 0x80);
+        assert_eq!(entry_hash(&[0x80], &[], true), 0xffff_ff80);
+    }
+
+    #[test]
+    fn test_acl_from_disk() {
+        let mut disk = 1u32.to_le_bytes().to_vec();
+        // User owner.
+        disk.extend([0x01, 0x00, 0x06, 0x00]);
+        // User 1000.
+        disk.extend([0x02, 0x00, 0x04, 0x00, 0xe8, 0x03, 0x00, 0x00]);
+        // Other.
+        disk.extend([0x20, 0x00, 0x00, 0x00]);
+
+        let mut expected = 2u32.to_le_bytes().to_vec();
+        expected.extend([0x01, 0x00, 0x06, 0x00, 0xff, 0xff, 0xff, 0xff]);
+        expected.extend([0x02, 0x00, 0x04, 0x00, 0xe8, 0x03, 0x00, 0x00]);
+        expected.extend([0x20, 0x00, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff]);
+
+        assert_eq!(acl_from_disk(inode(), &disk).unwrap(), expected);
+
+        // Invalid version.
+        assert_eq!(
+            acl_from_disk(inode(), &[2, 0, 0, 0]).unwrap_err(),
+            CorruptKind::XattrAcl(inode())
+        );
+
+        // Truncated entry.
+        assert_eq!(
+            acl_from_disk(inode(), &disk[..disk.len() - 1]).unwrap_err(),
+            CorruptKind::XattrAcl(inode())
+        );
+    }
+}
@@ -0,0 +1,260 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::expected_holes_data;
+use crate::test_util::{load_test_disk1, read_compressed_filesystem};
+use ext4_view::{AsyncExt4, AsyncExt4Read, Ext4Error, Ext4Read};
+use std::cell::Cell;
+use std::error::Error;
+use std::future::{Future, poll_fn};
+use std::pin::pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+
+/// Run a future to completion by polling it in a loop.
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+/// Async reader that returns `Pending` once before each read
+/// completes.
+struct YieldingReader {
+    data: Vec<u8>,
+}
+
+impl AsyncExt4Read for YieldingReader {
+    async fn read(
+        &mut self,
+        start_byte: u64,
+        dst: &mut [u8],
+    ) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
+        let mut yielded = false;
+        poll_fn(|cx| {
+            if yielded {
+                Poll::Ready(())
+            } else {
+                yielded = true;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        })
+        .await;
+
+        Ext4Read::read(&mut self.data, start_byte, dst)
+    }
+}
+
+fn load_async_test_disk1() -> AsyncExt4<YieldingReader> {
+    let reader = YieldingReader {
+        data: read_compressed_filesystem("test_disk1.bin.zst"),
+    };
+    block_on(AsyncExt4::load(reader)).unwrap()
+}
+
+#[test]
+fn test_async_read() {
+    let mut fs = load_async_test_disk1();
+
+    assert_eq!(block_on(fs.read("/small_file")).unwrap(), b"hello, world!");
+    assert_eq!(block_on(fs.read("/holes")).unwrap(), expected_holes_data());
+    assert_eq!(block_on(fs.read("/empty_file")).unwrap(), []);
+    assert_eq!(
+        block_on(fs.read("/dir1/dir2/sym_rel")).unwrap(),
+        b"hello, world!"
+    );
+
+    assert!(matches!(
+        block_on(fs.read("/empty_dir")).unwrap_err(),
+        Ext4Error::IsADirectory
+    ));
+    assert!(matches!(
+        block_on(fs.read("/does_not_exist")).unwrap_err(),
+        Ext4Error::NotFound
+    ));
+}
+
+/// Test reading a file in small pieces, and seeking.
+#[test]
+fn test_async_file() {
+    let mut fs = load_async_test_disk1();
+    let expected = expected_holes_data();
+
+    let mut file = block_on(fs.open("/holes")).unwrap();
+    assert_eq!(file.metadata().len(), expected.len() as u64);
+
+    let mut data: Vec<u8> = Vec::new();
+    let mut buf = vec![0; 300];
+    loop {
+        let n = block_on(file.read_bytes(&mut fs, &mut buf)).unwrap();
+        if n == 0 {
+            break;
+        }
+        data.extend(&buf[..n]);
+    }
+    assert_eq!(data, expected);
+    assert_eq!(file.position(), expected.len() as u64);
+
+    // Seek backwards and read across a block boundary.
+    file.seek_to(2000);
+    let mut buf = vec![0; 100];
+    let n = block_on(file.read_bytes(&mut fs, &mut buf)).unwrap();
+    assert_eq!(buf[..n], expected[2000..2000 + n]);
+}
+
+#[test]
+fn test_async_read_dir() {
+    let mut fs = load_async_test_disk1();
+    let sync_fs = load_test_disk1();
+
+    for dir in ["/", "/dir1", "/medium_dir", "/big_dir"] {
+        let mut names: Vec<_> = block_on(fs.read_dir(dir))
+            .unwrap()
+            .iter()
+            .map(|e| e.file_name().as_str().unwrap().to_owned())
+            .collect();
+        let mut expected: Vec<_> = sync_fs
+            .read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().as_str().unwrap().to_owned())
+            .collect();
+        names.sort();
+        expected.sort();
+        assert_eq!(names, expected);
+    }
+}
+
+/// Test that entries returned by `read_dir` can read from the
+/// filesystem.
+#[test]
+fn test_async_dir_entry() {
+    let mut fs = load_async_test_disk1();
+    let sync_fs = load_test_disk1();
+
+    let mut entries = block_on(fs.read_dir("/dir1")).unwrap();
+    let mut expected: Vec<_> = sync_fs
+        .read_dir("/dir1")
+        .unwrap()
+        .map(|e| e.unwrap())
+        .collect();
+    assert!(!entries.is_empty());
+    assert_eq!(entries.len(), expected.len());
+    entries.sort_by_key(|e| e.path());
+    expected.sort_by_key(|e| e.path());
+    for (entry, expected) in entries.iter().zip(expected) {
+        assert_eq!(entry.path(), expected.path());
+        assert_eq!(entry.ino(), expected.ino());
+        assert_eq!(
+            block_on(entry.metadata(&mut fs)).unwrap(),
+            expected.metadata().unwrap()
+        );
+        assert_eq!(
+            block_on(entry.list_xattrs(&mut fs)).unwrap(),
+            expected.list_xattrs().unwrap()
+        );
+    }
+}
+
+#[test]
+fn test_async_metadata() {
+    let mut fs = load_async_test_disk1();
+    let sync_fs = load_test_disk1();
+
+    for path in ["/", "/small_file", "/dir1/dir2", "/big_dir/123"] {
+        assert_eq!(
+            block_on(fs.metadata(path)).unwrap(),
+            sync_fs.metadata(path).unwrap()
+        );
+    }
+    assert!(block_on(fs.exists("/dir1/dir2/sym_abs_dir")).unwrap());
+    assert!(!block_on(fs.exists("/does_not_exist")).unwrap());
+}
+
+/// Test that data read through the journal and inline data work.
+#[test]
+fn test_async_special_cases() {
+    let data = read_compressed_filesystem("test_disk_journal_classic.bin.zst");
+    let mut fs = block_on(AsyncExt4::load(data)).unwrap();
+    assert_eq!(block_on(fs.read("/a")).unwrap(), [b'n'; 1024]);
+    assert_eq!(block_on(fs.read("/b")).unwrap(), [b'o'; 1024]);
+
+    let data = read_compressed_filesystem("test_disk_inline_data.bin.zst");
+    let mut fs = block_on(AsyncExt4::load(data)).unwrap();
+    let sync_fs = ext4_view::Ext4::load(Box::new(read_compressed_filesystem(
+        "test_disk_inline_data.bin.zst",
+    )))
+    .unwrap();
+    for entry in block_on(fs.read_dir("/")).unwrap() {
+        if entry.file_type().unwrap().is_regular_file() {
+            let path = entry.path();
+            assert_eq!(
+                block_on(fs.read(&path)).unwrap(),
+                sync_fs.read(&path).unwrap()
+            );
+        }
+    }
+}
+
+/// Test seeking within a file that has multiple levels of block map,
+/// then reading. Mapping a block after a seek may need more than one
+/// retry.
+#[test]
+fn test_async_seek_big_file() {
+    let reader = YieldingReader {
+        data: read_compressed_filesystem("test_disk_ext2.bin.zst"),
+    };
+    let mut fs = block_on(AsyncExt4::load(reader)).unwrap();
+    let mut file = block_on(fs.open("/big_file")).unwrap();
+
+    // Each block of the file starts with its index within the file.
+    for block in [65814u32, 40000, 68804, 301, 1001, 0, 12, 268] {
+        file.seek_to(u64::from(block) * 1024);
+        let mut buf = [0; 4];
+        let n = block_on(file.read_bytes(&mut fs, &mut buf)).unwrap();
+        assert_eq!(n, 4);
+        assert_eq!(u32::from_le_bytes(buf), block);
+    }
+}
+
+/// Async reader that counts the number of reads.
+struct CountingReader {
+    data: Vec<u8>,
+    num_reads: Rc<Cell<usize>>,
+}
+
+impl AsyncExt4Read for CountingReader {
+    async fn read(
+        &mut self,
+        start_byte: u64,
+        dst: &mut [u8],
+    ) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
+        self.num_reads.set(self.num_reads.get() + 1);
+        Ext4Read::read(&mut self.data, start_byte, dst)
+    }
+}
+
+/// Test that the missing data for a large directory is read in
+/// batches rather than one block per retry.
+#[test]
+fn test_async_read_dir_batched() {
+    let num_reads = Rc::new(Cell::new(0));
+    let reader = CountingReader {
+        data: read_compressed_filesystem("test_disk1.bin.zst"),
+        num_reads: num_reads.clone(),
+    };
+    let mut fs = block_on(AsyncExt4::load(reader)).unwrap();
+    num_reads.set(0);
+    let entries = block_on(fs.read_dir("/big_dir")).unwrap();
+    assert_eq!(entries.len(), 10_002);
+    assert!(num_reads.get() <= 7);
+}
@@ -0,0 +1,103 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::expected_holes_data;
+use crate::test_util::read_compressed_filesystem;
+use ext4_view::{
+    BlockCacheBackend, BlockCacheKey, BlockKind, Ext4, Ext4Options,
+    LruBlockCache, SharedBlockCache,
+};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+/// Unbounded cache backend that records which filesystems have been
+/// removed.
+#[derive(Default)]
+struct MapCache {
+    blocks: HashMap<BlockCacheKey, Vec<u8>>,
+    removed: Arc<Mutex<Vec<u64>>>,
+}
+
+impl BlockCacheBackend for MapCache {
+    fn get(&mut self, key: BlockCacheKey) -> Option<&[u8]> {
+        self.blocks.get(&key).map(|data| data.as_slice())
+    }
+
+    fn insert(
+        &mut self,
+        key: BlockCacheKey,
+        data: &[u8],
+        _kind: BlockKind,
+    ) -> usize {
+        self.blocks.insert(key, data.to_vec());
+        0
+    }
+
+    fn remove_filesystem(&mut self, fs_id: u64) {
+        self.blocks.retain(|key, _| key.fs_id() != fs_id);
+        self.removed.lock().unwrap().push(fs_id);
+    }
+}
+
+fn load(name: &str, cache: &SharedBlockCache) -> Ext4 {
+    let options = Ext4Options::new().block_cache(cache.clone());
+    Ext4::load_with_options(Box::new(read_compressed_filesystem(name)), options)
+        .unwrap()
+}
+
+/// Test that multiple filesystems can share a custom cache backend.
+#[test]
+fn test_shared_custom_block_cache() {
+    let removed = Arc::new(Mutex::new(Vec::new()));
+    let cache = SharedBlockCache::new(MapCache {
+        blocks: HashMap::new(),
+        removed: removed.clone(),
+    });
+
+    let fs1 = load("test_disk1.bin.zst", &cache);
+    let fs2 = load("test_disk_ext2.bin.zst", &cache);
+    let fs3 = load("test_disk1.bin.zst", &cache);
+
+    for fs in [&fs1, &fs2, &fs3] {
+        assert_eq!(fs.read("/holes").unwrap(), expected_holes_data());
+        assert!(fs.exists("/small_file").unwrap());
+    }
+
+    // Dropping a filesystem removes its blocks from the cache. Each
+    // filesystem has a different ID, even if loaded from the same
+    // data.
+    drop(fs1);
+    drop(fs2);
+    drop(fs3);
+    let removed = removed.lock().unwrap();
+    assert_eq!(removed.len(), 3);
+    assert_eq!(removed.iter().collect::<HashSet<_>>().len(), 3);
+}
+
+/// Test sharing a small `LruBlockCache`, so that filesystems evict each
+/// other's blocks.
+#[test]
+fn test_shared_lru_block_cache() {
+    let cache = SharedBlockCache::new(LruBlockCache::new(4));
+    let options = Ext4Options::new().block_cache(cache.clone());
+    assert_eq!(options, Ext4Options::new().block_cache(cache.clone()));
+    assert_ne!(
+        options,
+        Ext4Options::new()
+            .block_cache(SharedBlockCache::new(LruBlockCache::new(4)))
+    );
+
+    let fs1 = load("test_disk1.bin.zst", &cache);
+    let fs2 = load("test_disk_ext2.bin.zst", &cache);
+    for _ in 0..2 {
+        for fs in [&fs1, &fs2] {
+            assert_eq!(fs.read("/small_file").unwrap(), b"hello, world!");
+            assert_eq!(fs.read("/holes").unwrap(), expected_holes_data());
+        }
+    }
+}
@@ -23,6 +23,17 @@ fn test_read_small_inode() {
     assert_eq!(entry.file_name(), ".");
 }
 
+/// Test that timestamps can be read from a 128-byte inode, which does
+/// not have space for the creation time or nanoseconds.
+#[test]
+fn test_small_inode_timestamps() {
+    let fs = load_ext3();
+    let metadata = fs.metadata("/medium_dir/1").unwrap();
+    assert!(metadata.created().is_none());
+    assert!(metadata.modified().seconds() > 1_704_067_200);
+    assert_eq!(metadata.modified().nanoseconds(), 0);
+}
+
 /// Test reading files from an htree directory that uses TEA hashes.
 #[test]
 fn test_tea_htree() {
@@ -8,7 +8,7 @@
 
 use crate::expected_holes_data;
 use crate::test_util::load_test_disk1;
-use ext4_view::{Ext4Error, Path, PathBuf};
+use ext4_view::{Ext4Error, FileAttributes, Path, PathBuf};
 
 #[cfg(feature = "std")]
 use ext4_view::Ext4;
@@ -299,6 +299,70 @@ fn test_metadata_uid_gid() {
     assert_eq!(metadata.gid(), 456);
 }
 
+#[test]
+fn test_metadata_inode_info() {
+    let fs = load_test_disk1();
+
+    // Root directory is always inode 2.
+    assert_eq!(fs.metadata("/").unwrap().ino(), 2);
+
+    // File with holes: ten blocks in size, but only four 1KiB blocks
+    // allocated, so eight 512-byte units.
+    let metadata = fs.metadata("/holes").unwrap();
+    assert_eq!(metadata.nlink(), 1);
+    assert_eq!(metadata.blocks(), 8);
+    assert_eq!(metadata.file_acl(), 0);
+
+    // An empty directory has two links: its entry in the parent, and
+    // its own "." entry. A directory with one subdirectory gets an
+    // additional link from the child's ".." entry.
+    assert_eq!(fs.metadata("/empty_dir").unwrap().nlink(), 2);
+    assert_eq!(fs.metadata("/dir1").unwrap().nlink(), 3);
+
+    // A symlink has its own inode, separate from the target.
+    assert_ne!(
+        fs.symlink_metadata("/sym_simple").unwrap().ino(),
+        fs.metadata("/sym_simple").unwrap().ino()
+    );
+}
+
+#[test]
+fn test_metadata_attributes() {
+    let fs = load_test_disk1();
+
+    assert_eq!(
+        fs.metadata("/small_file").unwrap().attributes(),
+        FileAttributes::EXTENTS
+    );
+    assert_eq!(
+        fs.metadata("/big_dir").unwrap().attributes(),
+        FileAttributes::EXTENTS | FileAttributes::DIRECTORY_HTREE
+    );
+    assert!(
+        fs.metadata("/encrypted_dir")
+            .unwrap()
+            .attributes()
+            .contains(FileAttributes::ENCRYPTED)
+    );
+}
+
+#[test]
+fn test_metadata_timestamps() {
+    let fs = load_test_disk1();
+
+    // Don't check specific values, since they will change if the test
+    // data is regenerated.
+    let metadata = fs.metadata("/small_file").unwrap();
+    let created = metadata.created().unwrap();
+    // The test data was created after 2024-01-01.
+    assert!(created.seconds() > 1_704_067_200);
+    // The file was written once and never modified or read afterwards.
+    assert_eq!(metadata.modified(), created);
+    assert_eq!(metadata.changed(), created);
+    assert_eq!(metadata.accessed(), created);
+    assert!(created.nanoseconds() < 1_000_000_000);
+}
+
 #[test]
 fn test_direntry_debug() {
     let fs = load_test_disk1();
@@ -333,6 +397,7 @@ fn test_direntry_metadata() {
         metadata.len(),
         u64::try_from("hello, world!".len()).unwrap()
     );
+    assert_eq!(entry.ino(), metadata.ino());
 }
 
 #[test]
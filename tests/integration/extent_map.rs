@@ -0,0 +1,176 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::test_util::{
+    load_compressed_filesystem, load_test_disk1, read_compressed_filesystem,
+};
+use ext4_view::{Ext4, Ext4Options, FileExtent, FileExtentFlags, JournalMode};
+
+/// Check that the extents are sorted, contiguous within the file, and
+/// that only the final extent has the `LAST` flag.
+fn check_extent_map(map: &[FileExtent], file_size: u64) {
+    let mut offset = 0;
+    for (i, extent) in map.iter().enumerate() {
+        assert_eq!(extent.logical_offset(), offset);
+        assert_eq!(
+            extent.flags().contains(FileExtentFlags::LAST),
+            i == map.len() - 1
+        );
+        if extent.flags().contains(FileExtentFlags::HOLE) {
+            assert_eq!(extent.physical_offset(), 0);
+        }
+        offset += extent.len();
+    }
+    assert_eq!(offset, file_size.next_multiple_of(1024));
+}
+
+/// Check that the extent map of `path` matches the raw disk data.
+fn check_extent_map_data(fs: &Ext4, disk: &[u8], path: &str) {
+    let file = fs.open(path).unwrap();
+    let map = file.extent_map().unwrap();
+    check_extent_map(&map, file.metadata().len());
+
+    let data = fs.read(path).unwrap();
+    for extent in map {
+        let start = usize::try_from(extent.logical_offset()).unwrap();
+        let end =
+            (start + usize::try_from(extent.len()).unwrap()).min(data.len());
+        let expected = &data[start..end];
+
+        // Holes and unwritten extents read as zeros. Unwritten extents
+        // may contain stale data on disk, so don't compare those.
+        if extent
+            .flags()
+            .intersects(FileExtentFlags::HOLE | FileExtentFlags::UNWRITTEN)
+        {
+            assert!(expected.iter().all(|b| *b == 0));
+        } else {
+            let physical = usize::try_from(extent.physical_offset()).unwrap();
+            assert_eq!(&disk[physical..physical + expected.len()], expected);
+        }
+    }
+}
+
+/// Get the logical offset, length, and hole flag of each extent.
+fn hole_layout(map: &[FileExtent]) -> Vec<(u64, u64, bool)> {
+    map.iter()
+        .map(|e| {
+            (
+                e.logical_offset(),
+                e.len(),
+                e.flags().contains(FileExtentFlags::HOLE),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_extent_map_holes() {
+    let expected = [
+        (0, 2048, true),
+        (2048, 2048, false),
+        (4096, 2048, true),
+        (6144, 2048, false),
+        (8192, 2048, true),
+    ];
+
+    for name in ["test_disk1.bin.zst", "test_disk_ext2.bin.zst"] {
+        let fs = load_compressed_filesystem(name);
+        let map = fs.open("/holes").unwrap().extent_map().unwrap();
+        check_extent_map(&map, 10 * 1024);
+
+        // The data ranges may have been merged if they happen to be
+        // physically contiguous, so only check the holes.
+        let holes: Vec<_> = hole_layout(&map)
+            .into_iter()
+            .filter(|(_, _, is_hole)| *is_hole)
+            .collect();
+        let expected_holes: Vec<_> =
+            expected.iter().copied().filter(|e| e.2).collect();
+        assert_eq!(holes, expected_holes);
+
+        check_extent_map_data(&fs, &read_compressed_filesystem(name), "/holes");
+    }
+}
+
+/// Test a large block-mapped file, which uses all levels of the block
+/// map.
+#[test]
+fn test_extent_map_block_map() {
+    let name = "test_disk_ext2.bin.zst";
+    let fs = load_compressed_filesystem(name);
+    check_extent_map_data(&fs, &read_compressed_filesystem(name), "/big_file");
+}
+
+#[test]
+fn test_extent_map_unwritten() {
+    let name = "test_disk_unwritten.bin.zst";
+    let fs = load_compressed_filesystem(name);
+
+    let map = fs.open("/prealloc").unwrap().extent_map().unwrap();
+    check_extent_map(&map, 8 * 1024);
+    assert!(
+        map.iter()
+            .all(|e| e.flags().contains(FileExtentFlags::UNWRITTEN))
+    );
+
+    let map = fs.open("/mixed").unwrap().extent_map().unwrap();
+    check_extent_map(&map, 6 * 1024);
+    assert_eq!(map.len(), 2);
+    assert_eq!(map[0].len(), 2048);
+    assert!(!map[0].flags().contains(FileExtentFlags::UNWRITTEN));
+    assert_eq!(map[1].len(), 4096);
+    assert!(map[1].flags().contains(FileExtentFlags::UNWRITTEN));
+    check_extent_map_data(&fs, &read_compressed_filesystem(name), "/mixed");
+}
+
+#[test]
+fn test_extent_map_small_and_empty() {
+    let fs = load_test_disk1();
+
+    let map = fs.open("/small_file").unwrap().extent_map().unwrap();
+    assert_eq!(map.len(), 1);
+    assert_eq!(map[0].logical_offset(), 0);
+    assert_eq!(map[0].len(), 1024);
+    assert_eq!(map[0].flags(), FileExtentFlags::LAST);
+
+    assert!(
+        fs.open("/empty_file")
+            .unwrap()
+            .extent_map()
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[test]
+fn test_extent_map_inline_data() {
+    let fs = load_compressed_filesystem("test_disk_inline_data.bin.zst");
+    assert!(fs.open("/small").unwrap().extent_map().unwrap().is_empty());
+}
+
+/// Test that blocks replaced by the journal point to the journal copy.
+#[test]
+fn test_extent_map_journal() {
+    let name = "test_disk_journal_classic.bin.zst";
+    let disk = read_compressed_filesystem(name);
+
+    let fs = load_compressed_filesystem(name);
+    check_extent_map_data(&fs, &disk, "/a");
+    let map = fs.open("/a").unwrap().extent_map().unwrap();
+    assert_eq!(map.len(), 1);
+    assert!(map[0].flags().contains(FileExtentFlags::JOURNAL));
+
+    // With the journal ignored, the original block is used.
+    let options = Ext4Options::new().journal_mode(JournalMode::Ignore);
+    let fs = Ext4::load_with_options(Box::new(disk.clone()), options).unwrap();
+    check_extent_map_data(&fs, &disk, "/a");
+    let map = fs.open("/a").unwrap().extent_map().unwrap();
+    assert_eq!(map.len(), 1);
+    assert!(!map[0].flags().contains(FileExtentFlags::JOURNAL));
+}
@@ -141,17 +141,25 @@ fn test_file_read_holes() {
     assert_eq!(file.read_bytes(&mut all).unwrap(), 0);
 }
 
-/// Test that each read is limited to at most one block.
+/// Test that each read is limited to at most one run of contiguous
+/// blocks.
 #[test]
-fn test_file_read_limited_to_block() {
-    let fs = load_ext2();
-    // Load a file that is larger than one block.
-    let mut file = fs.open("/big_file").unwrap();
+fn test_file_read_limited_to_run() {
+    let fs = load_test_disk1();
+    let mut file = fs.open("/holes").unwrap();
 
-    let mut buf = vec![0xff; 2048];
+    // Holes are read one block at a time.
+    let mut buf = vec![0xff; 4096];
     assert_eq!(file.read_bytes(&mut buf).unwrap(), 1024);
     assert_eq!(&buf[..1024], vec![0; 1024]);
-    assert_eq!(&buf[1024..], vec![0xff; 1024]);
+    assert_eq!(&buf[1024..], vec![0xff; 3072]);
+
+    // The data blocks are read up to the following hole.
+    let mut buf = vec![0xff; 4096];
+    file.seek_to(2048).unwrap();
+    assert_eq!(file.read_bytes(&mut buf).unwrap(), 2048);
+    assert_eq!(&buf[..2048], &expected_holes_data()[2048..4096]);
+    assert_eq!(&buf[2048..], vec![0xff; 2048]);
 }
 
 /// Test seeking in a small file.
@@ -195,6 +203,263 @@ fn test_file_seek_multiple_blocks() {
     assert_eq!(u32::from_le_bytes(buf), 1);
 }
 
+/// Test seeking to each level of a block map, including backwards
+/// seeks.
+#[test]
+fn test_file_seek_block_map_levels() {
+    let fs = load_ext2();
+    let mut file = fs.open("/big_file").unwrap();
+
+    let mut buf = [0; 4];
+
+    // Direct, indirect, double indirect, and triple indirect blocks,
+    // plus the boundaries between them. Seeking backwards to the start
+    // of the file at the end.
+    let blocks: [u32; 10] = [
+        11,
+        12,
+        12 + 255,
+        12 + 256,
+        12 + 256 + 1000,
+        12 + 256 + 65535,
+        12 + 256 + 65536,
+        12 + 256 + 65536 + 4095,
+        300,
+        0,
+    ];
+    for block in blocks {
+        file.seek_to(u64::from(block) * 1024).unwrap();
+        assert_eq!(file.read_bytes(&mut buf).unwrap(), buf.len());
+        assert_eq!(u32::from_le_bytes(buf), block);
+
+        // Reading continues into the next block.
+        file.seek_to(u64::from(block) * 1024 + 1020).unwrap();
+        assert_eq!(file.read_bytes(&mut buf).unwrap(), buf.len());
+        assert_eq!(u32::from_le_bytes(buf), block);
+        if block != 12 + 256 + 65536 + 4095 {
+            assert_eq!(file.read_bytes(&mut buf).unwrap(), buf.len());
+            assert_eq!(u32::from_le_bytes(buf), block + 1);
+        }
+    }
+}
+
+/// Test seeking into and out of holes.
+#[test]
+fn test_file_seek_holes() {
+    let expected = expected_holes_data();
+    for fs in [load_test_disk1(), load_ext2()] {
+        let mut file = fs.open("/holes").unwrap();
+
+        // Visit the blocks out of order.
+        for block in [9, 2, 0, 6, 5, 3, 8, 1, 7, 4] {
+            let start = block * 1024 + 10;
+            file.seek_to(u64::try_from(start).unwrap()).unwrap();
+
+            // Read up to 2048 bytes, which crosses at least one block
+            // boundary.
+            let end = (start + 2048).min(expected.len());
+            let mut buf = vec![0; end - start];
+            let mut num_read = 0;
+            while num_read < buf.len() {
+                num_read += file.read_bytes(&mut buf[num_read..]).unwrap();
+            }
+            assert_eq!(buf, expected[start..end]);
+        }
+    }
+}
+
+/// Test `File::read_at` on files with holes, including reads that
+/// cross block boundaries and reads past the end of the file.
+#[test]
+fn test_file_read_at_holes() {
+    let expected = expected_holes_data();
+    for fs in [load_test_disk1(), load_ext2()] {
+        let file = fs.open("/holes").unwrap();
+
+        for start in [0, 10, 1023, 1024, 2048 + 500, 5000, 10239] {
+            let mut buf = vec![0; 3000];
+            let n = file
+                .read_at(u64::try_from(start).unwrap(), &mut buf)
+                .unwrap();
+            let end = (start + buf.len()).min(expected.len());
+            assert_eq!(n, end - start);
+            assert_eq!(buf[..n], expected[start..end]);
+        }
+
+        // At and past the end of the file.
+        let mut buf = [0; 10];
+        assert_eq!(file.read_at(10240, &mut buf).unwrap(), 0);
+        assert_eq!(file.read_at(u64::MAX, &mut buf).unwrap(), 0);
+
+        // Empty buffer.
+        assert_eq!(file.read_at(0, &mut []).unwrap(), 0);
+
+        // The file position is not used or changed.
+        assert_eq!(file.position(), 0);
+    }
+}
+
+/// Test that `File::read_at` does not interfere with `File::read_bytes`.
+#[test]
+fn test_file_read_at_position() {
+    let fs = load_ext2();
+    let mut file = fs.open("/big_file").unwrap();
+
+    let mut buf = [0; 4];
+    file.seek_to(5 * 1024).unwrap();
+
+    let block = 12 + 256 + 65536 + 10;
+    file.read_at(block * 1024, &mut buf).unwrap();
+    assert_eq!(u64::from(u32::from_le_bytes(buf)), block);
+
+    assert_eq!(file.position(), 5 * 1024);
+    file.read_bytes(&mut buf).unwrap();
+    assert_eq!(u32::from_le_bytes(buf), 5);
+}
+
+/// Test `File::read_exact_at`.
+#[test]
+fn test_file_read_exact_at() {
+    let fs = load_test_disk1();
+    let file = fs.open("/small_file").unwrap();
+
+    let mut buf = [0; 5];
+    file.read_exact_at(7, &mut buf).unwrap();
+    assert_eq!(buf, *b"world");
+
+    let mut buf = [0; 7];
+    assert!(matches!(
+        file.read_exact_at(7, &mut buf).unwrap_err(),
+        Ext4Error::UnexpectedEof
+    ));
+
+    #[cfg(feature = "std")]
+    assert_eq!(
+        std::io::Error::from(file.read_exact_at(100, &mut buf).unwrap_err())
+            .kind(),
+        ErrorKind::UnexpectedEof
+    );
+}
+
+/// Test `File::seek_data` and `File::seek_hole` on files with holes.
+#[test]
+fn test_file_seek_data_and_hole() {
+    for fs in [load_test_disk1(), load_ext2()] {
+        let mut file = fs.open("/holes").unwrap();
+
+        assert_eq!(file.seek_hole(0).unwrap(), Some(0));
+        assert_eq!(file.seek_data(0).unwrap(), Some(2048));
+        assert_eq!(file.position(), 2048);
+        assert_eq!(file.seek_data(3000).unwrap(), Some(3000));
+        assert_eq!(file.seek_hole(2048).unwrap(), Some(4096));
+        assert_eq!(file.position(), 4096);
+        assert_eq!(file.seek_data(4096).unwrap(), Some(6144));
+        assert_eq!(file.seek_hole(7000).unwrap(), Some(8192));
+        assert_eq!(file.seek_hole(9000).unwrap(), Some(9000));
+
+        // No data after the last hole; the position is unchanged.
+        assert_eq!(file.seek_data(8192).unwrap(), None);
+        assert_eq!(file.position(), 9000);
+
+        // At or past the end of the file.
+        assert_eq!(file.seek_data(10240).unwrap(), None);
+        assert_eq!(file.seek_hole(10240).unwrap(), None);
+        assert_eq!(file.seek_hole(u64::MAX).unwrap(), None);
+    }
+}
+
+/// Test that the end of the file counts as a hole.
+#[test]
+fn test_file_seek_hole_end() {
+    let fs = load_test_disk1();
+    let mut file = fs.open("/small_file").unwrap();
+
+    assert_eq!(file.seek_data(0).unwrap(), Some(0));
+    assert_eq!(file.seek_hole(0).unwrap(), Some(13));
+
+    let mut file = fs.open("/empty_file").unwrap();
+    assert_eq!(file.seek_data(0).unwrap(), None);
+    assert_eq!(file.seek_hole(0).unwrap(), None);
+}
+
+/// Test copying a file with holes with `File::copy_sparse_to`.
+#[cfg(feature = "std")]
+#[test]
+fn test_file_copy_sparse_to() {
+    let path = std::env::temp_dir()
+        .join(format!("ext4-view-copy-sparse-{}", std::process::id()));
+
+    for fs in [load_test_disk1(), load_ext2()] {
+        let file = fs.open("/holes").unwrap();
+
+        // Start with existing data to check that holes are skipped
+        // rather than written, and that the length is set.
+        std::fs::write(&path, vec![0xff; 20 * 1024]).unwrap();
+        let mut dst = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .truncate(true)
+            .open(&path)
+            .unwrap();
+        assert_eq!(file.copy_sparse_to(&mut dst).unwrap(), 4096);
+        drop(dst);
+
+        assert_eq!(std::fs::read(&path).unwrap(), expected_holes_data());
+        assert_eq!(file.position(), 0);
+    }
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+/// Test reading with buffers that span multiple blocks, from various
+/// starting offsets.
+#[test]
+fn test_file_read_multiple_blocks() {
+    let fs = load_ext2();
+    let expected = fs.read("/big_file").unwrap();
+    let mut file = fs.open("/big_file").unwrap();
+
+    // Start in the direct, indirect, and double indirect blocks.
+    for start in [0, 11 * 1024 + 1, 300 * 1024 + 7] {
+        for buf_len in [1000, 1024, 5000, 100_000] {
+            file.seek_to(u64::try_from(start).unwrap()).unwrap();
+            let mut buf = vec![0; buf_len];
+            let mut pos = start;
+            // Read a few times to cross several block boundaries.
+            for _ in 0..4 {
+                let n = file.read_bytes(&mut buf).unwrap();
+                assert!(n > 0);
+                assert_eq!(buf[..n], expected[pos..pos + n]);
+                pos += n;
+            }
+            assert_eq!(file.position(), u64::try_from(pos).unwrap());
+        }
+
+        // A large `read_at` crosses several discontinuities in the
+        // block map.
+        let mut buf = vec![0; 300_000];
+        let n = file
+            .read_at(u64::try_from(start).unwrap(), &mut buf)
+            .unwrap();
+        assert_eq!(n, buf.len());
+        assert_eq!(buf, expected[start..start + n]);
+    }
+
+    for fs in [load_test_disk1(), load_ext2()] {
+        let mut file = fs.open("/holes").unwrap();
+        let mut buf = vec![0; 3000];
+        let mut data: Vec<u8> = Vec::new();
+        loop {
+            let n = file.read_bytes(&mut buf).unwrap();
+            if n == 0 {
+                break;
+            }
+            data.extend(&buf[..n]);
+        }
+        assert_eq!(data, expected_holes_data());
+    }
+}
+
 /// Test that seeking past the end is allowed (matching the behavior of
 /// `std::io::Seek` and POSIX seek in general).
 #[test]
@@ -0,0 +1,108 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::test_util::load_compressed_filesystem;
+use ext4_view::{Ext4, FileAttributes, Metadata};
+
+fn load_inline_data() -> Ext4 {
+    load_compressed_filesystem("test_disk_inline_data.bin.zst")
+}
+
+fn is_inline(metadata: &Metadata) -> bool {
+    metadata.attributes().contains(FileAttributes::INLINE_DATA)
+}
+
+#[test]
+fn test_inline_data_read() {
+    let fs = load_inline_data();
+
+    // Empty file.
+    assert!(is_inline(&fs.metadata("/empty").unwrap()));
+    assert_eq!(fs.read("/empty").unwrap(), b"");
+
+    // Data fits in `i_block`.
+    assert!(is_inline(&fs.metadata("/small").unwrap()));
+    assert_eq!(fs.read("/small").unwrap(), b"hello, inline!");
+    assert_eq!(fs.read("/exact60").unwrap(), [b'x'; 60]);
+
+    // Data is split between `i_block` and the `system.data` xattr.
+    let expected: Vec<u8> = (b'a'..=b'z').cycle().take(100).collect();
+    assert!(is_inline(&fs.metadata("/medium").unwrap()));
+    assert_eq!(fs.read("/medium").unwrap(), expected);
+
+    // Too large to be inline.
+    assert!(!is_inline(&fs.metadata("/large").unwrap()));
+    assert_eq!(fs.read("/large").unwrap(), [b'L'; 5000]);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_inline_data_seek() {
+    use std::io::{Read, Seek, SeekFrom};
+
+    let fs = load_inline_data();
+    let mut file = fs.open("/medium").unwrap();
+
+    // Read across the boundary between `i_block` and `system.data`.
+    file.seek(SeekFrom::Start(55)).unwrap();
+    let mut buf = [0; 10];
+    file.read_exact(&mut buf).unwrap();
+    assert_eq!(buf, *b"defghijklm");
+
+    file.seek(SeekFrom::End(-2)).unwrap();
+    let mut rest = Vec::new();
+    file.read_to_end(&mut rest).unwrap();
+    assert_eq!(rest, b"uv");
+}
+
+#[test]
+fn test_inline_data_read_dir() {
+    let fs = load_inline_data();
+
+    assert!(is_inline(&fs.metadata("/dir").unwrap()));
+
+    let names: Vec<String> = fs
+        .read_dir("/dir")
+        .unwrap()
+        .map(|e| e.unwrap().file_name().as_str().unwrap().to_owned())
+        .collect();
+    assert_eq!(names, [".", "..", "a", "b", "c"]);
+}
+
+#[test]
+fn test_inline_data_lookup() {
+    let fs = load_inline_data();
+
+    assert_eq!(fs.read("/dir/a").unwrap(), b"hello, inline!");
+    assert_eq!(fs.read("/dir/./b").unwrap(), b"hello, inline!");
+    assert_eq!(fs.read("/dir/c/../a").unwrap(), b"hello, inline!");
+    assert!(fs.metadata("/dir/c").unwrap().is_dir());
+    assert!(fs.exists("/dir/..").unwrap());
+    assert!(!fs.exists("/dir/d").unwrap());
+}
+
+#[test]
+fn test_inline_data_symlink() {
+    let fs = load_inline_data();
+
+    let expected = format!("/{}", "t".repeat(79));
+    assert_eq!(fs.read_link("/sym_80").unwrap(), expected.as_str());
+}
+
+#[test]
+fn test_inline_data_read_at() {
+    let fs = load_inline_data();
+
+    let file = fs.open("/medium").unwrap();
+    let expected: Vec<u8> = (b'a'..=b'z').cycle().take(100).collect();
+    let mut buf = [0; 50];
+    assert_eq!(file.read_at(55, &mut buf).unwrap(), 45);
+    assert_eq!(buf[..45], expected[55..]);
+    file.read_exact_at(10, &mut buf).unwrap();
+    assert_eq!(buf, expected[10..60]);
+}
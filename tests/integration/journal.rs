@@ -0,0 +1,95 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::test_util::{
+    load_compressed_filesystem, read_compressed_filesystem,
+};
+use ext4_view::{Ext4, Ext4Error, Ext4Options, JournalMode};
+
+/// Check that the journal transaction was applied. The transaction
+/// replaces the data of `/a` and `/b`, but the block for `/b` is also
+/// revoked.
+fn check_journal_replay(name: &str) {
+    let fs = load_compressed_filesystem(name);
+
+    assert_eq!(fs.read("/a").unwrap(), [b'n'; 1024]);
+    assert_eq!(fs.read("/b").unwrap(), [b'o'; 1024]);
+}
+
+/// Test a journal with 32-bit block indices and no checksums.
+#[test]
+fn test_journal_classic() {
+    check_journal_replay("test_disk_journal_classic.bin.zst");
+}
+
+/// Test a journal with 64-bit block indices and no checksums.
+#[test]
+fn test_journal_64bit_no_checksums() {
+    check_journal_replay("test_disk_journal_64bit.bin.zst");
+}
+
+/// Test a journal with 32-bit block indices and v2 checksums.
+#[test]
+fn test_journal_checksum_v2() {
+    check_journal_replay("test_disk_journal_csum_v2.bin.zst");
+}
+
+fn load_with_journal_mode(name: &str, journal_mode: JournalMode) -> Ext4 {
+    let data = read_compressed_filesystem(name);
+    let options = Ext4Options::new().journal_mode(journal_mode);
+    Ext4::load_with_options(Box::new(data), options).unwrap()
+}
+
+/// Test that the journal is only replayed when requested.
+#[test]
+fn test_journal_mode() {
+    let name = "test_disk_journal_classic.bin.zst";
+
+    for mode in [JournalMode::Replay, JournalMode::ReplayIfNeeded] {
+        let fs = load_with_journal_mode(name, mode);
+        assert_eq!(fs.read("/a").unwrap(), [b'n'; 1024]);
+    }
+
+    // With the journal ignored, the original data is visible.
+    let fs = load_with_journal_mode(name, JournalMode::Ignore);
+    assert_eq!(fs.read("/a").unwrap(), [b'o'; 1024]);
+}
+
+/// Test that replaying the journal of a cleanly-unmounted filesystem
+/// works.
+#[test]
+fn test_journal_mode_replay_clean() {
+    let name = "test_disk1.bin.zst";
+    let fs = load_with_journal_mode(name, JournalMode::Replay);
+    assert_eq!(fs.read("/small_file").unwrap(), b"hello, world!");
+}
+
+/// Test that an invalid journal inode is only an error if the journal
+/// is used.
+#[test]
+fn test_journal_mode_invalid_journal_inode() {
+    let mut data =
+        read_compressed_filesystem("test_disk_journal_classic.bin.zst");
+
+    // Set `s_journal_inum` to zero.
+    let journal_inum_offset = 1024 + 0xe0;
+    data[journal_inum_offset..journal_inum_offset + 4].fill(0);
+
+    let options = Ext4Options::new()
+        .journal_mode(JournalMode::Ignore)
+        .verify_checksums(false);
+    let fs = Ext4::load_with_options(Box::new(data.clone()), options).unwrap();
+    assert_eq!(fs.read("/a").unwrap(), [b'o'; 1024]);
+
+    let options = Ext4Options::new()
+        .journal_mode(JournalMode::Replay)
+        .verify_checksums(false);
+    let err = Ext4::load_with_options(Box::new(data), options).unwrap_err();
+    assert!(matches!(err, Ext4Error::Corrupt(_)));
+    assert!(err.to_string().contains("invalid journal inode"));
+}
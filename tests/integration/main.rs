@@ -6,13 +6,26 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+mod async_ext4;
+mod block_cache;
 mod ext2;
 mod ext3;
 mod ext4;
+mod extent_map;
 mod file;
+mod inline_data;
+mod journal;
 mod label;
+mod meta_bg;
+mod options;
 mod path;
+mod reader;
+mod stats;
+#[cfg(feature = "sync")]
+mod sync;
+mod unwritten;
 mod uuid;
+mod xattr;
 
 use ext4_view::Ext4;
 
@@ -0,0 +1,54 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::test_util::{
+    load_compressed_filesystem, read_compressed_filesystem,
+};
+use ext4_view::{Ext4, Metadata};
+
+const NAME: &str = "test_disk_meta_bg.bin.zst";
+
+/// Byte offset of the checksum of block group descriptor 16 in the
+/// copy of the descriptor block stored at `block`.
+fn checksum_offset(block: usize) -> usize {
+    block * 1024 + 0x1e
+}
+
+fn far_metadata(fs: &Ext4) -> Metadata {
+    fs.metadata("/far").unwrap()
+}
+
+#[test]
+fn test_meta_bg() {
+    let fs = load_compressed_filesystem(NAME);
+
+    // The file's inode is in block group 16, whose descriptor is in
+    // the second meta group.
+    assert_eq!(far_metadata(&fs).ino(), 32769);
+    assert_eq!(fs.read_to_string("/far").unwrap(), "hello, meta_bg!");
+}
+
+#[test]
+fn test_meta_bg_backup_descriptor() {
+    let mut data = read_compressed_filesystem(NAME);
+
+    // Corrupt the primary copy of the descriptor in group 16. The
+    // backup in group 17 is used instead.
+    data[checksum_offset(131073)] ^= 0xff;
+    let fs = Ext4::load(Box::new(data.clone())).unwrap();
+    assert_eq!(fs.read_to_string("/far").unwrap(), "hello, meta_bg!");
+
+    // Corrupt the backup too. There is no copy in the last group of the
+    // meta group, because it is past the end of the filesystem.
+    data[checksum_offset(139265)] ^= 0xff;
+    let fs = Ext4::load(Box::new(data)).unwrap();
+    assert_eq!(
+        fs.read_to_string("/far").unwrap_err().to_string(),
+        "corrupt filesystem: invalid checksum for block group descriptor 16"
+    );
+}
@@ -0,0 +1,96 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::expected_holes_data;
+use crate::test_util::read_compressed_filesystem;
+use ext4_view::{Ext4, Ext4Error, Ext4Options};
+
+fn load_test_disk1_with_options(options: Ext4Options) -> Ext4 {
+    let data = read_compressed_filesystem("test_disk1.bin.zst");
+    Ext4::load_with_options(Box::new(data), options).unwrap()
+}
+
+/// Test that small and large cache settings don't affect the data that
+/// is read.
+#[test]
+fn test_cache_options() {
+    for (num_entries, read_ahead_size) in
+        [(1, 0), (1, 1024), (4, 4096), (128, 64 * 1024)]
+    {
+        let options = Ext4Options::new()
+            .cache_entries(num_entries)
+            .read_ahead_size(read_ahead_size);
+        let fs = load_test_disk1_with_options(options);
+
+        assert_eq!(fs.read("/small_file").unwrap(), b"hello, world!");
+        assert_eq!(fs.read("/holes").unwrap(), expected_holes_data());
+    }
+}
+
+/// Test that checksum verification can be disabled.
+#[test]
+fn test_verify_checksums() {
+    let mut data = read_compressed_filesystem("test_disk1.bin.zst");
+
+    // Corrupt the superblock checksum, which is stored in the last
+    // four bytes of the superblock.
+    let superblock_checksum_offset = 1024 + 0x3fc;
+    data[superblock_checksum_offset] ^= 0xff;
+
+    assert!(matches!(
+        Ext4::load(Box::new(data.clone())).unwrap_err(),
+        Ext4Error::Corrupt(_)
+    ));
+
+    let options = Ext4Options::new().verify_checksums(false);
+    let fs = Ext4::load_with_options(Box::new(data), options).unwrap();
+    assert_eq!(fs.read("/small_file").unwrap(), b"hello, world!");
+}
+
+/// Test that the symlink limit is configurable.
+#[test]
+fn test_max_symlinks() {
+    // With the default limit, a symlink loop is detected.
+    let fs = load_test_disk1_with_options(Ext4Options::new());
+    assert!(matches!(
+        fs.read("/sym_loop_a").unwrap_err(),
+        Ext4Error::TooManySymlinks
+    ));
+
+    // With no symlinks allowed, any symlink causes an error.
+    let fs = load_test_disk1_with_options(Ext4Options::new().max_symlinks(0));
+    assert!(matches!(
+        fs.read("/sym_simple").unwrap_err(),
+        Ext4Error::TooManySymlinks
+    ));
+    assert_eq!(fs.read("/small_file").unwrap(), b"hello, world!");
+
+    // Resolving this path follows two symlinks.
+    let path = "/dir1/dir2/sym_rel_dir/../sym_simple";
+    let fs = load_test_disk1_with_options(Ext4Options::new().max_symlinks(1));
+    assert!(matches!(
+        fs.read(path).unwrap_err(),
+        Ext4Error::TooManySymlinks
+    ));
+    let fs = load_test_disk1_with_options(Ext4Options::new().max_symlinks(2));
+    assert_eq!(fs.read(path).unwrap(), b"hello, world!");
+
+    // Very large limits are clamped, so a symlink loop is still
+    // detected.
+    assert_eq!(
+        Ext4Options::new().max_symlinks(usize::MAX),
+        Ext4Options::new().max_symlinks(Ext4Options::MAX_SYMLINKS_LIMIT)
+    );
+    let fs = load_test_disk1_with_options(
+        Ext4Options::new().max_symlinks(usize::MAX),
+    );
+    assert!(matches!(
+        fs.read("/sym_loop_a").unwrap_err(),
+        Ext4Error::TooManySymlinks
+    ));
+}
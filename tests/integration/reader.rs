@@ -0,0 +1,360 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::expected_holes_data;
+use crate::test_util::read_compressed_filesystem;
+use ext4_view::{Advice, Ext4, Ext4Options, Ext4Read};
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+
+/// Start byte and length of each read in a `read_many` call.
+type ReadManyCall = Vec<(u64, usize)>;
+
+/// Reader that records the arguments of each `read` and `read_many`
+/// call.
+struct RecordingReader {
+    data: Vec<u8>,
+    read_calls: Arc<Mutex<Vec<(u64, usize)>>>,
+    read_many_calls: Arc<Mutex<Vec<ReadManyCall>>>,
+}
+
+impl RecordingReader {
+    fn new(name: &str) -> Self {
+        Self {
+            data: read_compressed_filesystem(name),
+            read_calls: Arc::default(),
+            read_many_calls: Arc::default(),
+        }
+    }
+}
+
+impl Ext4Read for RecordingReader {
+    fn read(
+        &mut self,
+        start_byte: u64,
+        dst: &mut [u8],
+    ) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
+        self.read_calls
+            .lock()
+            .unwrap()
+            .push((start_byte, dst.len()));
+        self.data.read(start_byte, dst)
+    }
+
+    fn read_many(
+        &mut self,
+        reads: &mut [(u64, &mut [u8])],
+    ) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
+        self.read_many_calls.lock().unwrap().push(
+            reads
+                .iter()
+                .map(|(start_byte, dst)| (*start_byte, dst.len()))
+                .collect(),
+        );
+        for (start_byte, dst) in reads {
+            self.data.read(*start_byte, dst)?;
+        }
+        Ok(())
+    }
+}
+
+/// Test that `Ext4::read` reads all of a file's data with one
+/// `read_many` call, with one read per run of contiguous blocks.
+#[test]
+fn test_read_many() {
+    let reader = RecordingReader::new("test_disk1.bin.zst");
+    let read_many_calls = reader.read_many_calls.clone();
+    let fs = Ext4::load(Box::new(reader)).unwrap();
+
+    assert_eq!(fs.read("/holes").unwrap(), expected_holes_data());
+
+    let calls = read_many_calls.lock().unwrap();
+    assert_eq!(calls.len(), 1);
+    let reads = &calls[0];
+    // The file has two runs of two data blocks, separated by holes.
+    assert_eq!(reads.len(), 2);
+    assert!(reads.iter().all(|(_, len)| *len == 2 * 1024));
+}
+
+/// Test that `File::read_bytes` with a large buffer reads directly from
+/// the reader, continuing past holes and discontinuities, with one
+/// `read_many` call per `read_bytes` call.
+#[test]
+fn test_read_bytes_direct() {
+    let reader = RecordingReader::new("test_disk1.bin.zst");
+    let read_calls = reader.read_calls.clone();
+    let read_many_calls = reader.read_many_calls.clone();
+    // Reads at least as large as the read-ahead size are read directly.
+    let options = Ext4Options::new().read_ahead_size(1024);
+    let fs = Ext4::load_with_options(Box::new(reader), options).unwrap();
+    let expected = expected_holes_data();
+
+    let mut file = fs.open("/holes").unwrap();
+    let mut buf = vec![0; 4096];
+
+    // Start partway into the first run of data blocks. The read
+    // continues through the holes into the second run; each run of data
+    // is one read.
+    file.seek_to(2048 + 100).unwrap();
+    take_read_lens(&read_calls);
+    assert_eq!(file.read_bytes(&mut buf).unwrap(), 4096);
+    assert_eq!(buf, expected[2148..6244]);
+    assert_eq!(take_read_many_lens(&read_many_calls), [vec![1948, 100]]);
+    assert!(take_read_lens(&read_calls).is_empty());
+
+    // Reads that start in a hole are still read one block at a time,
+    // without any reads.
+    file.seek_to(4096).unwrap();
+    assert_eq!(file.read_bytes(&mut buf).unwrap(), 1024);
+    assert_eq!(file.read_bytes(&mut buf).unwrap(), 1024);
+    assert!(take_read_many_lens(&read_many_calls).is_empty());
+
+    // Stop partway through the second run, then continue reading
+    // through the cache.
+    assert_eq!(file.read_bytes(&mut buf[..1500]).unwrap(), 1500);
+    assert_eq!(buf[..1500], expected[6144..7644]);
+    assert_eq!(take_read_many_lens(&read_many_calls), [vec![1500]]);
+    assert_eq!(file.read_bytes(&mut buf[..100]).unwrap(), 100);
+    assert_eq!(buf[..100], expected[7644..7744]);
+    assert_eq!(take_read_lens(&read_calls).len(), 1);
+    assert_eq!(file.position(), 7744);
+}
+
+/// Test that `File::read_at` with a large buffer reads all of the
+/// file's runs of blocks with one `read_many` call.
+#[test]
+fn test_read_at_direct() {
+    let reader = RecordingReader::new("test_disk1.bin.zst");
+    let read_calls = reader.read_calls.clone();
+    let read_many_calls = reader.read_many_calls.clone();
+    let options = Ext4Options::new().read_ahead_size(1024);
+    let fs = Ext4::load_with_options(Box::new(reader), options).unwrap();
+    let expected = expected_holes_data();
+
+    let file = fs.open("/holes").unwrap();
+    take_read_lens(&read_calls);
+    let mut buf = vec![0; expected.len()];
+    assert_eq!(file.read_at(0, &mut buf).unwrap(), expected.len());
+    assert_eq!(buf, expected);
+    // The file has two runs of two data blocks, separated by holes.
+    assert_eq!(take_read_many_lens(&read_many_calls), [vec![2048, 2048]]);
+    assert!(take_read_lens(&read_calls).is_empty());
+
+    // Small reads still go through the block cache.
+    let mut buf = vec![0; 100];
+    assert_eq!(file.read_at(2048, &mut buf).unwrap(), 100);
+    assert_eq!(buf, expected[2048..2148]);
+    assert!(take_read_many_lens(&read_many_calls).is_empty());
+    assert_eq!(take_read_lens(&read_calls).len(), 1);
+}
+
+/// Test that `File::read_bytes` with buffers smaller than the
+/// read-ahead size reads through the block cache, so that sequential
+/// reads still benefit from read-ahead.
+#[test]
+fn test_read_bytes_small_buffers() {
+    let reader = RecordingReader::new("test_disk_ext2.bin.zst");
+    let read_calls = reader.read_calls.clone();
+    let read_many_calls = reader.read_many_calls.clone();
+    let fs = Ext4::load(Box::new(reader)).unwrap();
+    let expected = fs.read("/big_file").unwrap();
+    take_read_many_lens(&read_many_calls);
+
+    // Read the start of the file with 8K buffers, like `BufReader`
+    // does. Block sizes are 1K, so each buffer spans eight blocks.
+    let mut file = fs.open("/big_file").unwrap();
+    take_read_lens(&read_calls);
+    let mut buf = vec![0; 8 * 1024];
+    let mut pos = 0;
+    while pos < 64 * 1024 {
+        let n = file.read_bytes(&mut buf).unwrap();
+        assert_eq!(buf[..n], expected[pos..pos + n]);
+        pos += n;
+    }
+    // The read-ahead window grows with each sequential read.
+    assert_eq!(
+        take_read_lens(&read_calls),
+        [8 * 1024, 8 * 1024, 16 * 1024, 32 * 1024]
+    );
+
+    // A buffer at least as large as the read-ahead size is read
+    // directly.
+    let mut buf = vec![0; 32 * 1024];
+    file.seek_to(128 * 1024).unwrap();
+    assert_eq!(file.read_bytes(&mut buf).unwrap(), buf.len());
+    assert_eq!(buf, expected[128 * 1024..160 * 1024]);
+    assert_eq!(take_read_many_lens(&read_many_calls), [vec![32 * 1024]]);
+    assert!(take_read_lens(&read_calls).is_empty());
+}
+
+/// Get the length of each recorded `read` call, and clear the record.
+fn take_read_lens(read_calls: &Mutex<Vec<(u64, usize)>>) -> Vec<usize> {
+    read_calls
+        .lock()
+        .unwrap()
+        .drain(..)
+        .map(|(_, len)| len)
+        .collect()
+}
+
+/// Get the length of each read in each recorded `read_many` call, and
+/// clear the record.
+fn take_read_many_lens(
+    read_many_calls: &Mutex<Vec<ReadManyCall>>,
+) -> Vec<Vec<usize>> {
+    read_many_calls
+        .lock()
+        .unwrap()
+        .drain(..)
+        .map(|reads| reads.into_iter().map(|(_, len)| len).collect())
+        .collect()
+}
+
+/// Test that read-ahead through the block cache stays within the
+/// current extent, and that `Advice::Random` disables it.
+#[test]
+fn test_read_ahead_extent_aware() {
+    let reader = RecordingReader::new("test_disk1.bin.zst");
+    let read_calls = reader.read_calls.clone();
+    let fs = Ext4::load(Box::new(reader)).unwrap();
+    let expected = expected_holes_data();
+
+    let mut file = fs.open("/holes").unwrap();
+    let mut buf = vec![0; 100];
+
+    // The first run of data is two blocks long. Only those two blocks
+    // are read, even though the read-ahead size is larger.
+    file.seek_to(2048).unwrap();
+    take_read_lens(&read_calls);
+    file.read_bytes(&mut buf).unwrap();
+    assert_eq!(buf, expected[2048..2148]);
+    assert_eq!(take_read_lens(&read_calls), [2048]);
+
+    // The second block was read ahead.
+    file.seek_to(3072).unwrap();
+    file.read_bytes(&mut buf).unwrap();
+    assert_eq!(buf, expected[3072..3172]);
+    assert!(take_read_lens(&read_calls).is_empty());
+
+    // With random access advice, only the requested block is read.
+    file.advise(0..0, Advice::Random).unwrap();
+    file.seek_to(6144).unwrap();
+    file.read_bytes(&mut buf).unwrap();
+    assert_eq!(buf, expected[6144..6244]);
+    assert_eq!(take_read_lens(&read_calls), [1024]);
+}
+
+/// Test that the read-ahead window grows as a file is read
+/// sequentially.
+#[test]
+fn test_read_ahead_grows() {
+    let reader = RecordingReader::new("test_disk_ext2.bin.zst");
+    let read_calls = reader.read_calls.clone();
+    let fs = Ext4::load(Box::new(reader)).unwrap();
+
+    // This file uses a block map. Block sizes are 1K, so the maximum
+    // read-ahead (32K) is 32 blocks.
+    let mut file = fs.open("/big_file").unwrap();
+    take_read_lens(&read_calls);
+    let mut buf = vec![0; 512];
+
+    // Read the twelve direct blocks. Read-ahead stops at the last
+    // direct block, rather than continuing into unrelated blocks.
+    for _ in 0..(2 * 12) {
+        file.read_bytes(&mut buf).unwrap();
+    }
+    assert_eq!(take_read_lens(&read_calls), [4 * 1024, 8 * 1024]);
+
+    // Continue through the indirect block. The window grows to the
+    // maximum.
+    for _ in 0..(2 * 100) {
+        file.read_bytes(&mut buf).unwrap();
+    }
+    let lens = take_read_lens(&read_calls);
+    assert_eq!(lens.iter().max(), Some(&(32 * 1024)));
+}
+
+/// Test that a large read of data prefetched with `Advice::WillNeed`
+/// is served from the block cache, and that only blocks that are not
+/// cached are read.
+#[test]
+fn test_read_bytes_uses_cache() {
+    let reader = RecordingReader::new("test_disk_ext2.bin.zst");
+    let read_calls = reader.read_calls.clone();
+    let read_many_calls = reader.read_many_calls.clone();
+    let fs = Ext4::load(Box::new(reader)).unwrap();
+    let expected = fs.read("/big_file").unwrap();
+    take_read_many_lens(&read_many_calls);
+
+    // Start within the indirect block, so that the data blocks are
+    // physically contiguous.
+    let start = 20 * 1024;
+    let mut file = fs.open("/big_file").unwrap();
+    file.advise(start..start + 16 * 1024, Advice::WillNeed)
+        .unwrap();
+    file.seek_to(start).unwrap();
+    take_read_lens(&read_calls);
+
+    // The first 16 blocks are cached, the next 24 are read with a
+    // single read.
+    let mut buf = vec![0; 40 * 1024];
+    assert_eq!(file.read_bytes(&mut buf).unwrap(), buf.len());
+    let start = usize::try_from(start).unwrap();
+    assert_eq!(buf, expected[start..start + buf.len()]);
+    assert_eq!(take_read_many_lens(&read_many_calls), [vec![24 * 1024]]);
+    assert!(take_read_lens(&read_calls).is_empty());
+
+    // Reading the same range again is served entirely from the cache
+    // for the blocks that were prefetched.
+    file.seek_to(u64::try_from(start).unwrap()).unwrap();
+    let mut buf = vec![0; 16 * 1024];
+    assert_eq!(file.read_bytes(&mut buf).unwrap(), buf.len());
+    assert!(take_read_lens(&read_calls).is_empty());
+    assert!(take_read_many_lens(&read_many_calls).is_empty());
+}
+
+/// Test that `Advice::WillNeed` reads data into the block cache.
+#[test]
+fn test_advise_will_need() {
+    let reader = RecordingReader::new("test_disk1.bin.zst");
+    let read_calls = reader.read_calls.clone();
+    let fs = Ext4::load(Box::new(reader)).unwrap();
+    let expected = expected_holes_data();
+
+    let mut file = fs.open("/holes").unwrap();
+    take_read_lens(&read_calls);
+
+    // Each run of two data blocks is read at once. Holes and the range
+    // past the end of the file are skipped.
+    file.advise(0..100_000, Advice::WillNeed).unwrap();
+    assert_eq!(take_read_lens(&read_calls), [2048, 2048]);
+    assert_eq!(file.position(), 0);
+
+    // Reads within the range are now cache hits.
+    let mut buf = vec![0; 100];
+    for offset in [2048, 3000, 6144, 7000] {
+        file.read_exact_at(offset, &mut buf).unwrap();
+        assert_eq!(buf, expected[offset as usize..][..100]);
+    }
+    assert!(take_read_lens(&read_calls).is_empty());
+}
+
+/// Test that the default `read_many` implementation works.
+#[test]
+fn test_read_many_default() {
+    let mut data = vec![1, 2, 3, 4, 5];
+    let mut a = [0; 2];
+    let mut b = [0; 1];
+    data.read_many(&mut [(3, &mut a), (0, &mut b)]).unwrap();
+    assert_eq!(a, [4, 5]);
+    assert_eq!(b, [1]);
+
+    assert!(data.read_many(&mut [(0, &mut a), (4, &mut b[..])]).is_ok());
+    let mut c = [0; 2];
+    assert!(data.read_many(&mut [(0, &mut a), (4, &mut c)]).is_err());
+}
@@ -0,0 +1,121 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::test_util::{
+    load_compressed_filesystem, read_compressed_filesystem,
+};
+use ext4_view::{Ext4, Ext4Options, Ext4Stats, JournalMode};
+
+#[test]
+fn test_stats() {
+    let fs = load_compressed_filesystem("test_disk1.bin.zst");
+
+    // Loading reads the superblock; everything else is read when
+    // needed.
+    let stats = fs.stats();
+    assert_eq!(stats.reads(), 1);
+    assert_eq!(stats.bytes_read(), 1024);
+    assert_eq!(stats.checksum_verifications(), 0);
+    assert_eq!(stats.journal_remaps(), 0);
+
+    fs.reset_stats();
+    assert_eq!(fs.stats(), Ext4Stats::default());
+
+    // The first lookup reads metadata through the cache.
+    fs.metadata("/small_file").unwrap();
+    let first = fs.stats();
+    assert!(first.cache_misses() > 0);
+    assert!(first.reads() > 0);
+    assert_eq!(first.bytes_read() % 1024, 0);
+    assert!(first.checksum_verifications() > 0);
+
+    // The second lookup is served entirely from the cache.
+    fs.reset_stats();
+    fs.metadata("/small_file").unwrap();
+    let second = fs.stats();
+    assert!(second.cache_hits() > 0);
+    assert_eq!(second.cache_misses(), 0);
+    assert_eq!(second.reads(), 0);
+    assert_eq!(second.bytes_read(), 0);
+    assert_eq!(second.cache_evictions(), 0);
+
+    // Clones share the same counters.
+    let clone = fs.clone();
+    assert_eq!(clone.stats(), second);
+    clone.reset_stats();
+    assert_eq!(fs.stats(), Ext4Stats::default());
+}
+
+#[test]
+fn test_stats_read_file() {
+    let fs = load_compressed_filesystem("test_disk1.bin.zst");
+
+    // Reading a file counts the bytes of file data read.
+    let data = fs.read("/holes").unwrap();
+    fs.reset_stats();
+    assert_eq!(fs.read("/holes").unwrap(), data);
+    let stats = fs.stats();
+    assert!(stats.reads() > 0);
+    assert!(stats.bytes_read() >= 4 * 1024);
+}
+
+#[test]
+fn test_stats_evictions() {
+    // With a tiny cache, reading many blocks evicts some of them.
+    let options = Ext4Options::new().cache_entries(2);
+    let fs = Ext4::load_with_options(
+        Box::new(read_compressed_filesystem("test_disk1.bin.zst")),
+        options,
+    )
+    .unwrap();
+    for entry in fs.read_dir("/").unwrap() {
+        let path = entry.unwrap().path();
+        fs.symlink_metadata(&path).unwrap();
+    }
+    assert!(fs.stats().cache_evictions() > 0);
+}
+
+#[test]
+fn test_stats_journal_remaps() {
+    let name = "test_disk_journal_classic.bin.zst";
+
+    let fs = load_compressed_filesystem(name);
+    fs.read("/a").unwrap();
+    assert!(fs.stats().journal_remaps() > 0);
+
+    // With the journal ignored, no blocks are remapped.
+    let options = Ext4Options::new().journal_mode(JournalMode::Ignore);
+    let fs = Ext4::load_with_options(
+        Box::new(read_compressed_filesystem(name)),
+        options,
+    )
+    .unwrap();
+    fs.read("/a").unwrap();
+    assert_eq!(fs.stats().journal_remaps(), 0);
+}
+
+/// Test that each remapped block is counted once per read of the
+/// block.
+#[test]
+fn test_stats_journal_remaps_count() {
+    let fs = load_compressed_filesystem("test_disk_journal_classic.bin.zst");
+
+    // `/a` is a single block that has been replaced by the journal.
+    let mut file = fs.open("/a").unwrap();
+    let mut buf = [0; 1024];
+    fs.reset_stats();
+    assert_eq!(file.read_bytes(&mut buf).unwrap(), 1024);
+    assert_eq!(buf, [b'n'; 1024]);
+    assert_eq!(fs.stats().journal_remaps(), 1);
+
+    // Reading the block again counts it again, even though it is now
+    // cached.
+    file.seek_to(0).unwrap();
+    assert_eq!(file.read_bytes(&mut buf).unwrap(), 1024);
+    assert_eq!(fs.stats().journal_remaps(), 2);
+}
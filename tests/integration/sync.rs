@@ -0,0 +1,106 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::expected_holes_data;
+use crate::test_util::{load_test_disk1, read_compressed_filesystem};
+use ext4_view::{DirEntry, Ext4, Ext4Error, Ext4Read, File, ReadDir};
+use std::error::Error;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+
+/// Check at compile time that the main types can be shared between
+/// threads.
+#[test]
+fn test_send_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    fn assert_send<T: Send>() {}
+
+    assert_send_sync::<Ext4>();
+    assert_send_sync::<Ext4Error>();
+    assert_send::<File>();
+    assert_send::<ReadDir>();
+    assert_send::<DirEntry>();
+}
+
+/// Test reading from multiple threads at once.
+#[test]
+fn test_concurrent_reads() {
+    let fs = load_test_disk1();
+    let expected_holes_data = expected_holes_data();
+
+    thread::scope(|s| {
+        for _ in 0..8 {
+            s.spawn(|| {
+                for _ in 0..10 {
+                    assert_eq!(
+                        fs.read("/small_file").unwrap(),
+                        b"hello, world!"
+                    );
+                    assert_eq!(fs.read("/holes").unwrap(), expected_holes_data);
+                    assert!(fs.read_dir("/").unwrap().count() > 2);
+                }
+            });
+        }
+    });
+}
+
+/// Test moving a `File` and a `ReadDir` to another thread.
+#[test]
+fn test_move_to_thread() {
+    let fs = load_test_disk1();
+    let file = fs.open("/small_file").unwrap();
+    let read_dir = fs.read_dir("/").unwrap();
+
+    let (file_size, num_entries) =
+        thread::spawn(move || (file.metadata().len(), read_dir.count()))
+            .join()
+            .unwrap();
+    assert_eq!(file_size, 13);
+    assert!(num_entries > 2);
+}
+
+/// Reader that panics while `should_panic` is set.
+struct PanickingReader {
+    data: Vec<u8>,
+    should_panic: Arc<AtomicBool>,
+}
+
+impl Ext4Read for PanickingReader {
+    fn read(
+        &mut self,
+        start_byte: u64,
+        dst: &mut [u8],
+    ) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
+        assert!(!self.should_panic.load(Ordering::Relaxed), "read failed");
+        self.data.read(start_byte, dst)
+    }
+}
+
+/// Test that a panic in the reader doesn't prevent later use of the
+/// filesystem.
+#[test]
+fn test_reader_panic() {
+    let should_panic = Arc::new(AtomicBool::new(false));
+    let fs = Ext4::load(Box::new(PanickingReader {
+        data: read_compressed_filesystem("test_disk1.bin.zst"),
+        should_panic: should_panic.clone(),
+    }))
+    .unwrap();
+
+    should_panic.store(true, Ordering::Relaxed);
+    let fs_clone = fs.clone();
+    assert!(
+        thread::spawn(move || fs_clone.read("/small_file"))
+            .join()
+            .is_err()
+    );
+
+    should_panic.store(false, Ordering::Relaxed);
+    assert_eq!(fs.read("/small_file").unwrap(), b"hello, world!");
+}
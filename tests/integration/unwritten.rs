@@ -0,0 +1,62 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::test_util::load_compressed_filesystem;
+use ext4_view::Ext4;
+
+fn load_unwritten() -> Ext4 {
+    load_compressed_filesystem("test_disk_unwritten.bin.zst")
+}
+
+/// The unwritten blocks on disk contain stale data from a deleted
+/// file; verify that zeros are returned instead.
+#[test]
+fn test_unwritten_extents() {
+    let fs = load_unwritten();
+
+    // File that is entirely unwritten.
+    assert_eq!(fs.read("/prealloc").unwrap(), vec![0; 8 * 1024]);
+
+    // File with initialized blocks followed by unwritten blocks.
+    let mut expected = vec![0xa1; 2 * 1024];
+    expected.extend(vec![0; 4 * 1024]);
+    assert_eq!(fs.read("/mixed").unwrap(), expected);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_unwritten_extents_seek() {
+    use std::io::{Read, Seek, SeekFrom};
+
+    let fs = load_unwritten();
+    let mut file = fs.open("/mixed").unwrap();
+
+    // Read across the boundary between the initialized and unwritten
+    // extents.
+    file.seek(SeekFrom::Start(2040)).unwrap();
+    let mut buf = [0xff; 16];
+    file.read_exact(&mut buf).unwrap();
+    assert_eq!(buf[..8], [0xa1; 8]);
+    assert_eq!(buf[8..], [0; 8]);
+}
+
+/// Unwritten extents are treated as holes by `seek_data` and
+/// `seek_hole`.
+#[test]
+fn test_unwritten_extents_seek_data_and_hole() {
+    let fs = load_unwritten();
+
+    let mut file = fs.open("/prealloc").unwrap();
+    assert_eq!(file.seek_data(0).unwrap(), None);
+    assert_eq!(file.seek_hole(0).unwrap(), Some(0));
+
+    let mut file = fs.open("/mixed").unwrap();
+    assert_eq!(file.seek_data(0).unwrap(), Some(0));
+    assert_eq!(file.seek_hole(0).unwrap(), Some(2048));
+    assert_eq!(file.seek_data(2048).unwrap(), None);
+}
@@ -0,0 +1,213 @@
+// Copyright 2026 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use crate::test_util::{
+    load_compressed_filesystem, load_test_disk1, read_compressed_filesystem,
+};
+use ext4_view::{Ext4, Ext4Error};
+
+fn load_xattr() -> Ext4 {
+    load_compressed_filesystem("test_disk_xattr.bin.zst")
+}
+
+#[test]
+fn test_xattrs_in_inode() {
+    let fs = load_xattr();
+
+    assert_eq!(
+        fs.list_xattrs("/small").unwrap(),
+        [b"user.a".to_vec(), b"trusted.b".to_vec()]
+    );
+    assert_eq!(fs.get_xattr("/small", "user.a").unwrap().unwrap(), b"abc");
+    assert_eq!(
+        fs.get_xattr("/small", b"trusted.b").unwrap().unwrap(),
+        b"def"
+    );
+
+    // Name must match exactly, including the prefix.
+    assert!(fs.get_xattr("/small", "a").unwrap().is_none());
+    assert!(fs.get_xattr("/small", "trusted.a").unwrap().is_none());
+    assert!(fs.get_xattr("/small", "user.c").unwrap().is_none());
+}
+
+#[test]
+fn test_xattrs_in_block() {
+    let fs = load_xattr();
+
+    // The small value is in the inode, the big value is in the block.
+    assert_ne!(fs.metadata("/block").unwrap().file_acl(), 0);
+    assert_eq!(
+        fs.list_xattrs("/block").unwrap(),
+        [b"user.small".to_vec(), b"user.big".to_vec()]
+    );
+    assert_eq!(
+        fs.get_xattr("/block", "user.small").unwrap().unwrap(),
+        b"in_inode"
+    );
+    assert_eq!(
+        fs.get_xattr("/block", "user.big").unwrap().unwrap(),
+        "0123456789".repeat(80).as_bytes()
+    );
+}
+
+#[test]
+fn test_xattrs_none() {
+    let fs = load_xattr();
+
+    assert!(fs.list_xattrs("/no_xattrs").unwrap().is_empty());
+    assert!(fs.get_xattr("/no_xattrs", "user.a").unwrap().is_none());
+
+    assert!(matches!(
+        fs.list_xattrs("/does_not_exist").unwrap_err(),
+        Ext4Error::NotFound
+    ));
+}
+
+/// Test that ACLs are converted from the on-disk format to the
+/// `getxattr` format.
+#[test]
+fn test_xattr_posix_acl() {
+    let fs = load_xattr();
+
+    // Should match `fill_xattr` in xtask.
+    let mut expected = 2u32.to_le_bytes().to_vec();
+    for (tag, perm, id) in [
+        (0x01u16, 6u16, u32::MAX),
+        (0x02, 4, 1000),
+        (0x04, 4, u32::MAX),
+        (0x10, 4, u32::MAX),
+        (0x20, 0, u32::MAX),
+    ] {
+        expected.extend(tag.to_le_bytes());
+        expected.extend(perm.to_le_bytes());
+        expected.extend(id.to_le_bytes());
+    }
+
+    assert_eq!(
+        fs.list_xattrs("/acl").unwrap(),
+        [b"system.posix_acl_access".to_vec()]
+    );
+    assert_eq!(
+        fs.get_xattr("/acl", "system.posix_acl_access")
+            .unwrap()
+            .unwrap(),
+        expected
+    );
+}
+
+#[test]
+fn test_file_and_dir_entry_xattrs() {
+    let fs = load_xattr();
+
+    let file = fs.open("/block").unwrap();
+    assert_eq!(
+        file.list_xattrs().unwrap(),
+        fs.list_xattrs("/block").unwrap()
+    );
+    assert_eq!(file.get_xattr("user.small").unwrap().unwrap(), b"in_inode");
+
+    let entry = fs
+        .read_dir("/")
+        .unwrap()
+        .map(|entry| entry.unwrap())
+        .find(|entry| entry.file_name() == "small")
+        .unwrap();
+    assert_eq!(
+        entry.list_xattrs().unwrap(),
+        fs.list_xattrs("/small").unwrap()
+    );
+    assert_eq!(entry.get_xattr("user.a").unwrap().unwrap(), b"abc");
+}
+
+/// Test that a corrupt xattr block is detected via its checksum.
+#[test]
+fn test_xattr_block_checksum() {
+    let mut data = read_compressed_filesystem("test_disk_xattr.bin.zst");
+    let fs = Ext4::load(Box::new(data.clone())).unwrap();
+    let block =
+        usize::try_from(fs.metadata("/block").unwrap().file_acl()).unwrap();
+
+    // Modify the value of `user.big`, which is at the end of the block.
+    data[block * 1024 + 1023] ^= 0xff;
+
+    let fs = Ext4::load(Box::new(data)).unwrap();
+    let err = fs.get_xattr("/block", "user.big").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "corrupt filesystem: extended attribute block of inode 14 has an invalid checksum"
+    );
+
+    // Attributes in the inode are still read first.
+    assert_eq!(
+        fs.get_xattr("/block", "user.small").unwrap().unwrap(),
+        b"in_inode"
+    );
+}
+
+/// Test a filesystem with the `ea_inode` feature, where large values
+/// are stored in separate inodes.
+#[test]
+fn test_xattr_ea_inode() {
+    let fs = load_xattr();
+
+    // File data is unaffected.
+    assert_eq!(fs.read("/ea_inode").unwrap(), b"hello\n");
+
+    assert_eq!(
+        fs.list_xattrs("/ea_inode").unwrap(),
+        [b"user.small".to_vec(), b"user.large".to_vec()]
+    );
+    assert_eq!(
+        fs.get_xattr("/ea_inode", "user.small").unwrap().unwrap(),
+        b"in_inode"
+    );
+    assert_eq!(
+        fs.get_xattr("/ea_inode", "user.large").unwrap().unwrap(),
+        "ea_inode".repeat(128).as_bytes()
+    );
+}
+
+/// Test that a modified value in an EA inode is detected via its hash.
+#[test]
+fn test_xattr_ea_inode_hash() {
+    let mut data = read_compressed_filesystem("test_disk_xattr.bin.zst");
+
+    // Find the EA inode's data block and modify it.
+    let value = "ea_inode".repeat(128);
+    let pos = data
+        .windows(value.len())
+        .position(|w| w == value.as_bytes())
+        .unwrap();
+    data[pos] ^= 0xff;
+
+    let fs = Ext4::load(Box::new(data)).unwrap();
+    let err = fs.get_xattr("/ea_inode", "user.large").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "corrupt filesystem: extended attribute in inode 16 has an invalid hash for EA inode 17"
+    );
+}
+
+/// Test reading the SELinux labels in the main test disk. The
+/// encryption context of the encrypted directory is also stored as an
+/// xattr, but it is not exposed.
+#[test]
+fn test_xattr_selinux() {
+    let fs = load_test_disk1();
+
+    assert_eq!(
+        fs.get_xattr("/small_file", "security.selinux")
+            .unwrap()
+            .unwrap(),
+        b"unconfined_u:object_r:unlabeled_t:s0\0"
+    );
+    assert_eq!(
+        fs.list_xattrs("/encrypted_dir").unwrap(),
+        [b"security.selinux".to_vec()]
+    );
+}
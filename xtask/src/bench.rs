@@ -27,6 +27,7 @@ pub fn run_bench(path: &Path, iters: u32) -> Result<()> {
         let ext4 = Ext4::load_from_path(path).unwrap();
         let digest = walk::walk(&ext4).unwrap();
         println!("filesystem hash: {digest}");
+        println!("{:#?}", ext4.stats());
     });
 
     bench_impl(iters, || {
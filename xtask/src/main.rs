@@ -85,6 +85,8 @@ struct DiskParams {
     hash_alg: Option<HashAlg>,
     // Inode size in bytes. If `None`, the `mkfs` default is used.
     inode_size: Option<u32>,
+    // Additional features to enable (e.g. "inline_data").
+    features: &'static [&'static str],
 }
 
 impl DiskParams {
@@ -119,6 +121,10 @@ impl DiskParams {
             cmd.args(["-O", "encrypt"]);
         }
 
+        for feature in self.features {
+            cmd.args(["-O", feature]);
+        }
+
         // Set block size.
         cmd.arg("-b");
         cmd.arg(self.block_size.to_string());
@@ -300,6 +306,213 @@ impl DiskParams {
         Ok(())
     }
 
+    /// Put files with extended attributes on the disk.
+    ///
+    /// This uses debugfs rather than mounting the filesystem, so that
+    /// the placement of each attribute (in the inode or in a separate
+    /// block) is deterministic.
+    fn fill_xattr(&self) -> Result<()> {
+        // Allow large values to be stored in separate inodes.
+        run_cmd(
+            Command::new("tune2fs")
+                .args(["-O", "ea_inode"])
+                .arg(&self.path),
+        )?;
+
+        let tmp_dir = TempDir::new()?;
+        let tmp_path = |name| tmp_dir.path().join(name).display().to_string();
+
+        fs::write(tmp_path("content"), "hello\n")?;
+
+        // This value is too large to fit in the inode, so it will be
+        // stored in an xattr block.
+        fs::write(tmp_path("big_value"), "0123456789".repeat(80))?;
+
+        // POSIX ACL in the xattr format (version 2). Debugfs converts
+        // this to the ext4 on-disk format (version 1).
+        let mut acl = 2u32.to_le_bytes().to_vec();
+        for (tag, perm, id) in [
+            // User owner: rw-
+            (0x01u16, 6u16, u32::MAX),
+            // User 1000: r--
+            (0x02, 4, 1000),
+            // Group owner: r--
+            (0x04, 4, u32::MAX),
+            // Mask: r--
+            (0x10, 4, u32::MAX),
+            // Other: ---
+            (0x20, 0, u32::MAX),
+        ] {
+            acl.extend(tag.to_le_bytes());
+            acl.extend(perm.to_le_bytes());
+            acl.extend(id.to_le_bytes());
+        }
+        fs::write(tmp_path("acl"), acl)?;
+
+        // This value is large enough to be stored in an EA inode.
+        fs::write(tmp_path("ea_inode_value"), "ea_inode".repeat(128))?;
+
+        let content = tmp_path("content");
+        let script = [
+            format!("write {content} no_xattrs"),
+            // Small attributes, stored in the inode.
+            format!("write {content} small"),
+            "ea_set small user.a abc".to_owned(),
+            "ea_set small trusted.b def".to_owned(),
+            // One attribute in the inode, one in an xattr block.
+            format!("write {content} block"),
+            "ea_set block user.small in_inode".to_owned(),
+            format!("ea_set -f {} block user.big", tmp_path("big_value")),
+            // ACL.
+            format!("write {content} acl"),
+            format!(
+                "ea_set -f {} acl system.posix_acl_access",
+                tmp_path("acl")
+            ),
+            // One attribute in the inode, one in an EA inode.
+            format!("write {content} ea_inode"),
+            "ea_set ea_inode user.small in_inode".to_owned(),
+            format!(
+                "ea_set -f {} ea_inode user.large",
+                tmp_path("ea_inode_value")
+            ),
+        ]
+        .join("\n");
+        self.run_debugfs_script(&script)
+    }
+
+    /// Put files with inline data on the disk.
+    ///
+    /// This uses debugfs rather than mounting the filesystem, so that
+    /// small files and directories are stored inline. The kernel only
+    /// stores data inline under some conditions, e.g. a file written
+    /// in multiple parts may not be stored inline.
+    fn fill_inline_data(&self) -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let tmp_path = |name| tmp_dir.path().join(name).display().to_string();
+
+        fs::write(tmp_path("empty"), "")?;
+        fs::write(tmp_path("small"), "hello, inline!")?;
+        // Exactly fills `i_block`.
+        fs::write(tmp_path("exact60"), "x".repeat(60))?;
+        // The first 60 bytes are stored in `i_block`, the rest in the
+        // `system.data` xattr.
+        let medium: String = ('a'..='z').cycle().take(100).collect();
+        fs::write(tmp_path("medium"), medium)?;
+        // Too big to store inline.
+        fs::write(tmp_path("large"), "L".repeat(5000))?;
+
+        let script = [
+            format!("write {} empty", tmp_path("empty")),
+            format!("write {} small", tmp_path("small")),
+            format!("write {} exact60", tmp_path("exact60")),
+            format!("write {} medium", tmp_path("medium")),
+            format!("write {} large", tmp_path("large")),
+            // Directory with entries stored inline.
+            "mkdir dir".to_owned(),
+            format!("write {} dir/a", tmp_path("small")),
+            format!("write {} dir/b", tmp_path("small")),
+            "mkdir dir/c".to_owned(),
+            // Symlink target too long to be a fast symlink, stored as
+            // inline data.
+            format!("symlink sym_80 /{}", "t".repeat(79)),
+        ]
+        .join("\n");
+        self.run_debugfs_script(&script)
+    }
+
+    /// Put files with unwritten (preallocated) extents on the disk.
+    ///
+    /// The preallocated blocks are reused from a deleted file, so they
+    /// contain stale data that must not be returned when reading.
+    fn fill_unwritten(&self) -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let tmp_path = |name| tmp_dir.path().join(name).display().to_string();
+
+        fs::write(tmp_path("stale"), vec![0xee; 16 * 1024])?;
+        fs::write(tmp_path("head"), vec![0xa1; 2 * 1024])?;
+
+        let script = [
+            format!("write {} stale", tmp_path("stale")),
+            "rm stale".to_owned(),
+            // File that is entirely unwritten.
+            "write /dev/null prealloc".to_owned(),
+            "fallocate prealloc 0 7".to_owned(),
+            "sif prealloc size 8192".to_owned(),
+            // File with two initialized blocks, followed by four
+            // unwritten blocks.
+            format!("write {} mixed", tmp_path("head")),
+            "fallocate mixed 2 5".to_owned(),
+            "sif mixed size 6144".to_owned(),
+        ]
+        .join("\n");
+        self.run_debugfs_script(&script)
+    }
+
+    /// Put a file on a `meta_bg` disk whose inode is in block group
+    /// 16. With 1KiB blocks and 64-byte descriptors, each meta group
+    /// holds 16 block groups, so the descriptor for that group is in
+    /// the second meta group.
+    ///
+    /// Inodes are normally allocated near their parent directory, so
+    /// the file is created in group 0 and its inode is then moved.
+    fn fill_meta_bg(&self) -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let tmp_path = |name| tmp_dir.path().join(name).display().to_string();
+
+        fs::write(tmp_path("far"), "hello, meta_bg!")?;
+
+        // First inode of group 16. The disk has 2048 inodes per group.
+        let far_inode = 16 * 2048 + 1;
+        let script = [
+            format!("write {} tmp", tmp_path("far")),
+            format!("copy_inode tmp <{far_inode}>"),
+            // Mark the group's inode table as initialized.
+            "set_bg 16 flags 0".to_owned(),
+            "set_bg 16 itable_unused 0".to_owned(),
+            "set_bg 16 checksum calc".to_owned(),
+            format!("seti <{far_inode}>"),
+            format!("link <{far_inode}> far"),
+            // Free the original inode, but not its blocks, which now
+            // belong to the moved inode.
+            "unlink tmp".to_owned(),
+            "clri <12>".to_owned(),
+            "freei <12>".to_owned(),
+        ]
+        .join("\n");
+        self.run_debugfs_script(&script)?;
+
+        // debugfs doesn't update the free inode counts of the block
+        // groups, so let e2fsck fix them. An exit code of 1 means that
+        // errors were fixed.
+        let status = Command::new("e2fsck")
+            .args(["-f", "-y"])
+            .arg(&self.path)
+            .status()?;
+        if !matches!(status.code(), Some(0 | 1)) {
+            bail!("e2fsck failed: {status:?}");
+        }
+        Ok(())
+    }
+
+    /// Run [debugfs] in write mode with a script containing one command
+    /// per line.
+    ///
+    /// [debugfs]: https://www.man7.org/linux/man-pages/man8/debugfs.8.html
+    fn run_debugfs_script(&self, script: &str) -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let script_path = tmp_dir.path().join("script");
+        fs::write(&script_path, script)?;
+
+        run_cmd(
+            Command::new("debugfs")
+                .arg("-w")
+                .arg("-f")
+                .arg(&script_path)
+                .arg(&self.path),
+        )
+    }
+
     /// Create a filesystem that was not unmounted cleanly. The root
     /// directory contains a number of subdirectories that are only in
     /// the journal.
@@ -337,6 +550,53 @@ impl DiskParams {
         bail!("failed to create filesystem");
     }
 
+    /// Create a filesystem with a single transaction in the journal
+    /// that has not yet been applied to the main filesystem.
+    ///
+    /// This uses debugfs to write the journal, so no mounting is
+    /// needed. The `journal_open` command controls the journal's
+    /// checksum format.
+    ///
+    /// The filesystem contains two single-block files, `a` and `b`,
+    /// each filled with `o`. The journal transaction replaces both
+    /// blocks with data filled with `n`, but the block for `b` is
+    /// also revoked in the same transaction. After replaying the
+    /// journal, `a` should contain `n` and `b` should contain `o`.
+    fn create_with_debugfs_journal(&self, journal_open: &str) -> Result<()> {
+        self.create()?;
+
+        let tmp_dir = TempDir::new()?;
+        let tmp_path = |name| tmp_dir.path().join(name).display().to_string();
+
+        fs::write(tmp_path("old"), vec![b'o'; 1024])?;
+        fs::write(tmp_path("new"), vec![b'n'; 2048])?;
+
+        let script = [
+            format!("write {} a", tmp_path("old")),
+            format!("write {} b", tmp_path("old")),
+        ]
+        .join("\n");
+        self.run_debugfs_script(&script)?;
+
+        let get_block = |name| -> Result<u32> {
+            let output = self.run_debugfs(&format!("bmap {name} 0"))?;
+            Ok(str::from_utf8(&output)?.trim().parse()?)
+        };
+        let block_a = get_block("a")?;
+        let block_b = get_block("b")?;
+
+        let script = [
+            journal_open.to_owned(),
+            format!(
+                "jw -b {block_a},{block_b} -r {block_b} {}",
+                tmp_path("new")
+            ),
+            "jc".to_owned(),
+        ]
+        .join("\n");
+        self.run_debugfs_script(&script)
+    }
+
     /// Modify the filesystem so that some data is written to the
     /// journal, but not yet flushed to the main filesystem.
     ///
@@ -532,6 +792,7 @@ fn create_test_data() -> Result<()> {
             block_size: 1024,
             hash_alg: None,
             inode_size: None,
+            features: &[],
         };
         disk.create()?;
         let data = fs::read(&path)?;
@@ -547,6 +808,7 @@ fn create_test_data() -> Result<()> {
         block_size: 1024,
         hash_alg: None,
         inode_size: None,
+        features: &[],
     };
     disk.create()?;
     disk.fill()?;
@@ -563,6 +825,7 @@ fn create_test_data() -> Result<()> {
         block_size: 1024,
         hash_alg: None,
         inode_size: None,
+        features: &[],
     };
     disk.create()?;
     disk.fill_ext2()?;
@@ -576,10 +839,33 @@ fn create_test_data() -> Result<()> {
         block_size: 4096,
         hash_alg: None,
         inode_size: None,
+        features: &[],
     };
     disk.create_with_journal()?;
     zstd_compress(&disk.path)?;
 
+    // Filesystems with journals that use the older formats: 32-bit
+    // without checksums, 64-bit without checksums, and 32-bit with v2
+    // checksums.
+    for (name, features, journal_open) in [
+        ("classic", &["^metadata_csum", "^64bit"][..], "jo"),
+        ("64bit", &["^metadata_csum", "64bit"][..], "jo"),
+        ("csum_v2", &["metadata_csum", "^64bit"][..], "jo -c -v 2"),
+    ] {
+        let path = dir.join(format!("test_disk_journal_{name}.bin"));
+        let disk = DiskParams {
+            path: path.to_owned(),
+            size_in_kilobytes: 1024 * 4,
+            fs_type: FsType::Ext4,
+            block_size: 1024,
+            hash_alg: None,
+            inode_size: None,
+            features,
+        };
+        disk.create_with_debugfs_journal(journal_open)?;
+        zstd_compress(&disk.path)?;
+    }
+
     // Ext3 filesystem with the smallest-possible inode size (128
     // bytes), and using TEA instead of half-MD4 for directory entry
     // hashes.
@@ -591,12 +877,74 @@ fn create_test_data() -> Result<()> {
         block_size: 1024,
         hash_alg: Some(HashAlg::Tea),
         inode_size: Some(128),
+        features: &[],
     };
     disk.create()?;
     disk.fill_ext3()?;
     disk.check_dir_htree_depth("/medium_dir", 0)?;
     zstd_compress(&disk.path)?;
 
+    // Small filesystem with extended attributes.
+    let path = dir.join("test_disk_xattr.bin");
+    let disk = DiskParams {
+        path: path.to_owned(),
+        size_in_kilobytes: 1024,
+        fs_type: FsType::Ext4,
+        block_size: 1024,
+        hash_alg: None,
+        inode_size: None,
+        features: &[],
+    };
+    disk.create()?;
+    disk.fill_xattr()?;
+    zstd_compress(&disk.path)?;
+
+    // Small filesystem with inline data.
+    let path = dir.join("test_disk_inline_data.bin");
+    let disk = DiskParams {
+        path: path.to_owned(),
+        size_in_kilobytes: 1024,
+        fs_type: FsType::Ext4,
+        block_size: 1024,
+        hash_alg: None,
+        inode_size: None,
+        features: &["inline_data"],
+    };
+    disk.create()?;
+    disk.fill_inline_data()?;
+    zstd_compress(&disk.path)?;
+
+    // Small filesystem with unwritten extents.
+    let path = dir.join("test_disk_unwritten.bin");
+    let disk = DiskParams {
+        path: path.to_owned(),
+        size_in_kilobytes: 1024,
+        fs_type: FsType::Ext4,
+        block_size: 1024,
+        hash_alg: None,
+        inode_size: None,
+        features: &[],
+    };
+    disk.create()?;
+    disk.fill_unwritten()?;
+    zstd_compress(&disk.path)?;
+
+    // Filesystem with the `meta_bg` descriptor layout, large enough to
+    // have two meta groups.
+    let path = dir.join("test_disk_meta_bg.bin");
+    let disk = DiskParams {
+        path: path.to_owned(),
+        size_in_kilobytes: 1024 * 160,
+        fs_type: FsType::Ext4,
+        block_size: 1024,
+        hash_alg: None,
+        inode_size: None,
+        features: &["meta_bg", "^resize_inode"],
+    };
+    disk.create()?;
+    disk.fill_meta_bg()?;
+    zstd_compress(&disk.path)?;
+
     Ok(())
 }
 